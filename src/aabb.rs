@@ -0,0 +1,166 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+
+use std::ops::*;
+
+/// An axis-aligned box, between a minimum and maximum corner.
+///
+/// Used as a bounding box, to skip rays which miss everything inside it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb<T> {
+    /// The corner with the lowest coordinates.
+    pub min: Vec3<T>,
+
+    /// The corner with the highest coordinates.
+    pub max: Vec3<T>,
+}
+
+impl<T> Aabb<T> {
+    /// Default constructor.
+    pub fn new(min: Vec3<T>, max: Vec3<T>) -> Self {
+        Aabb { min, max }
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Aabb<T> {
+    /// The smallest box containing every point.
+    ///
+    /// Returns None if there are no points.
+    ///
+    /// ```
+    /// # use rusttracing::aabb::*;
+    /// # use rusttracing::vector::*;
+    /// let bounds = Aabb::from_points([Vec3::new(1.0, -2.0, 0.0), Vec3::new(-1.0, 3.0, 0.5)]).unwrap();
+    ///
+    /// assert_eq!(bounds, Aabb::new(Vec3::new(-1.0, -2.0, 0.0), Vec3::new(1.0, 3.0, 0.5)));
+    /// ```
+    pub fn from_points(points: impl IntoIterator<Item = Vec3<T>>) -> Option<Self> {
+        let mut points = points.into_iter();
+
+        let first = points.next()?;
+        let mut bounds = Aabb::new(first, first);
+
+        for point in points {
+            bounds = bounds.expand(point);
+        }
+
+        Some(bounds)
+    }
+
+    /// The smallest box containing this box, and a point.
+    pub fn expand(self, point: Vec3<T>) -> Self {
+        let lower = |a: T, b: T| if b < a { b } else { a };
+        let higher = |a: T, b: T| if b > a { b } else { a };
+
+        Aabb::new(
+            Vec3::new(lower(self.min.x, point.x), lower(self.min.y, point.y), lower(self.min.z, point.z)),
+            Vec3::new(higher(self.max.x, point.x), higher(self.max.y, point.y), higher(self.max.z, point.z)),
+        )
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(self, other: Aabb<T>) -> Self {
+        self.expand(other.min).expand(other.max)
+    }
+
+    /// The point in the middle of the box.
+    pub fn center(&self) -> Vec3<T> {
+        (self.min + self.max) * T::from(0.5)
+    }
+
+    /// The length of the box along each axis.
+    pub fn size(&self) -> Vec3<T> {
+        self.max - self.min
+    }
+
+    /// Whether a point lies within the box, or on its surface.
+    pub fn contains(&self, point: Vec3<T>) -> bool {
+        point.x >= self.min.x && point.y >= self.min.y && point.z >= self.min.z
+            && point.x <= self.max.x && point.y <= self.max.y && point.z <= self.max.z
+    }
+
+    /// The distances along the ray at which it enters and leaves the box, by the slab method.
+    ///
+    /// Covers the whole line of the ray, so distances may be negative.
+    ///
+    /// Returns None if the line misses the box.
+    pub fn slab(&self, ray: &Ray<T>) -> Option<(T, T)> {
+        let (origin, direction) = (ray.origin.to_f64(), ray.direction.to_f64());
+        let (min, max) = (self.min.to_f64(), self.max.to_f64());
+
+        let mut near = f64::NEG_INFINITY;
+        let mut far = f64::INFINITY;
+
+        for (o, d, low, high) in [(origin.x, direction.x, min.x, max.x), (origin.y, direction.y, min.y, max.y), (origin.z, direction.z, min.z, max.z)] {
+            if d == 0.0 {
+                if o < low || o > high {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (low - o) / d;
+            let t2 = (high - o) / d;
+
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+
+        if near > far {
+            return None;
+        }
+
+        Some((near.into(), far.into()))
+    }
+
+    /// Whether the ray passes through the box, in front of its origin.
+    pub fn hits(&self, ray: &Ray<T>) -> bool {
+        self.slab(ray).is_some_and(|(_near, far)| far >= 0.0.into())
+    }
+
+    /// The outward unit normal of the face of the box nearest to a point.
+    pub fn normal_at(&self, point: Vec3<T>) -> Vec3<f64> {
+        let (point, min, max) = (point.to_f64(), self.min.to_f64(), self.max.to_f64());
+
+        let faces = [
+            (point.x - min.x, Vec3::new(-1.0, 0.0, 0.0)),
+            (max.x - point.x, Vec3::new(1.0, 0.0, 0.0)),
+            (point.y - min.y, Vec3::new(0.0, -1.0, 0.0)),
+            (max.y - point.y, Vec3::new(0.0, 1.0, 0.0)),
+            (point.z - min.z, Vec3::new(0.0, 0.0, -1.0)),
+            (max.z - point.z, Vec3::new(0.0, 0.0, 1.0)),
+        ];
+
+        faces.into_iter().min_by(|a, b| a.0.abs().total_cmp(&b.0.abs())).unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slab() {
+        let bounds = Aabb::new(Vec3::new(-1.0, -1.0, 4.0), Vec3::new(1.0, 1.0, 6.0));
+
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(bounds.slab(&ray), Some((4.0, 6.0)));
+        assert!(bounds.hits(&ray));
+
+        // Behind the ray.
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 10.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(bounds.slab(&ray), Some((-6.0, -4.0)));
+        assert!(!bounds.hits(&ray));
+
+        // Parallel to a face, outside of it.
+        let ray = Ray::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(bounds.slab(&ray), None);
+
+        // Diagonally, past a corner.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(bounds.slab(&ray), None);
+
+        assert_eq!(bounds.normal_at(Vec3::new(0.2, 1.0, 5.5)), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(bounds.normal_at(Vec3::new(-0.2, 0.3, 4.0)), Vec3::new(0.0, 0.0, -1.0));
+    }
+}
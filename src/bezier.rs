@@ -0,0 +1,294 @@
+use crate::vector::Vec3;
+use crate::color::Color;
+use crate::tri::Tri;
+
+use std::ops::*;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug)]
+/// Invalid or unsupported Bézier patch data.
+pub struct ParseError;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid or unsupported patch data, please use bicubic patches in the bpt format")
+    }
+}
+
+/// The weights of the 4 control points of a cubic Bézier curve at t.
+fn bernstein(t: f64) -> [f64; 4] {
+    let s = 1.0 - t;
+
+    [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t]
+}
+
+/// The weights of the 4 control points in the derivative of a cubic Bézier curve at t.
+fn bernstein_derivative(t: f64) -> [f64; 4] {
+    let s = 1.0 - t;
+
+    [-3.0 * s * s, 3.0 * s * s - 6.0 * t * s, 6.0 * t * s - 3.0 * t * t, 3.0 * t * t]
+}
+
+/// The sum of points with weights.
+fn weighted(points: [Vec3<f64>; 4], weights: [f64; 4]) -> Vec3<f64> {
+    points.iter().zip(weights).fold(Vec3::new(0.0, 0.0, 0.0), |sum, (&point, weight)| sum + point * weight)
+}
+
+/// The number of straight segments needed to keep within a distance of a cubic Bézier curve,
+/// from the bound on the chord error of its second differences.
+fn segments(points: [Vec3<f64>; 4], tolerance: f64) -> usize {
+    let bend = (points[0] - points[1] * 2.0 + points[2]).length().max((points[1] - points[2] * 2.0 + points[3]).length());
+
+    ((0.75 * bend / tolerance).sqrt().ceil() as usize).max(1)
+}
+
+/// A point on a cubic Bézier curve split into straight segments, at t along it.
+fn along_segments(points: [Vec3<f64>; 4], segments: usize, t: f64) -> Vec3<f64> {
+    let position = t * segments as f64;
+    let start = (position.floor() as usize).min(segments - 1);
+    let offset = position - start as f64;
+
+    let a = weighted(points, bernstein(start as f64 / segments as f64));
+    let b = weighted(points, bernstein((start + 1) as f64 / segments as f64));
+
+    a * (1.0 - offset) + b * offset
+}
+
+/// Bicubic Bézier patch.
+///
+/// A smooth surface, shaped by a 4 by 4 grid of control points, passing through the 4 at the corners.
+///
+/// Rendered by tessellating into tris, such as with [crate::object::Object::from_bezier].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BezierPatch<T> {
+    /// The control points, in rows along v, of columns along u.
+    pub points: [[Vec3<T>; 4]; 4],
+}
+
+impl<T> BezierPatch<T> {
+    /// Default constructor.
+    pub fn new(points: [[Vec3<T>; 4]; 4]) -> Self {
+        BezierPatch { points }
+    }
+}
+
+impl<T: Copy + Into<f64>> BezierPatch<T> {
+    /// The control points as f64.
+    fn grid(&self) -> [[Vec3<f64>; 4]; 4] {
+        self.points.map(|row| row.map(|point| point.to_f64()))
+    }
+
+    /// The position on the patch at uv coordinates.
+    pub fn at(&self, u: f64, v: f64) -> Vec3<f64> {
+        let rows = self.grid().map(|row| weighted(row, bernstein(u)));
+
+        weighted(rows, bernstein(v))
+    }
+
+    /// The unit normal of the patch at uv coordinates, facing along the cross product of the u and v tangents.
+    ///
+    /// Where the patch is pinched to a point, such as at the top of a lid, the normal is taken from just inside it.
+    pub fn normal_at(&self, u: f64, v: f64) -> Vec3<f64> {
+        let grid = self.grid();
+
+        let tangents = |u: f64, v: f64| {
+            let du = weighted(grid.map(|row| weighted(row, bernstein_derivative(u))), bernstein(v));
+            let dv = weighted(grid.map(|row| weighted(row, bernstein(u))), bernstein_derivative(v));
+
+            du.cross(&dv)
+        };
+
+        let mut normal = tangents(u, v);
+        let mut inset = 1e-4;
+
+        while normal.length() < 1e-12 && inset < 0.5 {
+            normal = tangents(u + (0.5 - u) * inset, v + (0.5 - v) * inset);
+            inset *= 10.0;
+        }
+
+        normal.unit()
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> BezierPatch<T> {
+
+    /// The number of segments along u and v needed to keep within a distance of the patch.
+    pub fn segments(&self, tolerance: f64) -> (usize, usize) {
+        let grid = self.grid();
+        let columns = [0, 1, 2, 3].map(|i| grid.map(|row| row[i]));
+
+        (
+            grid.iter().map(|&row| segments(row, tolerance)).max().unwrap(),
+            columns.iter().map(|&column| segments(column, tolerance)).max().unwrap(),
+        )
+    }
+
+    /// Splits the patch into tris, with the normals of the patch at each point, for smooth shading.
+    ///
+    /// More tris are used along directions where the patch bends more, keeping within tolerance of the surface.
+    /// The points along each edge depend only on that edge, so patches sharing an edge meet without cracks.
+    pub fn tessellate(&self, tolerance: f64, color: Color, roughness: f64) -> Vec<Tri<T>> where f64: From<T> {
+        let grid = self.grid();
+        let (nu, nv) = self.segments(tolerance);
+
+        let bottom = grid[0];
+        let top = grid[3];
+        let left = grid.map(|row| row[0]);
+        let right = grid.map(|row| row[3]);
+
+        let point = |i: usize, j: usize| {
+            let (u, v) = (i as f64 / nu as f64, j as f64 / nv as f64);
+
+            let position = if j == 0 {
+                along_segments(bottom, segments(bottom, tolerance), u)
+            } else if j == nv {
+                along_segments(top, segments(top, tolerance), u)
+            } else if i == 0 {
+                along_segments(left, segments(left, tolerance), v)
+            } else if i == nu {
+                along_segments(right, segments(right, tolerance), v)
+            } else {
+                self.at(u, v)
+            };
+
+            (position, self.normal_at(u, v))
+        };
+
+        let points: Vec<Vec<(Vec3<f64>, Vec3<f64>)>> = (0..=nu).map(|i| (0..=nv).map(|j| point(i, j)).collect()).collect();
+
+        let mut tris = vec![];
+
+        for i in 0..nu {
+            for j in 0..nv {
+                let (p00, p10, p01, p11) = (points[i][j], points[i + 1][j], points[i][j + 1], points[i + 1][j + 1]);
+
+                for [a, b, c] in [[p00, p10, p11], [p00, p11, p01]] {
+                    // Skips tris with no area, where the patch is pinched to a point.
+                    if (b.0 - a.0).cross(&(c.0 - a.0)).length() < 1e-12 {
+                        continue;
+                    }
+
+                    tris.push(
+                        Tri::new(Vec3::from_f64(a.0), Vec3::from_f64(b.0), Vec3::from_f64(c.0), color, roughness)
+                            .with_normals(Vec3::from_f64(a.1), Vec3::from_f64(b.1), Vec3::from_f64(c.1))
+                    );
+                }
+            }
+        }
+
+        tris
+    }
+}
+
+impl<T: From<f64>> BezierPatch<T> {
+    /// Reads patches in the bpt text format, as used for the Utah teapot.
+    ///
+    /// The number of patches, then for each, its degree in u and v, which must be 3 and 3,
+    /// followed by its 16 control points of 3 numbers each, in rows along v.
+    ///
+    /// # Errors
+    /// Will error on patches which are not bicubic, or on missing or invalid numbers.
+    ///
+    /// ```
+    /// # use rusttracing::bezier::*;
+    /// let text = "1\n3 3\n".to_owned() + &"0.0 0.0 0.0\n".repeat(16);
+    /// let patches = BezierPatch::<f64>::from_bpt(&text).unwrap();
+    ///
+    /// assert_eq!(patches.len(), 1);
+    /// assert!(BezierPatch::<f64>::from_bpt("1\n2 2\n").is_err());
+    /// ```
+    pub fn from_bpt(text: &str) -> Result<Vec<Self>, ParseError> {
+        let mut tokens = text.split_whitespace();
+        let mut next = || -> Result<f64, ParseError> { tokens.next().ok_or(ParseError)?.parse().map_err(|_| ParseError) };
+
+        // Grows with the patches read, rather than trusting the count.
+        let count = next()? as usize;
+        let mut patches = vec![];
+
+        for _patch in 0..count {
+            if next()? != 3.0 || next()? != 3.0 {
+                return Err(ParseError);
+            }
+
+            let mut points = [[Vec3::new(0.0, 0.0, 0.0); 4]; 4];
+            for row in points.iter_mut() {
+                for point in row.iter_mut() {
+                    *point = Vec3::new(next()?, next()?, next()?);
+                }
+            }
+
+            patches.push(BezierPatch::new(points.map(|row| row.map(Vec3::from_f64))));
+        }
+
+        Ok(patches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate() {
+        // A bulge over the unit square, rising to 0.75 in the middle.
+        let patch = BezierPatch::new([0.0, 1.0, 2.0, 3.0].map(|v| [0.0, 1.0, 2.0, 3.0].map(|u| {
+            let inner = (u == 1.0 || u == 2.0) && (v == 1.0 || v == 2.0);
+
+            Vec3::new(u / 3.0, v / 3.0, if inner { 1.0 } else { 0.0 })
+        })));
+
+        assert!((patch.at(0.0, 0.0) - Vec3::new(0.0, 0.0, 0.0)).length() < 1e-12);
+        assert!((patch.at(1.0, 1.0) - Vec3::new(1.0, 1.0, 0.0)).length() < 1e-12);
+        assert!((patch.at(0.5, 0.5) - Vec3::new(0.5, 0.5, 0.5625)).length() < 1e-12);
+
+        assert!((patch.normal_at(0.5, 0.5) - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-12);
+        assert!(patch.normal_at(0.0, 0.5).x < 0.0);
+    }
+
+    #[test]
+    fn tessellate() {
+        // A bulge over the unit square, rising to 0.75 in the middle.
+        let patch = BezierPatch::new([0.0, 1.0, 2.0, 3.0].map(|v| [0.0, 1.0, 2.0, 3.0].map(|u| {
+            let inner = (u == 1.0 || u == 2.0) && (v == 1.0 || v == 2.0);
+
+            Vec3::new(u / 3.0, v / 3.0, if inner { 1.0 } else { 0.0 })
+        })));
+
+        // Flat edges need a single segment, while the middle bends.
+        let (nu, nv) = patch.segments(0.01);
+        assert!(nu > 1 && nu == nv);
+
+        let tris = patch.tessellate(0.01, Color::new(1.0, 1.0, 1.0), 1.0);
+        assert_eq!(tris.len(), nu * nv * 2);
+
+        // Every point lies close to the surface.
+        for tri in &tris {
+            for point in [tri.bounds.x, tri.bounds.y, tri.bounds.z] {
+                let exact = patch.at(point.x, point.y);
+
+                assert!((point.z - exact.z).abs() < 0.05);
+
+                // Each edge is straight, so the points along it stay on the line between the corners.
+                if point.x == 0.0 || point.x == 1.0 || point.y == 0.0 || point.y == 1.0 {
+                    assert_eq!(point.z, 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn from_bpt() {
+        let patch = "3 3\n".to_owned() + &"0.0 1.0 2.0\n".repeat(16);
+
+        assert_eq!(BezierPatch::<f64>::from_bpt(&("2\n".to_owned() + &patch + &patch)).unwrap().len(), 2);
+
+        // Counts beyond the patches given, including ones too large to allocate, are rejected.
+        assert!(BezierPatch::<f64>::from_bpt(&("3\n".to_owned() + &patch + &patch)).is_err());
+        assert!(BezierPatch::<f64>::from_bpt(&("1e30\n".to_owned() + &patch)).is_err());
+        assert!(BezierPatch::<f64>::from_bpt(&("18446744073709551615\n".to_owned() + &patch)).is_err());
+
+        // Missing or invalid numbers.
+        assert!(BezierPatch::<f64>::from_bpt("").is_err());
+        assert!(BezierPatch::<f64>::from_bpt(&("1\n".to_owned() + &patch.replace("2.0", "x"))).is_err());
+        assert!(BezierPatch::<f64>::from_bpt(&("1\n".to_owned() + &patch[..patch.len() - 5])).is_err());
+    }
+}
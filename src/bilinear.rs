@@ -0,0 +1,211 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::polynomial::quadratic;
+
+use std::ops::*;
+
+/// Bilinear patch.
+///
+/// The surface between four corners, which need not lie in a plane,
+/// blended linearly along u and then along v.
+///
+/// Implements the Raytrace and SampleArea traits, so can be used as an [crate::light::AreaLight].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BilinearPatch<T> {
+    /// The corners at (0, 0), (1, 0), (0, 1) and (1, 1) in uv coordinates.
+    pub corners: [Vec3<T>; 4],
+
+    /// Colour of the patch.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T> BilinearPatch<T> {
+    /// Default constructor, from the corners at (0, 0), (1, 0), (0, 1) and (1, 1) in uv coordinates.
+    pub fn new(p00: Vec3<T>, p10: Vec3<T>, p01: Vec3<T>, p11: Vec3<T>, color: Color) -> Self {
+        BilinearPatch { corners: [p00, p10, p01, p11], color, roughness: 1.0 }
+    }
+}
+
+impl<T: Copy + Into<f64>> BilinearPatch<T> {
+    /// The corners as f64.
+    fn points(&self) -> [Vec3<f64>; 4] {
+        self.corners.map(|corner| corner.to_f64())
+    }
+
+    /// The position on the patch at uv coordinates.
+    pub fn at(&self, u: f64, v: f64) -> Vec3<f64> {
+        let [p00, p10, p01, p11] = self.points();
+
+        (p00 * (1.0 - u) + p10 * u) * (1.0 - v) + (p01 * (1.0 - u) + p11 * u) * v
+    }
+
+    /// The rate of change of position with u and v, at uv coordinates.
+    fn tangents(&self, u: f64, v: f64) -> (Vec3<f64>, Vec3<f64>) {
+        let [p00, p10, p01, p11] = self.points();
+
+        ((p10 - p00) * (1.0 - v) + (p11 - p01) * v, (p01 - p00) * (1.0 - u) + (p11 - p10) * u)
+    }
+
+    /// The unit normal of the patch at uv coordinates.
+    fn normal_at(&self, u: f64, v: f64) -> Vec3<f64> {
+        let (du, dv) = self.tangents(u, v);
+
+        du.cross(&dv).unit()
+    }
+
+    /// The first distance along the ray to the patch, with the uv coordinates there.
+    fn hit(&self, ray: &Ray<T>) -> Option<(f64, (f64, f64))> {
+        let [p00, p10, p01, p11] = self.points();
+        let (origin, direction) = (ray.origin.to_f64(), ray.direction.to_f64());
+
+        // Each u gives a line across the patch, from p00 + u e10 along e00 + u twist.
+        let (e10, e00) = (p10 - p00, p01 - p00);
+        let twist = p11 - p10 - p01 + p00;
+        let q = p00 - origin;
+
+        // The ray meets the line where the line, and the offset to it, lie in a plane with the ray.
+        let a = e10.cross(&twist) * direction;
+        let b = (q.cross(&twist) + e10.cross(&e00)) * direction;
+        let c = q.cross(&e00) * direction;
+
+        let mut nearest: Option<(f64, (f64, f64))> = None;
+
+        for u in quadratic(a, b, c) {
+            if !(0.0..=1.0).contains(&u) {
+                continue;
+            }
+
+            let start = q + e10 * u;
+            let across = e00 + twist * u;
+
+            // The closest points between the line across the patch and the ray.
+            let perpendicular = across.cross(&direction);
+            let squared = perpendicular * perpendicular;
+            if squared == 0.0 {
+                continue;
+            }
+
+            let v = direction.cross(&start) * perpendicular / squared;
+            let distance = across.cross(&start) * perpendicular / squared;
+
+            if !(0.0..=1.0).contains(&v) || distance < 0.01 {
+                continue;
+            }
+
+            if nearest.is_none_or(|(t, _)| distance < t) {
+                nearest = Some((distance, (u, v)));
+            }
+        }
+
+        nearest
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for BilinearPatch<T> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.hit(ray)?.0.into())
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let (distance, (u, v)) = self.hit(ray)?;
+
+        Some(Ray::new(ray.at(T::from(distance)), Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), self.normal_at(u, v), self.roughness))))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        let (u, v) = self.hit(ray)?.1;
+
+        Some(Vec3::from_f64(self.normal_at(u, v)))
+    }
+
+    /// Covered from 0 to 1 between each pair of corners.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        Some(self.hit(ray)?.1)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        let (u, v) = self.hit(ray)?.1;
+
+        Some(self.tangents(u, v))
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let (u, v) = self.hit(ray)?.1;
+
+        let normal = self.normal_at(u, v);
+        reflection_pdf(ray.direction.to_f64(), normal, self.roughness, direction.to_f64())
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+impl<T: Copy + Into<f64>> SampleArea for BilinearPatch<T> {
+    /// Uniform over uv coordinates, so denser where the patch is pinched.
+    fn sample_area(&self, u: f64, v: f64) -> (Vec3<f64>, Vec3<f64>, f64) {
+        (self.at(u, v), self.normal_at(u, v), self.pdf_area(u, v))
+    }
+
+    fn pdf_area(&self, u: f64, v: f64) -> f64 {
+        let (du, dv) = self.tangents(u, v);
+
+        1.0 / du.cross(&dv).length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat() {
+        // A parallelogram matches the quad across the same corners.
+        let patch = BilinearPatch::new(
+            Vec3::new(-1.0, -1.0, 5.0),
+            Vec3::new(3.0, -1.0, 5.0),
+            Vec3::new(-1.0, 1.0, 5.0),
+            Vec3::new(3.0, 1.0, 5.0),
+            Color::new(1.0, 1.0, 1.0),
+        );
+
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(patch.intersects_along(&ray), Some(5.0));
+        assert_eq!(patch.normal(&ray), Some(Vec3::new(0.0, 0.0, 1.0)));
+        assert_eq!(patch.uv(&ray), Some((0.25, 0.75)));
+
+        assert_eq!(patch.intersects_along(&Ray::new(Vec3::new(3.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))), None);
+
+        let (point, _normal, pdf) = patch.sample_area(0.5, 0.5);
+        assert_eq!(point, Vec3::new(1.0, 0.0, 5.0));
+        assert_eq!(pdf, 1.0 / 8.0);
+    }
+
+    #[test]
+    fn twisted() {
+        // The saddle z = xy over the unit square.
+        let patch = BilinearPatch::new(
+            Vec3::new(0.0_f64, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+            Color::new(1.0, 1.0, 1.0),
+        );
+
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!((patch.intersects_along(&ray).unwrap() - 4.75).abs() < 1e-9);
+
+        let (u, v) = patch.uv(&ray).unwrap();
+        assert!((u - 0.5).abs() < 1e-9 && (v - 0.5).abs() < 1e-9);
+        assert!((patch.normal(&ray).unwrap() - Vec3::new(-0.5, -0.5, 1.0).unit()).length() < 1e-9);
+
+        // Along the diagonal where the saddle rises, through it from the side, at x = y = 0.8.
+        let ray = Ray::new(Vec3::new(-1.0, -1.0, 0.64), Vec3::new(1.0, 1.0, 0.0));
+        assert!((patch.intersects_along(&ray).unwrap() - 1.8 * 2.0_f64.sqrt()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::{Raytrace, rotate_between};
+use crate::color::Color;
+use crate::texture::Texture;
+
+use std::ops::*;
+
+/// The step in uv coordinates used to find the slope of a bump map.
+const BUMP_STEP: f64 = 0.001;
+
+/// The surface directions where a ray intersects a shape.
+struct Frame {
+    /// Unit direction of increasing u.
+    tangent: Vec3<f64>,
+
+    /// Unit direction of increasing v, perpendicular to the tangent.
+    bitangent: Vec3<f64>,
+
+    /// Unit normal, facing against the ray.
+    normal: Vec3<f64>,
+
+    /// The distance moved across the surface per unit of u and v.
+    scale: (f64, f64),
+}
+
+impl Frame {
+    /// Builds the frame from the normal and uv tangents of a shape.
+    fn new<T: Copy + Into<f64> + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>>(shape: &S, ray: &Ray<T>) -> Option<Self> {
+        let mut normal = shape.normal(ray)?.to_f64();
+        let (du, dv) = shape.uv_tangents(ray)?;
+
+        if normal * ray.direction.to_f64() > 0.0 {
+            normal = normal * -1.0;
+        }
+
+        let tangent = du - normal * (normal * du);
+        if tangent.length() == 0.0 {
+            return None;
+        }
+        let tangent = tangent.unit();
+
+        let mut bitangent = normal.cross(&tangent);
+        if bitangent * dv < 0.0 {
+            bitangent = bitangent * -1.0;
+        }
+
+        Some(Frame { tangent, bitangent, normal, scale: (du.length(), dv.length()) })
+    }
+}
+
+/// The reflection of a ray from the shape's own normal, and from a perturbed normal.
+///
+/// Returns None where the perturbed reflection would pass through the surface.
+fn reflections<T: Copy + Into<f64> + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>>(shape: &S, ray: &Ray<T>, normal: Vec3<f64>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+    let direction = ray.direction.to_f64().unit();
+    let geometric = shape.normal(ray)?.to_f64();
+
+    let reflect = |n: Vec3<f64>| (direction - n * (n * direction * 2.0)).unit();
+    let (original, perturbed) = (reflect(geometric), reflect(normal));
+
+    if (perturbed * geometric) * (direction * geometric) > 0.0 {
+        return None;
+    }
+
+    Some((original, perturbed))
+}
+
+/// Reflects as the shape does, but about a perturbed normal.
+fn rotated_transmit<T: From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>>(shape: &S, ray: &Ray<T>, normal: Option<Vec3<f64>>) -> Option<Ray<T>> {
+    let out = shape.transmit(ray)?.with_time(ray.time);
+
+    match normal.and_then(|n| reflections(shape, ray, n)) {
+        Some((original, perturbed)) => Some(Ray::new(out.origin, Vec3::from_f64(rotate_between(original, perturbed, out.direction.to_f64()))).with_time(out.time)),
+        None => Some(out),
+    }
+}
+
+/// The scatter_pdf of the shape, for reflection about a perturbed normal.
+///
+/// Rotations do not change solid angle, so the density is that of the unrotated direction.
+fn rotated_pdf<T: From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>>(shape: &S, ray: &Ray<T>, normal: Option<Vec3<f64>>, direction: &Vec3<T>) -> Option<f64> {
+    match normal.and_then(|n| reflections(shape, ray, n)) {
+        Some((original, perturbed)) => shape.scatter_pdf(ray, &Vec3::from_f64(rotate_between(perturbed, original, direction.to_f64()))),
+        None => shape.scatter_pdf(ray, direction),
+    }
+}
+
+/// A shape with a tangent space normal map applied.
+///
+/// The red, green and blue of the map, from 0 to 1, give the normal along increasing u, increasing v,
+/// and away from the surface, from -1 to 1, so a flat map is `Color::new(0.5, 0.5, 1.0)`.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NormalMapped<S, X> {
+    /// The shape, which must give uv coordinates and tangents for the map to apply.
+    pub shape: S,
+
+    /// The normal map.
+    pub map: X,
+
+    /// How strongly the map tilts the normal, where 1 is unchanged.
+    pub strength: f64,
+}
+
+impl<S, X> NormalMapped<S, X> {
+    /// Default constructor.
+    pub fn new(shape: S, map: X) -> Self {
+        NormalMapped { shape, map, strength: 1.0 }
+    }
+
+    /// Sets how strongly the map tilts the normal.
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+
+        self
+    }
+
+    /// The unit normal from the map, where a ray intersects the shape.
+    fn mapped_normal<T: Copy + Into<f64> + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>>(&self, ray: &Ray<T>) -> Option<Vec3<f64>> where S: Raytrace<T>, X: Texture {
+        let frame = Frame::new(&self.shape, ray)?;
+        let color = self.map.color_at(self.shape.uv(ray)?, self.shape.intersects_at(ray)?.to_f64());
+
+        let x = (color.r * 2.0 - 1.0) * self.strength;
+        let y = (color.g * 2.0 - 1.0) * self.strength;
+        let z = color.b * 2.0 - 1.0;
+
+        Some((frame.tangent * x + frame.bitangent * y + frame.normal * z).unit())
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>, X: Texture> Raytrace<T> for NormalMapped<S, X> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        self.shape.intersects_along(ray)
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        self.shape.intervals(ray)
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        rotated_transmit(&self.shape, ray, self.mapped_normal(ray))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.mapped_normal(ray).map(Vec3::from_f64).or_else(|| self.shape.normal(ray))
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.shape.uv(ray)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.shape.uv_tangents(ray)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        rotated_pdf(&self.shape, ray, self.mapped_normal(ray), direction)
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        self.shape.recolor(ray, color)
+    }
+}
+
+/// A shape with a bump map applied.
+///
+/// The luminance of the map gives the height of the surface, which tilts the normal where it changes.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BumpMapped<S, X> {
+    /// The shape, which must give uv coordinates and tangents for the map to apply.
+    pub shape: S,
+
+    /// The bump map.
+    pub map: X,
+
+    /// The height of the surface where the map has a luminance of 1.
+    pub height: f64,
+}
+
+impl<S, X> BumpMapped<S, X> {
+    /// Default constructor.
+    pub fn new(shape: S, map: X, height: f64) -> Self {
+        BumpMapped { shape, map, height }
+    }
+
+    /// The unit normal from the slope of the map, where a ray intersects the shape.
+    fn bumped_normal<T: Copy + Into<f64> + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>>(&self, ray: &Ray<T>) -> Option<Vec3<f64>> where S: Raytrace<T>, X: Texture {
+        let frame = Frame::new(&self.shape, ray)?;
+        let (u, v) = self.shape.uv(ray)?;
+        let position = self.shape.intersects_at(ray)?.to_f64();
+
+        let height = |uv: (f64, f64)| self.map.color_at(uv, position).luminance() * self.height;
+        let base = height((u, v));
+
+        // The change in height per distance across the surface.
+        let slope_u = (height((u + BUMP_STEP, v)) - base) / (BUMP_STEP * frame.scale.0);
+        let slope_v = (height((u, v + BUMP_STEP)) - base) / (BUMP_STEP * frame.scale.1);
+
+        Some((frame.normal - frame.tangent * slope_u - frame.bitangent * slope_v).unit())
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>, X: Texture> Raytrace<T> for BumpMapped<S, X> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        self.shape.intersects_along(ray)
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        self.shape.intervals(ray)
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        rotated_transmit(&self.shape, ray, self.bumped_normal(ray))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.bumped_normal(ray).map(Vec3::from_f64).or_else(|| self.shape.normal(ray))
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.shape.uv(ray)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.shape.uv_tangents(ray)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        rotated_pdf(&self.shape, ray, self.bumped_normal(ray), direction)
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        self.shape.recolor(ray, color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plane::Plane;
+    use crate::texture::Procedural;
+
+    #[test]
+    fn flat() {
+        let ground = Plane { roughness: 0.0, ..Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0)) };
+        let ray = Ray::new(Vec3::new(0.0, 1.0, -1.0), Vec3::new(0.0, -1.0, 1.0));
+        let mapped = NormalMapped::new(ground, Color::new(0.5, 0.5, 1.0));
+
+        assert!((mapped.normal(&ray).unwrap() - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+        assert!((mapped.transmit(&ray).unwrap().direction - ground.transmit(&ray).unwrap().direction).length() < 1e-9);
+    }
+
+    #[test]
+    fn tilted() {
+        let ground = Plane { roughness: 0.0, ..Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0)) };
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+
+        // Tilted 45 degrees towards increasing u.
+        let mapped = NormalMapped::new(ground, Color::new(0.5 + 0.5 / 2.0_f64.sqrt(), 0.5, 0.5 + 0.5 / 2.0_f64.sqrt()));
+        let (tangent, _bitangent) = ground.tangents();
+
+        let normal = mapped.normal(&ray).unwrap();
+        assert!((normal * tangent - 0.5_f64.sqrt()).abs() < 1e-9);
+
+        // Straight down reflects along the tangent.
+        let out = mapped.transmit(&ray).unwrap();
+        assert!((out.direction - tangent).length() < 1e-9);
+
+        // Keeps the time of the ray, for geometry in motion.
+        assert_eq!(mapped.transmit(&ray.with_time(0.4)).unwrap().time, 0.4);
+    }
+
+    #[test]
+    fn bump() {
+        let ground = Plane { roughness: 0.0, ..Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0)) };
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let (tangent, bitangent) = ground.tangents();
+
+        // Rises by 0.5 for each unit along v.
+        let ramp = Procedural(|uv: (f64, f64), _pos: Vec3<f64>| Color::new(uv.1, uv.1, uv.1));
+        let bumped = BumpMapped::new(ground, ramp, 0.5);
+
+        let normal = bumped.normal(&ray).unwrap();
+        let expected = (Vec3::new(0.0, 1.0, 0.0) - bitangent * 0.5).unit();
+
+        assert!((normal - expected).length() < 1e-6);
+        assert!((normal * tangent).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pdf() {
+        let ray = Ray::new(Vec3::new(0.0, 1.0, -1.0), Vec3::new(0.0, -1.0, 1.0));
+        let shape = Plane { roughness: 0.3, ..Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0)) };
+        let mapped = NormalMapped::new(shape, Color::new(0.6, 0.45, 0.9));
+
+        // The densest direction is the reflection about the mapped normal.
+        let normal = mapped.normal(&ray).unwrap();
+        let direction = ray.direction - normal * (normal * ray.direction * 2.0);
+        let reflected = ray.direction - Vec3::new(0.0, 1.0, 0.0) * (ray.direction.y * 2.0);
+
+        assert!((mapped.scatter_pdf(&ray, &direction).unwrap() - shape.scatter_pdf(&ray, &reflected).unwrap()).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+
+/// The most items kept together in a leaf, rather than split further.
+const LEAF_SIZE: usize = 4;
+
+/// A node of the hierarchy, bounding every item beneath it.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// A range of items, within the order of the hierarchy.
+    Leaf { bounds: Aabb<f64>, start: usize, end: usize },
+
+    /// Two nodes, by index.
+    Branch { bounds: Aabb<f64>, left: usize, right: usize },
+}
+
+impl Node {
+    fn bounds(&self) -> &Aabb<f64> {
+        match self {
+            Node::Leaf { bounds, .. } => bounds,
+            Node::Branch { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A bounding volume hierarchy.
+///
+/// Nested boxes around a list of items, which skip testing a ray against items whose boxes it misses.
+///
+/// Items are given only by their bounds, so the hierarchy can be built over any primitive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bvh {
+    /// Every node, with the root first.
+    nodes: Vec<Node>,
+
+    /// The indices of the items, in the order the leaves refer to.
+    order: Vec<usize>,
+}
+
+impl Bvh {
+    /// Builds the hierarchy over items with bounds, splitting at the median along the longest axis.
+    pub fn new(bounds: &[Aabb<f64>]) -> Self {
+        let mut bvh = Bvh { nodes: vec![], order: (0..bounds.len()).collect() };
+
+        if !bounds.is_empty() {
+            bvh.build(bounds, 0, bounds.len());
+        }
+
+        bvh
+    }
+
+    /// Builds the node over a range of the order, returning its index.
+    fn build(&mut self, bounds: &[Aabb<f64>], start: usize, end: usize) -> usize {
+        let items = &mut self.order[start..end];
+        let around = items.iter().fold(bounds[items[0]], |sum, &i| sum.union(bounds[i]));
+
+        let index = self.nodes.len();
+
+        if items.len() <= LEAF_SIZE {
+            self.nodes.push(Node::Leaf { bounds: around, start, end });
+
+            return index;
+        }
+
+        // Splits along the axis the centers are most spread over.
+        let centers = Aabb::from_points(items.iter().map(|&i| bounds[i].center())).unwrap();
+        let size = centers.size();
+
+        let axis = |i: &usize| {
+            let center = bounds[*i].center();
+
+            if size.x >= size.y && size.x >= size.z { center.x } else if size.y >= size.z { center.y } else { center.z }
+        };
+
+        items.sort_by(|a, b| axis(a).total_cmp(&axis(b)));
+
+        // The children are filled in once built.
+        self.nodes.push(Node::Leaf { bounds: around, start, end });
+
+        let middle = (start + end) / 2;
+        let left = self.build(bounds, start, middle);
+        let right = self.build(bounds, middle, end);
+
+        self.nodes[index] = Node::Branch { bounds: around, left, right };
+
+        index
+    }
+
+    /// The nearest item hit by a ray, and the distance to it.
+    ///
+    /// Hit gives the distance along the ray to an item, if it is hit before the current nearest distance.
+    pub fn closest(&self, ray: &Ray<f64>, mut hit: impl FnMut(usize, f64) -> Option<f64>) -> Option<(usize, f64)> {
+        let mut closest: Option<(usize, f64)> = None;
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+
+        while let Some(index) = stack.pop() {
+            let limit = closest.map_or(f64::INFINITY, |x| x.1);
+
+            match self.nodes[index].bounds().slab(ray) {
+                Some((near, far)) if far >= 0.0 && near <= limit => (),
+                _ => continue,
+            }
+
+            match self.nodes[index] {
+                Node::Leaf { start, end, .. } => {
+                    for &item in &self.order[start..end] {
+                        let limit = closest.map_or(f64::INFINITY, |x| x.1);
+
+                        if let Some(distance) = hit(item, limit) {
+                            if distance < limit {
+                                closest = Some((item, distance));
+                            }
+                        }
+                    }
+                },
+                Node::Branch { left, right, .. } => {
+                    // The nearer child is visited first, so that the further may be skipped.
+                    let near = |node: usize| self.nodes[node].bounds().slab(ray).map_or(f64::INFINITY, |x| x.0);
+
+                    if near(left) <= near(right) {
+                        stack.push(right);
+                        stack.push(left);
+                    } else {
+                        stack.push(left);
+                        stack.push(right);
+                    }
+                },
+            }
+        }
+
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vec3;
+
+    #[test]
+    fn closest() {
+        // A row of unit boxes along z, in a shuffled order.
+        let bounds: Vec<Aabb<f64>> = (0..50).map(|i| {
+            let z = ((i * 37) % 50) as f64 * 2.0;
+
+            Aabb::new(Vec3::new(-0.5, -0.5, z), Vec3::new(0.5, 0.5, z + 1.0))
+        }).collect();
+
+        let bvh = Bvh::new(&bounds);
+
+        let mut tested = 0;
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 9.5), Vec3::new(0.0, 0.0, 1.0));
+        let hit = bvh.closest(&ray, |i, _limit| {
+            tested += 1;
+
+            bounds[i].slab(&ray).map(|x| x.0).filter(|&x| x >= 0.0)
+        });
+
+        // The box starting at 10, which is the 15th.
+        assert_eq!(hit, Some((15, 0.5)));
+        assert!(tested < 50);
+
+        assert_eq!(bvh.closest(&Ray::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0)), |_i, _limit| Some(0.0)), None);
+        assert_eq!(Bvh::new(&[]).closest(&ray, |_i, _limit| Some(0.0)), None);
+    }
+}
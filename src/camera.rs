@@ -7,6 +7,8 @@ use std::ops::*;
 /// A camera object, with a postion and a rotation matrix.
 ///
 /// Can be instantiated with a position and rotation matrix, in degrees [[Camera::new]].
+///
+/// Rays are cast at times between the opening and closing of the shutter, blurring moving objects.
 pub struct Camera<T> {
     /// The position of the camera in 3D space.
     pub position: Vec3<T>,
@@ -17,6 +19,14 @@ pub struct Camera<T> {
     /// * Column 2 is the direction of the camera's up-vector.
     /// * Column 3 is the direction of the camera's facing.
     pub rotation: Matrix<T>,
+
+    /// The time the shutter opens at.
+    pub shutter_open: f64,
+
+    /// The time the shutter closes at.
+    ///
+    /// When equal to shutter_open, there is no motion blur.
+    pub shutter_close: f64,
 }
 
 impl<T: Copy + Add + Sub + Mul + Div> Camera<T> {
@@ -26,17 +36,34 @@ impl<T: Copy + Add + Sub + Mul + Div> Camera<T> {
     pub fn new(position: Vec3<T>, rotation: Vec3<T>) -> Self
         where T: From<i32>, Vec3<T>: Into<Matrix<T>> {
         Camera::<T> {
-            position: position,
+            position,
+
+            rotation: <Vec3<T> as Into<Matrix<T>>>::into(rotation),
 
-            rotation: <Vec3<T> as Into<Matrix<T>>>::into(rotation)
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
+    /// Sets the times the shutter opens and closes at.
+    pub fn with_shutter(mut self, open: f64, close: f64) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close;
+
+        self
+    }
+
+    /// The time a fraction of the way through the shutter being open, from 0 to 1.
+    pub fn time(&self, u: f64) -> f64 {
+        self.shutter_open + (self.shutter_close - self.shutter_open) * u
+    }
+
     /// The direction the camera is facing as a ray.
     pub fn ray(&self) -> Ray<T> {
         Ray::<T> {
             origin: self.position,
-            direction: self.rotation[0].clone().try_into().unwrap()
+            direction: self.rotation[0].clone().try_into().unwrap(),
+            time: self.shutter_open,
         }
     }
 
@@ -77,7 +104,9 @@ mod tests {
                 0.0, 0.0, 1.0;
                 0.0, 1.0, 0.0;
                 -1.0, 0.0, 0.0;
-            ]
+            ],
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         };
 
         assert_eq!(
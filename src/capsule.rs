@@ -0,0 +1,124 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::polynomial::quadratic;
+
+use std::ops::*;
+
+/// Capsule.
+///
+/// Every point within a radius of the line between two points,
+/// making a cylinder with rounded ends.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capsule<T> {
+    /// The center of one rounded end.
+    pub start: Vec3<T>,
+
+    /// The center of the other rounded end.
+    pub end: Vec3<T>,
+
+    /// Radius.
+    pub radius: T,
+
+    /// Colour of the capsule.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T> Capsule<T> {
+    /// Default constructor.
+    pub fn new(start: Vec3<T>, end: Vec3<T>, radius: T, color: Color) -> Self {
+        Capsule { start, end, radius, color, roughness: 1.0 }
+    }
+}
+
+impl<T: Copy + Into<f64>> Solid<T> for Capsule<T> {
+    fn hits(&self, ray: &Ray<T>) -> Vec<(f64, Vec3<f64>)> {
+        let start = self.start.to_f64();
+        let axis = self.end.to_f64() - start;
+        let height = axis.length();
+
+        // With no length the capsule is a sphere, around any axis.
+        let axis = if height > 0.0 { axis / height } else { Vec3::new(0.0, 1.0, 0.0) };
+        let radius: f64 = self.radius.into();
+
+        // Splits the ray into parts along and across the axis.
+        let offset = ray.origin.to_f64() - start;
+        let direction = ray.direction.to_f64();
+        let (along, speed) = (offset * axis, direction * axis);
+        let (across, drift) = (offset - axis * along, direction - axis * speed);
+
+        let mut hits = Vec::new();
+
+        for t in quadratic(drift * drift, 2.0 * (across * drift), across * across - radius * radius) {
+            if (0.0..=height).contains(&(along + speed * t)) {
+                hits.push((t, (across + drift * t) / radius));
+            }
+        }
+
+        // Each end only keeps the half of its sphere beyond the cylinder.
+        for (center, outside) in [(0.0, -1.0), (height, 1.0)] {
+            let relative = offset - axis * center;
+
+            for t in quadratic(direction * direction, 2.0 * (relative * direction), relative * relative - radius * radius) {
+                let normal = (relative + direction * t) / radius;
+
+                if normal * axis * outside > 0.0 {
+                    hits.push((t, normal));
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        hits
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn roughness(&self) -> f64 {
+        self.roughness
+    }
+}
+
+solid_raytrace!(Capsule);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        let capsule = Capsule::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 4.0, 5.0), 1.0, Color::new(1.0, 1.0, 1.0));
+
+        // Through the side.
+        let ray = Ray::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(capsule.intervals(&ray), Some(vec![(4.0, 6.0)]));
+
+        // End to end, through both rounded ends.
+        let ray = Ray::new(Vec3::new(0.0, 10.0, 5.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(capsule.intervals(&ray), Some(vec![(5.0, 11.0)]));
+        assert_eq!(capsule.normal(&ray), Some(Vec3::new(0.0, 1.0, 0.0)));
+
+        // Through only a rounded end.
+        let ray = Ray::new(Vec3::new(0.0, 4.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let (enter, exit) = capsule.intervals(&ray).unwrap()[0];
+        assert!((enter - (5.0 - 0.75_f64.sqrt())).abs() < 1e-9);
+        assert!((exit - (5.0 + 0.75_f64.sqrt())).abs() < 1e-9);
+
+        let normal = capsule.normal(&ray).unwrap();
+        assert!((normal - Vec3::new(0.0, 0.5, -0.75_f64.sqrt())).length() < 1e-9);
+
+        // With no length, a sphere.
+        let sphere = Capsule::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 5.0), 1.0, Color::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(sphere.intervals(&ray), Some(vec![(4.0, 6.0)]));
+    }
+}
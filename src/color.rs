@@ -26,6 +26,16 @@ impl Color {
         Color { r: r.clamp(0.0, 1.0) * a, g: g.clamp(0.0, 1.0) * a, b: b.clamp(0.0, 1.0) * a }
     }
 
+    /// New colour along a black, red, yellow, white heatmap, from a float between 0 and 1.
+    pub fn heat(t: f64) -> Self {
+        Color::new(t * 3.0, t * 3.0 - 1.0, t * 3.0 - 2.0)
+    }
+
+    /// The perceived brightness of the colour.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     /// Returns the (R, G, B) values for a colour, as bytes from 0 to 255.
     pub fn bytes(&self) -> (u8, u8, u8) {
         ( (self.r * 255.0).floor() as u8, (self.g * 255.0).floor() as u8, (self.b * 255.0).floor() as u8 )
@@ -44,6 +54,19 @@ impl Mul<f64> for Color {
     }
 }
 
+impl Mul<Color> for Color {
+    type Output = Self;
+
+    /// Multiplies each component separately, such as when filtering light through a surface.
+    fn mul(mut self, other: Color) -> Self::Output {
+        self.r *= other.r;
+        self.g *= other.g;
+        self.b *= other.b;
+
+        self
+    }
+}
+
 impl Div<f64> for Color {
     type Output = Self;
 
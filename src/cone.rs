@@ -0,0 +1,144 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::polynomial::quadratic;
+
+use std::ops::*;
+
+/// Cone.
+///
+/// Narrows from a flat base to a point, or to a smaller flat top, making a frustum.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cone<T> {
+    /// The center of the base.
+    pub start: Vec3<T>,
+
+    /// The point, or center of the top.
+    pub end: Vec3<T>,
+
+    /// Radius of the base.
+    pub radius: T,
+
+    /// Radius of the top, which is 0 for a point.
+    pub end_radius: T,
+
+    /// Colour of the cone.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T: From<f64>> Cone<T> {
+    /// Default constructor, narrowing to a point.
+    pub fn new(start: Vec3<T>, end: Vec3<T>, radius: T, color: Color) -> Self {
+        Cone { start, end, radius, end_radius: 0.0.into(), color, roughness: 1.0 }
+    }
+}
+
+impl<T> Cone<T> {
+    /// Sets the radius of the top, making a frustum.
+    pub fn with_end_radius(mut self, end_radius: T) -> Self {
+        self.end_radius = end_radius;
+
+        self
+    }
+}
+
+impl<T: Copy + Into<f64>> Solid<T> for Cone<T> {
+    fn hits(&self, ray: &Ray<T>) -> Vec<(f64, Vec3<f64>)> {
+        let start = self.start.to_f64();
+        let axis = self.end.to_f64() - start;
+        let height = axis.length();
+
+        // With no length there is no volume, so nothing to hit.
+        if height == 0.0 {
+            return vec![];
+        }
+
+        let axis = axis / height;
+        let (radius, end_radius): (f64, f64) = (self.radius.into(), self.end_radius.into());
+
+        // How fast the radius changes along the axis.
+        let slope = (end_radius - radius) / height;
+
+        // Splits the ray into parts along and across the axis.
+        let offset = ray.origin.to_f64() - start;
+        let direction = ray.direction.to_f64();
+        let (along, speed) = (offset * axis, direction * axis);
+        let (across, drift) = (offset - axis * along, direction - axis * speed);
+
+        // The radius at the origin of the ray.
+        let local = radius + slope * along;
+
+        let mut hits = Vec::new();
+
+        let a = drift * drift - slope * slope * speed * speed;
+        let b = 2.0 * (across * drift - slope * speed * local);
+        let c = across * across - local * local;
+
+        for t in quadratic(a, b, c) {
+            if (0.0..=height).contains(&(along + speed * t)) {
+                let radial = (across + drift * t).unit();
+
+                hits.push((t, (radial - axis * slope).unit()));
+            }
+        }
+
+        if speed != 0.0 {
+            for (cap, cap_radius, normal) in [(0.0, radius, axis * -1.0), (height, end_radius, axis)] {
+                let t = (cap - along) / speed;
+                let radial = across + drift * t;
+
+                if cap_radius > 0.0 && radial * radial <= cap_radius * cap_radius {
+                    hits.push((t, normal));
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        hits
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn roughness(&self) -> f64 {
+        self.roughness
+    }
+}
+
+solid_raytrace!(Cone);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        let cone = Cone::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 2.0, 5.0), 2.0, Color::new(1.0, 1.0, 1.0));
+
+        // Halfway up, the radius has halved, and the side slopes at 45°.
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(cone.intervals(&ray), Some(vec![(4.0, 6.0)]));
+
+        let normal = cone.normal(&ray).unwrap();
+        assert!((normal - Vec3::new(0.0, 1.0, -1.0).unit()).length() < 1e-9);
+
+        // Up through the base, to the point.
+        let ray = Ray::new(Vec3::new(0.0, -3.0, 5.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(cone.intersects_along(&ray), Some(3.0));
+        assert_eq!(cone.normal(&ray), Some(Vec3::new(0.0, -1.0, 0.0)));
+
+        // A frustum has a top.
+        let frustum = cone.with_end_radius(1.0);
+        let ray = Ray::new(Vec3::new(0.5, 10.0, 5.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(frustum.intervals(&ray), Some(vec![(8.0, 10.0)]));
+        assert_eq!(frustum.normal(&ray), Some(Vec3::new(0.0, 1.0, 0.0)));
+    }
+}
@@ -0,0 +1,343 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+
+use std::ops::*;
+use std::cmp::Ordering;
+
+/// How the volumes of two objects are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Inside either object.
+    Union,
+
+    /// Inside both objects.
+    Intersection,
+
+    /// Inside the first object, but not the second.
+    Difference,
+}
+
+impl Operation {
+    /// Whether a point is inside the combination, from whether it is inside each object.
+    fn contains(&self, a: bool, b: bool) -> bool {
+        match self {
+            Operation::Union => a || b,
+            Operation::Intersection => a && b,
+            Operation::Difference => a && !b,
+        }
+    }
+}
+
+/// The distance a ray crosses the surface of one of the objects of a [Csg].
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Boundary<T> {
+    distance: T,
+
+    /// Whether the surface belongs to the first object, rather than the second.
+    first: bool,
+
+    /// Whether the ray is entering the object the surface belongs to, rather than leaving it.
+    entering: bool,
+
+    /// The distance to the neighbouring crossing of the same object, before the surface if entering, or after it if leaving.
+    gap: f64,
+}
+
+/// An object of a [Csg] with the ray to pass on to it, and the boundary where the ray hits it.
+type Surface<'a, T> = (&'a dyn Raytrace<T>, Ray<T>, Boundary<T>);
+
+/// Constructive solid geometry, combining the volumes of two objects.
+///
+/// Both objects must give their [Raytrace::intervals], so must enclose a volume,
+/// and the combination does too, so can itself be combined.
+///
+/// The surface where a ray hits keeps the color and roughness of the object it belongs to.
+///
+/// ```
+/// # use rusttracing::csg::*;
+/// # use rusttracing::sphere::*;
+/// # use rusttracing::vector::*;
+/// # use rusttracing::color::*;
+/// # use rusttracing::ray::*;
+/// # use rusttracing::raytrace::*;
+/// let white = Color::new(1.0, 1.0, 1.0);
+///
+/// // A sphere with a bite taken out of the front.
+/// let bitten = Csg::difference(
+///     Sphere::new(Vec3::new(0.0, 0.0, 5.0), 1.0, white),
+///     Sphere::new(Vec3::new(0.0, 0.0, 4.0), 0.5, white),
+/// );
+///
+/// let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+/// assert_eq!(bitten.intersects_along(&ray), Some(4.5));
+/// ```
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Csg<A, B> {
+    /// The first object.
+    pub a: A,
+
+    /// The second object.
+    pub b: B,
+
+    /// How the objects are combined.
+    pub operation: Operation,
+}
+
+impl<A, B> Csg<A, B> {
+    /// Default constructor.
+    pub fn new(a: A, b: B, operation: Operation) -> Self {
+        Csg { a, b, operation }
+    }
+
+    /// The volume inside either object.
+    pub fn union(a: A, b: B) -> Self {
+        Csg::new(a, b, Operation::Union)
+    }
+
+    /// The volume inside both objects.
+    pub fn intersection(a: A, b: B) -> Self {
+        Csg::new(a, b, Operation::Intersection)
+    }
+
+    /// The volume inside the first object, with the second cut away.
+    pub fn difference(a: A, b: B) -> Self {
+        Csg::new(a, b, Operation::Difference)
+    }
+}
+
+impl<A, B> Csg<A, B> {
+    /// The intervals of the combination, as pairs of the boundaries entering and leaving it.
+    fn boundaries<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>>(&self, ray: &Ray<T>) -> Option<Vec<(Boundary<T>, Boundary<T>)>> where A: Raytrace<T>, B: Raytrace<T> {
+        let mut crossings = Vec::new();
+
+        for (first, intervals) in [(true, self.a.intervals(ray)?), (false, self.b.intervals(ray)?)] {
+            for (i, &(enter, exit)) in intervals.iter().enumerate() {
+                let before = if i > 0 { (enter - intervals[i - 1].1).into() } else { f64::INFINITY };
+                let after = if i + 1 < intervals.len() { (intervals[i + 1].0 - exit).into() } else { f64::INFINITY };
+
+                crossings.push(Boundary { distance: enter, first, entering: true, gap: before });
+                crossings.push(Boundary { distance: exit, first, entering: false, gap: after });
+            }
+        }
+
+        crossings.sort_by(|x, y| x.distance.partial_cmp(&y.distance).unwrap_or(Ordering::Equal));
+
+        // Walks along the ray, keeping track of which objects it is inside.
+        let mut inside = (false, false);
+        let mut start = None;
+        let mut out = Vec::new();
+
+        for crossing in crossings {
+            let before = self.operation.contains(inside.0, inside.1);
+
+            if crossing.first {
+                inside.0 = crossing.entering;
+            } else {
+                inside.1 = crossing.entering;
+            }
+
+            let after = self.operation.contains(inside.0, inside.1);
+
+            if !before && after {
+                start = Some(crossing);
+            }
+            if before && !after {
+                if let Some(start) = start.take() {
+                    out.push((start, crossing));
+                }
+            }
+        }
+
+        Some(out)
+    }
+
+    /// The first boundary of the combination in front of the ray.
+    fn hit<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>>(&self, ray: &Ray<T>) -> Option<Boundary<T>> where A: Raytrace<T>, B: Raytrace<T> {
+        self.boundaries(ray)?
+            .into_iter()
+            .flat_map(|(enter, exit)| [enter, exit])
+            .find(|x| x.distance >= T::from(0.01))
+    }
+
+    /// A ray which first hits the object the boundary belongs to at the same point.
+    ///
+    /// Where the ray leaves the object, the ray is reversed, so as to hit the surface from outside.
+    ///
+    /// Starts halfway to the neighbouring crossing of the same object, so that thin parts are not stepped over.
+    fn probe<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>>(ray: &Ray<T>, boundary: &Boundary<T>) -> Ray<T> {
+        let offset = T::from((boundary.gap * 0.5).min(1.0));
+
+        if boundary.entering {
+            Ray { origin: ray.at(boundary.distance - offset), direction: ray.direction, time: ray.time }
+        } else {
+            Ray { origin: ray.at(boundary.distance + offset), direction: ray.direction * T::from(-1.0), time: ray.time }
+        }
+    }
+
+    /// The object the first boundary belongs to, and the ray to pass on to it.
+    fn surface<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>>(&self, ray: &Ray<T>) -> Option<Surface<'_, T>> where A: Raytrace<T>, B: Raytrace<T> {
+        let boundary = self.hit(ray)?;
+        let object: &dyn Raytrace<T> = if boundary.first { &self.a } else { &self.b };
+
+        Some((object, Self::probe(ray, &boundary), boundary))
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, A: Raytrace<T>, B: Raytrace<T>> Raytrace<T> for Csg<A, B> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.hit(ray)?.distance)
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        Some(self.boundaries(ray)?.into_iter().map(|(enter, exit)| (enter.distance, exit.distance)).collect())
+    }
+
+    /// Transmits from the object the surface belongs to.
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let (object, probe, boundary) = self.surface(ray)?;
+        let out = object.transmit(&probe)?;
+
+        // Reflections of the reversed ray are reversed back.
+        if boundary.entering {
+            Some(out)
+        } else {
+            Some(Ray { origin: out.origin, direction: out.direction * T::from(-1.0), time: out.time })
+        }
+    }
+
+    /// Faces out of the combination, so surfaces cut by the second object of a difference face inwards.
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        let (object, probe, boundary) = self.surface(ray)?;
+        let normal = object.normal(&probe)?;
+
+        if self.operation == Operation::Difference && !boundary.first {
+            Some(normal * T::from(-1.0))
+        } else {
+            Some(normal)
+        }
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let (object, probe, _boundary) = self.surface(ray)?;
+
+        object.uv(&probe)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        let (object, probe, _boundary) = self.surface(ray)?;
+
+        object.uv_tangents(&probe)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let (object, probe, boundary) = self.surface(ray)?;
+
+        if boundary.entering {
+            object.scatter_pdf(&probe, direction)
+        } else {
+            object.scatter_pdf(&probe, &(*direction * T::from(-1.0)))
+        }
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        match self.surface(ray) {
+            Some((object, probe, _boundary)) => object.recolor(&probe, color),
+            None => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+    use crate::plane::Plane;
+
+    fn spheres() -> (Sphere<f64>, Sphere<f64>) {
+        let white = Color::new(1.0, 1.0, 1.0);
+
+        (
+            Sphere { roughness: 0.0, ..Sphere::new(Vec3::new(0.0, 0.0, 5.0), 2.0, white) },
+            Sphere { roughness: 0.0, ..Sphere::new(Vec3::new(0.0, 0.0, 7.0), 1.0, Color::new(1.0, 0.0, 0.0)) },
+        )
+    }
+
+    #[test]
+    fn intervals() {
+        let (a, b) = spheres();
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(Csg::union(a, b).intervals(&ray), Some(vec![(3.0, 8.0)]));
+        assert_eq!(Csg::intersection(a, b).intervals(&ray), Some(vec![(6.0, 7.0)]));
+        assert_eq!(Csg::difference(a, b).intervals(&ray), Some(vec![(3.0, 6.0)]));
+        assert_eq!(Csg::difference(b, a).intervals(&ray), Some(vec![(7.0, 8.0)]));
+
+        // Nested combinations, and objects without a volume.
+        let hollow = Csg::difference(Csg::union(a, b), Sphere::new(Vec3::new(0.0, 0.0, 5.0), 1.0, Color::new(1.0, 1.0, 1.0)));
+        assert_eq!(hollow.intervals(&ray), Some(vec![(3.0, 4.0), (6.0, 8.0)]));
+
+        let tri = crate::tri::Tri::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 1.0), Vec3::new(0.0, 1.0, 1.0), Color::new(1.0, 1.0, 1.0), 0.0);
+        assert_eq!(Csg::union(a, tri).intersects_along(&ray), None);
+    }
+
+    #[test]
+    fn surfaces() {
+        let (a, b) = spheres();
+        let bitten = Csg::difference(a, b);
+
+        // Hits the inside of the second sphere, which faces back towards the ray.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(bitten.intersects_along(&ray), Some(4.0));
+        assert_eq!(bitten.normal(&ray), Some(Vec3::new(0.0, 0.0, 1.0)));
+        assert_eq!(bitten.recolor(&ray, Color::new(1.0, 1.0, 1.0)), Color::new(1.0, 0.0, 0.0));
+
+        let out = bitten.transmit(&ray).unwrap();
+        assert!((out.origin - Vec3::new(0.0, 0.0, 6.0)).length() < 1e-9);
+        assert!((out.direction - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-9);
+
+        // Off centre, the hollow reflects back towards the axis, as a concave mirror.
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 10.0), Vec3::new(0.0, 0.0, -1.0));
+        let out = bitten.transmit(&ray).unwrap();
+        assert!((out.direction - Vec3::new(0.0, -0.75_f64.sqrt(), 0.5)).length() < 1e-9);
+    }
+
+    #[test]
+    fn half_space() {
+        let (a, _b) = spheres();
+        let ground = Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0));
+        let dome = Csg::difference(a, ground);
+
+        let down = Ray::new(Vec3::new(0.0, 5.0, 5.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(dome.intersects_along(&down), Some(3.0));
+
+        let up = Ray::new(Vec3::new(0.0, -5.0, 5.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(dome.intersects_along(&up), Some(5.0));
+        assert_eq!(dome.normal(&up), Some(Vec3::new(0.0, -1.0, 0.0)));
+    }
+
+    #[test]
+    fn thin() {
+        use crate::torus::Torus;
+        use crate::instance::Instance;
+        use std::sync::Arc;
+
+        // A tiny ring, with a hole of 0.015 across between the walls of its tube.
+        let ring = Torus { roughness: 0.0, ..Torus::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 2.0, 0.5, Color::new(1.0, 1.0, 1.0)) };
+        let ring = Instance::new(Arc::new(ring)).scale(Vec3::new(0.005, 0.005, 0.005)).translate(Vec3::new(0.0, 0.0, 10.0));
+
+        let (a, _b) = spheres();
+        let combined = Csg::union(ring, Sphere { origin: Vec3::new(0.0, 0.0, -10.0), ..a });
+
+        // Leaving the near side of the tube, without reaching across the hole to the far side.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 9.98), Vec3::new(0.0, 0.0, 1.0));
+        assert!((combined.intersects_along(&ray).unwrap() - 0.0125).abs() < 1e-9);
+
+        let out = combined.transmit(&ray).unwrap();
+        assert!((out.origin - Vec3::new(0.0, 0.0, 9.9925)).length() < 1e-9);
+        assert!((out.direction - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+    }
+}
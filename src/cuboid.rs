@@ -0,0 +1,155 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::aabb::Aabb;
+
+use std::ops::*;
+
+/// Cuboid.
+///
+/// A solid axis-aligned box, intersected exactly by the slab method.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cuboid<T> {
+    /// The corners of the box.
+    pub bounds: Aabb<T>,
+
+    /// Colour of the box.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T> Cuboid<T> {
+    /// Default constructor, between a minimum and maximum corner.
+    pub fn new(min: Vec3<T>, max: Vec3<T>, color: Color) -> Self {
+        Cuboid { bounds: Aabb::new(min, max), color, roughness: 1.0 }
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Cuboid<T> {
+    /// Constructor from the center, and the length along each axis, as with [crate::object::Object::new_box].
+    pub fn from_center(center: Vec3<T>, size: Vec3<T>, color: Color) -> Self {
+        let half = size * T::from(0.5);
+
+        Cuboid::new(center - half, center + half, color)
+    }
+
+    /// The two axes across a face, as indices of x, y and z, from its normal.
+    fn face_axes(normal: Vec3<f64>) -> (usize, usize) {
+        if normal.x != 0.0 {
+            (2, 1)
+        } else if normal.y != 0.0 {
+            (0, 2)
+        } else {
+            (0, 1)
+        }
+    }
+}
+
+/// A part of a vector, by index of x, y and z.
+fn part(vec: Vec3<f64>, axis: usize) -> f64 {
+    match axis {
+        0 => vec.x,
+        1 => vec.y,
+        _ => vec.z,
+    }
+}
+
+/// A unit vector along an axis, by index of x, y and z.
+fn along(axis: usize, length: f64) -> Vec3<f64> {
+    match axis {
+        0 => Vec3::new(length, 0.0, 0.0),
+        1 => Vec3::new(0.0, length, 0.0),
+        _ => Vec3::new(0.0, 0.0, length),
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Cuboid<T> {
+    /// Hits the far side from within the box.
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        let (near, far) = self.bounds.slab(ray)?;
+
+        if near >= (0.01).into() {
+            Some(near)
+        } else if far >= (0.01).into() {
+            Some(far)
+        } else {
+            None
+        }
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        Some(self.bounds.slab(ray).into_iter().collect())
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let pos = self.intersects_at(ray)?;
+        let normal = self.bounds.normal_at(pos);
+
+        Some(Ray::new(pos, Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), normal, self.roughness))))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(Vec3::from_f64(self.bounds.normal_at(self.intersects_at(ray)?)))
+    }
+
+    /// Each face is covered from 0 to 1 in u and v.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let pos = self.intersects_at(ray)?;
+        let (u, v) = Self::face_axes(self.bounds.normal_at(pos));
+
+        let relative = pos.to_f64() - self.bounds.min.to_f64();
+        let size = self.bounds.size().to_f64();
+
+        Some((part(relative, u) / part(size, u), part(relative, v) / part(size, v)))
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        let pos = self.intersects_at(ray)?;
+        let (u, v) = Self::face_axes(self.bounds.normal_at(pos));
+        let size = self.bounds.size().to_f64();
+
+        Some((along(u, part(size, u)), along(v, part(size, v))))
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let normal = self.bounds.normal_at(self.intersects_at(ray)?);
+        reflection_pdf(ray.direction.to_f64(), normal, self.roughness, direction.to_f64())
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faces() {
+        let cuboid = Cuboid::from_center(Vec3::new(0.0, 0.0, 5.0), Vec3::new(2.0, 4.0, 2.0), Color::new(1.0, 1.0, 1.0));
+
+        let ray = Ray::new(Vec3::new(0.5, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(cuboid.intersects_along(&ray), Some(4.0));
+        assert_eq!(cuboid.normal(&ray), Some(Vec3::new(0.0, 0.0, -1.0)));
+        assert_eq!(cuboid.uv(&ray), Some((0.75, 0.75)));
+
+        let ray = Ray::new(Vec3::new(0.5, 10.0, 5.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(cuboid.intersects_along(&ray), Some(8.0));
+        assert_eq!(cuboid.normal(&ray), Some(Vec3::new(0.0, 1.0, 0.0)));
+
+        // From inside, the far face is hit.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(cuboid.intersects_along(&ray), Some(1.0));
+        assert_eq!(cuboid.normal(&ray), Some(Vec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(cuboid.intervals(&ray), Some(vec![(-1.0, 1.0)]));
+
+        let out = Cuboid { roughness: 0.0, ..cuboid }.transmit(&ray).unwrap();
+        assert_eq!(out.direction, Vec3::new(-1.0, 0.0, 0.0));
+    }
+}
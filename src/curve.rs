@@ -0,0 +1,496 @@
+use rand::Rng;
+
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+
+use std::ops::*;
+use std::fmt;
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug)]
+/// Invalid curve list.
+pub struct CurveError;
+impl fmt::Display for CurveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid curve list, please use lines of 4 control points followed by 2 widths")
+    }
+}
+
+/// The number of segments each curve is split into, which are each bounded separately.
+const SEGMENTS: usize = 4;
+
+/// The shape across the width of a curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveShape {
+    /// A flat ribbon, turned to face each ray, for thin curves such as hair and grass.
+    Flat,
+
+    /// A round tube, for thicker curves such as cables.
+    Tube,
+}
+
+/// A cubic Bézier curve, with a width varying linearly along it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Curve<T> {
+    /// The control points, where the curve passes through the first and last.
+    pub points: [Vec3<T>; 4],
+
+    /// The width at the start and end of the curve.
+    pub widths: [T; 2],
+}
+
+impl<T> Curve<T> {
+    /// Default constructor.
+    pub fn new(points: [Vec3<T>; 4], start_width: T, end_width: T) -> Self {
+        Curve { points, widths: [start_width, end_width] }
+    }
+}
+
+impl<T: Copy + Into<f64>> Curve<T> {
+    /// The width at u along the curve.
+    pub fn width(&self, u: f64) -> f64 {
+        self.widths[0].into() * (1.0 - u) + self.widths[1].into() * u
+    }
+
+    /// The position at u along the curve, and the rate of change of position with u.
+    pub fn at(&self, u: f64) -> (Vec3<f64>, Vec3<f64>) {
+        evaluate(self.points.map(|point| point.to_f64()), u)
+    }
+}
+
+/// The position on a cubic Bézier curve at t, and the rate of change of position with t, by de Casteljau's algorithm.
+fn evaluate(points: [Vec3<f64>; 4], t: f64) -> (Vec3<f64>, Vec3<f64>) {
+    let lerp = |a: Vec3<f64>, b: Vec3<f64>| a * (1.0 - t) + b * t;
+
+    let (a, b, c) = (lerp(points[0], points[1]), lerp(points[1], points[2]), lerp(points[2], points[3]));
+    let (d, e) = (lerp(a, b), lerp(b, c));
+
+    (lerp(d, e), (e - d) * 3.0)
+}
+
+/// Splits a cubic Bézier curve in two at t, giving the control points of each half.
+fn split(points: [Vec3<f64>; 4], t: f64) -> ([Vec3<f64>; 4], [Vec3<f64>; 4]) {
+    let lerp = |a: Vec3<f64>, b: Vec3<f64>| a * (1.0 - t) + b * t;
+
+    let (a, b, c) = (lerp(points[0], points[1]), lerp(points[1], points[2]), lerp(points[2], points[3]));
+    let (d, e) = (lerp(a, b), lerp(b, c));
+    let f = lerp(d, e);
+
+    ([points[0], a, d, f], [f, e, c, points[3]])
+}
+
+/// A part of a curve, bounded separately within the hierarchy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+    /// The index of the curve.
+    curve: usize,
+
+    /// The control points of the part.
+    points: [Vec3<f64>; 4],
+
+    /// The start and end of the part, in u along the curve.
+    range: (f64, f64),
+}
+
+/// Where a ray hits a curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Hit {
+    /// The distance along the ray.
+    distance: f64,
+
+    /// The index of the curve.
+    curve: usize,
+
+    /// From 0 at the start of the curve to 1 at the end.
+    u: f64,
+
+    /// From 0 to 1 across the width of the curve.
+    v: f64,
+}
+
+/// Curves.
+///
+/// Many cubic Bézier curves, such as hair, fur, grass or cables, within a bounding volume hierarchy.
+///
+/// Shaded as fibres, which scatter light around a cone about the direction of the curve, as in the Kajiya-Kay model.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curves<T> {
+    /// The curves, which are fixed once the hierarchy is built.
+    curves: Vec<Curve<T>>,
+
+    /// The shape across the width of every curve.
+    pub shape: CurveShape,
+
+    /// Colour of the curves.
+    pub color: Color,
+
+    /// How far light is spread from the cone about the curve,
+    /// in the cosine of the angle to the curve, where 0 scatters only around the cone and 2 or more scatters evenly.
+    pub roughness: f64,
+
+    /// Every part of every curve.
+    segments: Vec<Segment>,
+
+    /// The hierarchy over the segments.
+    bvh: Bvh,
+}
+
+impl<T: Copy + Into<f64>> Curves<T> {
+    /// Default constructor, building the hierarchy over the curves.
+    pub fn new(curves: Vec<Curve<T>>, shape: CurveShape, color: Color) -> Self {
+        let mut segments = vec![];
+
+        for (i, curve) in curves.iter().enumerate() {
+            let mut rest = curve.points.map(|point| point.to_f64());
+
+            for j in 0..SEGMENTS {
+                let (start, end) = (j as f64 / SEGMENTS as f64, (j + 1) as f64 / SEGMENTS as f64);
+
+                // Splits the remainder of the curve, where this segment makes up 1 of the remaining parts.
+                let (points, remainder) = split(rest, 1.0 / (SEGMENTS - j) as f64);
+                rest = remainder;
+
+                segments.push(Segment { curve: i, points, range: (start, end) });
+            }
+        }
+
+        // Curves lie within the hull of their control points, widened by half their width.
+        let bounds: Vec<Aabb<f64>> = segments.iter().map(|segment| {
+            let curve = &curves[segment.curve];
+            let half = curve.width(segment.range.0).max(curve.width(segment.range.1)) * 0.5;
+            let hull = Aabb::from_points(segment.points).unwrap();
+
+            Aabb::new(hull.min - Vec3::new(half, half, half), hull.max + Vec3::new(half, half, half))
+        }).collect();
+
+        let bvh = Bvh::new(&bounds);
+
+        Curves { curves, shape, color, roughness: 0.3, segments, bvh }
+    }
+
+    /// Sets how far light is spread from the cone about the curve.
+    pub fn with_roughness(mut self, roughness: f64) -> Self {
+        self.roughness = roughness;
+
+        self
+    }
+
+    /// The curves.
+    pub fn curves(&self) -> &[Curve<T>] {
+        &self.curves
+    }
+
+    /// The nearest hit on a segment, within a distance, where the segment is given relative to the ray,
+    /// with the ray starting at the origin and pointing along z.
+    ///
+    /// Splits the segment in half until each part is close to straight, then tests it as a line.
+    fn intersect(&self, curve: usize, points: [Vec3<f64>; 4], range: (f64, f64), depth: usize, limit: f64) -> Option<Hit> {
+        let half = self.curves[curve].width(range.0).max(self.curves[curve].width(range.1)) * 0.5;
+        let hull = Aabb::from_points(points).unwrap();
+
+        if hull.min.x - half > 0.0 || hull.max.x + half < 0.0 || hull.min.y - half > 0.0 || hull.max.y + half < 0.0 {
+            return None;
+        }
+        if hull.max.z + half < 0.0 || hull.min.z - half > limit {
+            return None;
+        }
+
+        if depth > 0 {
+            let (a, b) = split(points, 0.5);
+            let middle = (range.0 + range.1) * 0.5;
+
+            let first = self.intersect(curve, a, (range.0, middle), depth - 1, limit);
+            let second = self.intersect(curve, b, (middle, range.1), depth - 1, first.map_or(limit, |hit| hit.distance));
+
+            return second.or(first);
+        }
+
+        // Only hits between the lines across the ends of the part, so that neighbouring parts are not both hit.
+        if (points[1].y - points[0].y) * -points[0].y + points[0].x * (points[0].x - points[1].x) < 0.0 {
+            return None;
+        }
+        if (points[2].y - points[3].y) * -points[3].y + points[3].x * (points[3].x - points[2].x) < 0.0 {
+            return None;
+        }
+
+        // The closest point to the ray along the line between the ends.
+        let (dx, dy) = (points[3].x - points[0].x, points[3].y - points[0].y);
+        let squared = dx * dx + dy * dy;
+        if squared == 0.0 {
+            return None;
+        }
+
+        let w = ((-points[0].x * dx - points[0].y * dy) / squared).clamp(0.0, 1.0);
+        let u = range.0 + (range.1 - range.0) * w;
+
+        let width = self.curves[curve].width(u);
+        let (center, tangent) = evaluate(points, w);
+
+        let offset_squared = center.x * center.x + center.y * center.y;
+        if offset_squared > width * width * 0.25 {
+            return None;
+        }
+
+        let distance = match self.shape {
+            CurveShape::Flat => center.z,
+            CurveShape::Tube => center.z - (width * width * 0.25 - offset_squared).sqrt(),
+        };
+
+        if distance < 0.01 || distance >= limit {
+            return None;
+        }
+
+        // Which side of the curve the ray passes.
+        let offset = offset_squared.sqrt() / width;
+        let v = if tangent.x * -center.y + center.x * tangent.y > 0.0 { 0.5 + offset } else { 0.5 - offset };
+
+        Some(Hit { distance, curve, u, v })
+    }
+
+    /// The nearest hit on any curve.
+    fn hit(&self, ray: &Ray<T>) -> Option<Hit> {
+        let (origin, direction) = (ray.origin.to_f64(), ray.direction.to_f64().unit());
+
+        // Axes across the ray.
+        let other = if direction.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let x = direction.cross(&other).unit();
+        let y = direction.cross(&x);
+
+        let relative = |point: Vec3<f64>| {
+            let offset = point - origin;
+
+            Vec3::new(offset * x, offset * y, offset * direction)
+        };
+
+        let mut nearest = None;
+
+        self.bvh.closest(&Ray::new(origin, direction), |i, limit| {
+            let segment = &self.segments[i];
+            let points = segment.points.map(relative);
+
+            // Splits until the error of a straight line is a small fraction of the width.
+            let bend = (points[0] - points[1] * 2.0 + points[2]).length().max((points[1] - points[2] * 2.0 + points[3]).length());
+            let curve = &self.curves[segment.curve];
+            let tolerance = curve.width(segment.range.0).max(curve.width(segment.range.1)) * 0.05;
+
+            let depth = if bend > 0.0 && tolerance > 0.0 {
+                ((6.0 * 2.0_f64.sqrt() * bend / (8.0 * tolerance)).log2() * 0.5).round().clamp(0.0, 10.0) as usize
+            } else {
+                0
+            };
+
+            let hit = self.intersect(segment.curve, points, segment.range, depth, limit)?;
+            nearest = Some(hit);
+
+            Some(hit.distance)
+        });
+
+        nearest
+    }
+
+    /// The unit direction along a curve, at u.
+    fn tangent(&self, curve: usize, u: f64) -> Vec3<f64> {
+        self.curves[curve].at(u).1.unit()
+    }
+
+    /// The unit normal at a hit, facing back along the ray across a flat curve, or out from the middle of a tube.
+    fn normal_at(&self, ray: &Ray<T>, hit: Hit) -> Vec3<f64> {
+        let direction = ray.direction.to_f64();
+        let (center, tangent) = self.curves[hit.curve].at(hit.u);
+        let tangent = tangent.unit();
+
+        let facing = tangent * (direction * tangent) - direction;
+        let outward = ray.origin.to_f64() + direction * hit.distance - center;
+
+        match self.shape {
+            CurveShape::Tube if outward.length() > 0.0 => outward.unit(),
+            _ if facing.length() > 0.0 => facing.unit(),
+            _ => direction * -1.0,
+        }
+    }
+}
+
+impl<T: Copy + Into<f64> + From<f64>> Curves<T> {
+    /// Creates curves from a list, in text, with one curve on each line.
+    ///
+    /// Each line has the 3 numbers of each of the 4 control points, followed by the width at the start and end.
+    /// Blank lines, and lines starting with #, are skipped.
+    ///
+    /// # Errors
+    /// Will error on lines without exactly 14 numbers.
+    ///
+    /// ```
+    /// # use rusttracing::curve::*;
+    /// # use rusttracing::color::*;
+    /// let text = "# A single blade of grass\n0 0 0  0 0.3 0  0.1 0.6 0  0.3 0.9 0  0.05 0.0\n";
+    /// let grass = Curves::<f64>::from_text(text, CurveShape::Flat, Color::new(0.2, 0.8, 0.1)).unwrap();
+    ///
+    /// assert_eq!(grass.curves().len(), 1);
+    /// assert!(Curves::<f64>::from_text("0 0 0", CurveShape::Flat, Color::new(0.2, 0.8, 0.1)).is_err());
+    /// ```
+    pub fn from_text(text: &str, shape: CurveShape, color: Color) -> Result<Self, CurveError> {
+        let mut curves = vec![];
+
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let numbers = line.split_whitespace().map(|x| x.parse::<f64>().map_err(|_| CurveError)).collect::<Result<Vec<f64>, CurveError>>()?;
+            if numbers.len() != 14 {
+                return Err(CurveError);
+            }
+
+            let point = |i: usize| Vec3::new(numbers[i * 3].into(), numbers[i * 3 + 1].into(), numbers[i * 3 + 2].into());
+
+            curves.push(Curve::new([point(0), point(1), point(2), point(3)], numbers[12].into(), numbers[13].into()));
+        }
+
+        Ok(Curves::new(curves, shape, color))
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Curves<T> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.hit(ray)?.distance.into())
+    }
+
+    /// Keeps the cosine of the angle to the curve, offset by up to the roughness, and turns around the curve at random.
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        // Uses ThreadRng::Default() so is not re-seeded.
+        let mut rng = rand::thread_rng();
+
+        let hit = self.hit(ray)?;
+        let tangent = self.tangent(hit.curve, hit.u);
+
+        let incoming = ray.direction.to_f64().unit() * tangent;
+
+        let mut along = incoming;
+        if self.roughness > 0.0 {
+            loop {
+                along = incoming + rng.gen_range(-1.0..1.0) * self.roughness;
+
+                if along.abs() <= 1.0 {
+                    break;
+                }
+            }
+        }
+
+        let other = if tangent.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let across = tangent.cross(&other).unit();
+        let around = tangent.cross(&across);
+
+        let angle = rng.gen_range(0.0..(2.0 * PI));
+        let direction = tangent * along + (across * angle.cos() + around * angle.sin()) * (1.0 - along * along).max(0.0).sqrt();
+
+        Some(Ray::new(ray.at(T::from(hit.distance)), Vec3::from_f64(direction)))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(Vec3::from_f64(self.normal_at(ray, self.hit(ray)?)))
+    }
+
+    /// From 0 to 1 along the curve, and across its width.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let hit = self.hit(ray)?;
+
+        Some((hit.u, hit.v))
+    }
+
+    /// Uniform over the band of directions transmit can choose, as each is equally likely.
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        if self.roughness <= 0.0 {
+            return None;
+        }
+
+        let hit = self.hit(ray)?;
+        let tangent = self.tangent(hit.curve, hit.u);
+
+        let incoming = ray.direction.to_f64().unit() * tangent;
+        let along = direction.to_f64().unit() * tangent;
+
+        if (along - incoming).abs() > self.roughness {
+            return Some(0.0);
+        }
+
+        // Solid angle is uniform in the cosine to the curve, and around it.
+        let band = (incoming + self.roughness).min(1.0) - (incoming - self.roughness).max(-1.0);
+
+        Some(1.0 / (2.0 * PI * band))
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        // A straight curve along x at z = 5, from x = -3 to 3, at an even speed.
+        let points = [-3.0, -1.0, 1.0, 3.0].map(|x| Vec3::new(x, 0.0, 5.0));
+        let ray = |x: f64, y: f64| Ray::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        let flat = Curves::new(vec![Curve::new(points, 0.5, 0.5)], CurveShape::Flat, Color::new(1.0, 1.0, 1.0));
+        assert!((flat.intersects_along(&ray(0.0, 0.0)).unwrap() - 5.0).abs() < 1e-9);
+        assert!((flat.normal(&ray(0.0, 0.0)).unwrap() - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+
+        let (u, v) = flat.uv(&ray(1.5, 0.1)).unwrap();
+        assert!((u - 0.75).abs() < 1e-9 && (v - 0.5).abs() > 0.1);
+
+        assert_eq!(flat.intersects_along(&ray(0.0, 0.3)), None);
+        assert_eq!(flat.intersects_along(&ray(3.5, 0.0)), None);
+
+        // The front of a tube is half its width closer, and faces out from the middle.
+        let tube = Curves::new(vec![Curve::new(points, 0.5, 0.5)], CurveShape::Tube, Color::new(1.0, 1.0, 1.0));
+        assert!((tube.intersects_along(&ray(0.0, 0.0)).unwrap() - 4.75).abs() < 1e-9);
+        assert!((tube.normal(&ray(0.0, 0.2)).unwrap() - Vec3::new(0.0, 0.8, -0.6)).length() < 1e-6);
+
+        // Widening along the curve.
+        let tapered = Curves::new(vec![Curve::new(points, 0.0, 1.0)], CurveShape::Flat, Color::new(1.0, 1.0, 1.0));
+        assert!(tapered.intersects_along(&ray(1.5, 0.3)).is_some());
+        assert_eq!(tapered.intersects_along(&ray(-1.5, 0.3)), None);
+    }
+
+    #[test]
+    fn scatter() {
+        let mut rng = rand::thread_rng();
+
+        // A straight curve along x at z = 5, from x = -3 to 3, at an even speed.
+        let points = [-3.0, -1.0, 1.0, 3.0].map(|x| Vec3::new(x, 0.0, 5.0));
+
+        let curves = Curves::new(vec![Curve::new(points, 0.5, 0.5)], CurveShape::Tube, Color::new(1.0, 1.0, 1.0)).with_roughness(0.4);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.3, 0.0, 1.0));
+
+        // The density integrates to 1 over every direction, estimated from even directions.
+        let count = 200000;
+        let mut sum = 0.0;
+        for _i in 0..count {
+            let z: f64 = rng.gen_range(-1.0..1.0);
+            let angle: f64 = rng.gen_range(0.0..(2.0 * PI));
+            let direction = Vec3::new((1.0 - z * z).sqrt() * angle.cos(), (1.0 - z * z).sqrt() * angle.sin(), z);
+
+            sum += curves.scatter_pdf(&ray, &direction).unwrap();
+        }
+
+        assert!((sum / count as f64 * 4.0 * PI - 1.0).abs() < 0.02);
+
+        // Transmitted directions keep close to the angle to the curve.
+        let incoming = ray.direction.x;
+        for _i in 0..100 {
+            let out = curves.transmit(&ray).unwrap();
+
+            assert!((out.direction.x - incoming).abs() <= 0.4 + 1e-9);
+            assert!(curves.scatter_pdf(&ray, &out.direction).unwrap() > 0.0);
+        }
+    }
+}
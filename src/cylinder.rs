@@ -0,0 +1,122 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::polynomial::quadratic;
+
+use std::ops::*;
+
+/// Cylinder.
+///
+/// Runs between the centers of two flat caps, with a radius about the line between them.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cylinder<T> {
+    /// The center of one cap.
+    pub start: Vec3<T>,
+
+    /// The center of the other cap.
+    pub end: Vec3<T>,
+
+    /// Radius.
+    pub radius: T,
+
+    /// Colour of the cylinder.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T> Cylinder<T> {
+    /// Default constructor.
+    pub fn new(start: Vec3<T>, end: Vec3<T>, radius: T, color: Color) -> Self {
+        Cylinder { start, end, radius, color, roughness: 1.0 }
+    }
+}
+
+impl<T: Copy + Into<f64>> Solid<T> for Cylinder<T> {
+    fn hits(&self, ray: &Ray<T>) -> Vec<(f64, Vec3<f64>)> {
+        let start = self.start.to_f64();
+        let axis = self.end.to_f64() - start;
+        let height = axis.length();
+
+        // With no length there is no volume, so nothing to hit.
+        if height == 0.0 {
+            return vec![];
+        }
+
+        let axis = axis / height;
+        let radius: f64 = self.radius.into();
+
+        // Splits the ray into parts along and across the axis.
+        let offset = ray.origin.to_f64() - start;
+        let direction = ray.direction.to_f64();
+        let (along, speed) = (offset * axis, direction * axis);
+        let (across, drift) = (offset - axis * along, direction - axis * speed);
+
+        let mut hits = Vec::new();
+
+        for t in quadratic(drift * drift, 2.0 * (across * drift), across * across - radius * radius) {
+            if (0.0..=height).contains(&(along + speed * t)) {
+                hits.push((t, (across + drift * t) / radius));
+            }
+        }
+
+        if speed != 0.0 {
+            for (cap, normal) in [(0.0, axis * -1.0), (height, axis)] {
+                let t = (cap - along) / speed;
+                let radial = across + drift * t;
+
+                if radial * radial <= radius * radius {
+                    hits.push((t, normal));
+                }
+            }
+        }
+
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        hits
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn roughness(&self) -> f64 {
+        self.roughness
+    }
+}
+
+solid_raytrace!(Cylinder);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        let cylinder = Cylinder::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 4.0, 5.0), 1.0, Color::new(1.0, 1.0, 1.0));
+
+        // Through the side.
+        let ray = Ray::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(cylinder.intersects_along(&ray), Some(4.0));
+        assert_eq!(cylinder.normal(&ray), Some(Vec3::new(0.0, 0.0, -1.0)));
+        assert_eq!(cylinder.intervals(&ray), Some(vec![(4.0, 6.0)]));
+
+        // Through the caps.
+        let ray = Ray::new(Vec3::new(0.5, 10.0, 5.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(cylinder.intersects_along(&ray), Some(6.0));
+        assert_eq!(cylinder.normal(&ray), Some(Vec3::new(0.0, 1.0, 0.0)));
+        assert_eq!(cylinder.intervals(&ray), Some(vec![(6.0, 10.0)]));
+
+        // Past the end.
+        let ray = Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(cylinder.intersects_along(&ray), None);
+
+        // With no length, never hit.
+        let flat = Cylinder::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 5.0), 1.0, Color::new(1.0, 1.0, 1.0));
+        assert_eq!(flat.intersects_along(&Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))), None);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+
+use std::ops::*;
+
+/// Disk.
+///
+/// A flat circle, facing along a normal, with an optional hole in the middle, making an annulus.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Disk<T> {
+    /// The center.
+    pub origin: Vec3<T>,
+
+    /// Unit normal vector of the disk.
+    pub normal: Vec3<T>,
+
+    /// Radius.
+    pub radius: T,
+
+    /// Radius of the hole in the middle, which is 0 for a whole disk.
+    pub inner_radius: T,
+
+    /// Colour of the disk.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T: From<f64>> Disk<T> {
+    /// Default constructor.
+    pub fn new(origin: Vec3<T>, normal: Vec3<T>, radius: T, color: Color) -> Self {
+        Disk { origin, normal, radius, inner_radius: 0.0.into(), color, roughness: 1.0 }
+    }
+}
+
+impl<T> Disk<T> {
+    /// Sets the radius of the hole in the middle, making an annulus.
+    pub fn with_inner_radius(mut self, inner_radius: T) -> Self {
+        self.inner_radius = inner_radius;
+
+        self
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Disk<T> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        let divisor = ray.direction * self.normal;
+
+        if divisor == 0.0.into() {
+            return None;
+        }
+
+        let distance = ((self.origin - ray.origin) * self.normal) / divisor;
+
+        if distance < (0.01).into() {
+            return None;
+        }
+
+        let offset = ray.at(distance) - self.origin;
+        let squared = offset * offset;
+
+        if squared > self.radius * self.radius || squared < self.inner_radius * self.inner_radius {
+            return None;
+        }
+
+        Some(distance)
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let pos = self.intersects_at(ray)?;
+
+        Some(Ray::new(pos, Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), self.normal.to_f64(), self.roughness))))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.intersects_along(ray)?;
+
+        Some(self.normal)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.intersects_along(ray)?;
+
+        reflection_pdf(ray.direction.to_f64(), self.normal.to_f64(), self.roughness, direction.to_f64())
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        let annulus = Disk::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 2.0, Color::new(1.0, 1.0, 1.0)).with_inner_radius(1.0);
+
+        let ray = |x| Ray::new(Vec3::new(x, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(annulus.intersects_along(&ray(0.5)), None);
+        assert_eq!(annulus.intersects_along(&ray(1.5)), Some(5.0));
+        assert_eq!(annulus.intersects_along(&ray(2.5)), None);
+
+        let whole = Disk { inner_radius: 0.0, ..annulus };
+        assert_eq!(whole.intersects_along(&ray(0.5)), Some(5.0));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::vector::Vec3;
+use crate::color::Color;
+use crate::image::Image;
+
+use std::f64::consts::PI;
+
+/// The trait for the light surrounding a scene, seen by any ray which escapes.
+///
+/// Directions are unit vectors, pointing away from the scene.
+pub trait Environment {
+    /// The color of light arriving from a direction.
+    fn color(&self, direction: Vec3<f64>) -> Color;
+
+    /// Chooses a direction from two uniform random numbers between 0 and 1,
+    /// favouring directions where the environment is bright.
+    ///
+    /// Returns the direction, and the probability density of choosing it,
+    /// or None if the environment is too uniform to be worth sampling directly.
+    fn sample(&self, _u: f64, _v: f64) -> Option<(Vec3<f64>, f64)> {
+        None
+    }
+
+    /// The probability density of sample choosing a direction.
+    fn pdf(&self, _direction: Vec3<f64>) -> f64 {
+        0.0
+    }
+}
+
+impl Environment for Color {
+    /// A constant color in every direction.
+    fn color(&self, _direction: Vec3<f64>) -> Color {
+        *self
+    }
+}
+
+/// A sky which blends between two colors from the bottom to the top.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gradient {
+    /// The color looking directly up.
+    pub top: Color,
+
+    /// The color looking directly down.
+    pub bottom: Color,
+}
+
+impl Environment for Gradient {
+    fn color(&self, direction: Vec3<f64>) -> Color {
+        let t = (direction.y + 1.0) * 0.5;
+
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
+
+/// A piecewise constant distribution over a list of weights,
+/// used to choose an index in proportion to its weight.
+#[derive(Clone, Debug, PartialEq)]
+struct Distribution {
+    /// The cumulative weights, normalised so that the last is 1.
+    cdf: Vec<f64>,
+
+    /// The sum of all weights.
+    total: f64,
+}
+
+impl Distribution {
+    fn new(weights: &[f64]) -> Self {
+        let mut cdf = Vec::with_capacity(weights.len());
+        let mut total = 0.0;
+
+        for weight in weights {
+            total += weight;
+            cdf.push(total);
+        }
+
+        if total > 0.0 {
+            for x in cdf.iter_mut() {
+                *x /= total;
+            }
+        } else {
+            // Falls back to uniform when there is no weight at all.
+            for (i, x) in cdf.iter_mut().enumerate() {
+                *x = (i + 1) as f64 / weights.len() as f64;
+            }
+        }
+
+        Distribution { cdf, total }
+    }
+
+    /// The probability of choosing an index.
+    fn probability(&self, i: usize) -> f64 {
+        match i {
+            0 => self.cdf[0],
+            _ => self.cdf[i] - self.cdf[i - 1],
+        }
+    }
+
+    /// Chooses an index from a uniform random number,
+    /// returning it along with where u fell within it, between 0 and 1.
+    fn sample(&self, u: f64) -> (usize, f64) {
+        let i = self.cdf.partition_point(|&x| x <= u).min(self.cdf.len() - 1);
+
+        let start = if i == 0 { 0.0 } else { self.cdf[i - 1] };
+        let probability = self.probability(i);
+
+        let offset = if probability > 0.0 { (u - start) / probability } else { 0.5 };
+
+        (i, offset.clamp(0.0, 1.0))
+    }
+}
+
+/// An equirectangular (latitude-longitude) image surrounding the scene.
+///
+/// Directions are chosen in proportion to the brightness of each pixel,
+/// so that small bright areas, such as the sun, light the scene without fireflies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentMap {
+    /// The pixels of the image, as in [Image::data].
+    ///
+    /// The top row looks directly up, and the centre column looks along positive z.
+    pub data: Vec<Vec<Color>>,
+
+    /// A multiplier applied to every pixel.
+    pub strength: f64,
+
+    /// The distribution over rows.
+    rows: Distribution,
+
+    /// The distribution over the pixels within each row.
+    columns: Vec<Distribution>,
+}
+
+impl EnvironmentMap {
+    /// Creates an environment map from an image, multiplying every pixel by strength.
+    pub fn new<const WIDTH: usize, const HEIGHT: usize>(image: &Image<WIDTH, HEIGHT>, strength: f64) -> Self {
+        let mut row_weights = vec![0.0; HEIGHT];
+        let mut columns = Vec::with_capacity(HEIGHT);
+
+        for (y, row_weight) in row_weights.iter_mut().enumerate() {
+            // Rows near the poles cover less of the sphere.
+            let sin_theta = (PI * (y as f64 + 0.5) / HEIGHT as f64).sin();
+
+            let weights: Vec<f64> = (0..WIDTH).map(|x| image[x][y].luminance().max(0.0) * sin_theta).collect();
+
+            *row_weight = weights.iter().sum();
+            columns.push(Distribution::new(&weights));
+        }
+
+        EnvironmentMap { data: image.data.clone(), strength, rows: Distribution::new(&row_weights), columns }
+    }
+
+    fn width(&self) -> usize {
+        self.data.len()
+    }
+
+    fn height(&self) -> usize {
+        self.data[0].len()
+    }
+
+    /// The pixel which a direction falls within.
+    fn pixel(&self, direction: Vec3<f64>) -> (usize, usize) {
+        let u = 0.5 + direction.x.atan2(direction.z) / (2.0 * PI);
+        let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+
+        (
+            ((u * self.width() as f64) as usize).min(self.width() - 1),
+            ((v * self.height() as f64) as usize).min(self.height() - 1),
+        )
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn color(&self, direction: Vec3<f64>) -> Color {
+        let (x, y) = self.pixel(direction);
+
+        self.data[x][y] * self.strength
+    }
+
+    fn sample(&self, u: f64, v: f64) -> Option<(Vec3<f64>, f64)> {
+        let (y, offset_y) = self.rows.sample(u);
+        let (x, offset_x) = self.columns[y].sample(v);
+
+        let theta = PI * (y as f64 + offset_y) / self.height() as f64;
+        let phi = 2.0 * PI * ((x as f64 + offset_x) / self.width() as f64 - 0.5);
+
+        let direction = Vec3::new(theta.sin() * phi.sin(), theta.cos(), theta.sin() * phi.cos());
+
+        Some((direction, self.pdf(direction)))
+    }
+
+    fn pdf(&self, direction: Vec3<f64>) -> f64 {
+        let (x, y) = self.pixel(direction);
+
+        let sin_theta = (1.0 - direction.y * direction.y).max(0.0).sqrt();
+        if sin_theta == 0.0 {
+            return 0.0;
+        }
+
+        // Converts from the probability of a pixel to a density over the sphere.
+        let probability = self.rows.probability(y) * self.columns[y].probability(x);
+
+        probability * (self.width() * self.height()) as f64 / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribution() {
+        let distribution = Distribution::new(&[1.0, 0.0, 3.0]);
+
+        assert_eq!(distribution.probability(0), 0.25);
+        assert_eq!(distribution.probability(1), 0.0);
+        assert_eq!(distribution.sample(0.0), (0, 0.0));
+        assert_eq!(distribution.sample(0.125), (0, 0.5));
+        assert_eq!(distribution.sample(0.625), (2, 0.5));
+    }
+
+    #[test]
+    fn map() {
+        let mut image: Image<8, 4> = Image::new();
+        image[5][1] = Color::new_emission(1.0, 1.0, 1.0, 100.0);
+
+        let map = EnvironmentMap::new(&image, 1.0);
+
+        // Every sample lands in the only bright pixel.
+        for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+            let (direction, pdf) = map.sample(u, v).unwrap();
+
+            assert_eq!(map.pixel(direction), (5, 1));
+            assert_eq!(map.color(direction), image[5][1]);
+            assert!(pdf > 0.0);
+        }
+
+        // The density integrates to 1 over the sphere.
+        let mut total = 0.0;
+        for y in 0..200 {
+            for x in 0..400 {
+                let theta = PI * (y as f64 + 0.5) / 200.0;
+                let phi = 2.0 * PI * (x as f64 + 0.5) / 400.0;
+                let direction = Vec3::new(theta.sin() * phi.sin(), theta.cos(), theta.sin() * phi.cos());
+
+                total += map.pdf(direction) * theta.sin() * (PI / 200.0) * (2.0 * PI / 400.0);
+            }
+        }
+        assert!((total - 1.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,264 @@
+/// A reconstruction filter, used to weight samples by their offset from the pixel centre.
+///
+/// Offsets and radii are measured in pixels.
+///
+/// A radius of 0 or less takes every sample at the pixel centre, as a delta filter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// Every sample within the radius has an equal weight.
+    Box {
+        /// The extent of the filter, from the pixel centre.
+        radius: f64,
+    },
+
+    /// The weight falls linearly to 0 at the radius.
+    Tent {
+        /// The extent of the filter, from the pixel centre.
+        radius: f64,
+    },
+
+    /// A gaussian, shifted such that it falls to 0 at the radius.
+    Gaussian {
+        /// The extent of the filter, from the pixel centre.
+        radius: f64,
+
+        /// The falloff of the gaussian, larger values are sharper.
+        alpha: f64,
+    },
+
+    /// The Mitchell-Netravali cubic filter.
+    ///
+    /// `b = c = 1/3` is the recommended configuration.
+    Mitchell {
+        /// The extent of the filter, from the pixel centre.
+        radius: f64,
+
+        /// The B parameter, controlling blur.
+        b: f64,
+
+        /// The C parameter, controlling ringing.
+        c: f64,
+    },
+}
+
+impl Default for Filter {
+    /// A box filter covering exactly one pixel.
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    /// The distance from the pixel centre outside of which all weights are 0.
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius } => radius,
+            Filter::Tent { radius } => radius,
+            Filter::Gaussian { radius, .. } => radius,
+            Filter::Mitchell { radius, .. } => radius,
+        }
+    }
+
+    /// The weight of a sample offset from the pixel centre by (x, y).
+    ///
+    /// ```
+    /// # use rusttracing::filter::*;
+    /// let tent = Filter::Tent { radius: 2.0 };
+    /// assert_eq!(tent.weight(0.0, 0.0), 1.0);
+    /// assert_eq!(tent.weight(1.0, 0.0), 0.5);
+    /// assert_eq!(tent.weight(0.0, 2.5), 0.0);
+    /// ```
+    pub fn weight(&self, x: f64, y: f64) -> f64 {
+        self.weight_1d(x) * self.weight_1d(y)
+    }
+
+    fn weight_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+
+        match *self {
+            Filter::Box { radius } => {
+                if x <= radius { 1.0 } else { 0.0 }
+            },
+            Filter::Tent { radius } => {
+                (1.0 - x / radius).max(0.0)
+            },
+            Filter::Gaussian { radius, alpha } => {
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0.0)
+            },
+            Filter::Mitchell { radius, b, c } => {
+                // The cubic is defined over [0, 2].
+                let x = 2.0 * x / radius;
+
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b)) / 6.0
+                } else if x < 2.0 {
+                    ((-b - 6.0 * c) * x * x * x
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)) / 6.0
+                } else {
+                    0.0
+                }
+            },
+        }
+    }
+}
+
+/// The number of bins a filter is tabulated into along each axis, for importance sampling.
+const BINS: usize = 256;
+
+/// A filter tabulated for importance sampling,
+/// placing samples in proportion to the magnitude of their weight rather than evenly.
+///
+/// Keeps the weight of every sample bounded, so filters with negative lobes, such as [Filter::Mitchell],
+/// never divide by a sum of weights near 0 at low sample counts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterSampler {
+    /// The filter being sampled.
+    filter: Filter,
+
+    /// The cumulative probability of choosing each bin along an axis, starting at 0 and ending at 1.
+    ///
+    /// Empty for a delta filter.
+    cdf: Vec<f64>,
+
+    /// The integral of the filter along one axis.
+    integral: f64,
+}
+
+impl Filter {
+    /// Tabulates the filter for importance sampling.
+    pub fn sampler(&self) -> FilterSampler {
+        let radius = self.radius();
+
+        // A delta filter, with nothing to tabulate.
+        if radius.is_nan() || radius <= 0.0 {
+            return FilterSampler { filter: *self, cdf: vec![], integral: 1.0 };
+        }
+        let width = 2.0 * radius / BINS as f64;
+
+        // The largest magnitude within each bin, so that no bin with any weight is skipped,
+        // and no sample is weighted far above the rest.
+        let magnitudes: Vec<f64> = (0..BINS).map(|i| {
+            let start = -radius + i as f64 * width;
+
+            [0.0, 0.25, 0.5, 0.75, 1.0].iter().fold(0.0, |max: f64, t| max.max(self.weight_1d(start + width * t).abs()))
+        }).collect();
+
+        let total: f64 = magnitudes.iter().sum();
+
+        let mut cdf = vec![0.0];
+        for magnitude in &magnitudes {
+            cdf.push(cdf.last().unwrap() + magnitude / total);
+        }
+
+        // The integral along one axis, by the midpoint rule over finer steps.
+        let steps = BINS * 16;
+        let step = 2.0 * radius / steps as f64;
+        let integral = (0..steps).map(|i| self.weight_1d(-radius + (i as f64 + 0.5) * step)).sum::<f64>() * step;
+
+        FilterSampler { filter: *self, cdf, integral }
+    }
+}
+
+impl FilterSampler {
+    /// Chooses an offset along one axis from a uniform random number between 0 and 1,
+    /// and the density of choosing it.
+    fn sample_1d(&self, u: f64) -> (f64, f64) {
+        let radius = self.filter.radius();
+        let width = 2.0 * radius / BINS as f64;
+
+        let bin = self.cdf.partition_point(|&x| x <= u).clamp(1, BINS) - 1;
+        let chance = self.cdf[bin + 1] - self.cdf[bin];
+        let within = if chance > 0.0 { (u - self.cdf[bin]) / chance } else { 0.5 };
+
+        (-radius + (bin as f64 + within) * width, chance / width)
+    }
+
+    /// Chooses an offset (x, y) from the pixel centre from two uniform random numbers between 0 and 1,
+    /// and the weight of the sample, such that the mean weight of many samples is 1.
+    ///
+    /// Weights are negative where the filter is.
+    ///
+    /// ```
+    /// # use rusttracing::filter::*;
+    /// let sampler = Filter::Box { radius: 0.5 }.sampler();
+    /// let (x, y, weight) = sampler.sample(0.25, 0.75);
+    ///
+    /// assert!((x + 0.25).abs() < 1e-9 && (y - 0.25).abs() < 1e-9);
+    /// assert!((weight - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn sample(&self, u: f64, v: f64) -> (f64, f64, f64) {
+        if self.cdf.is_empty() {
+            return (0.0, 0.0, 1.0);
+        }
+
+        let ((x, pdf_x), (y, pdf_y)) = (self.sample_1d(u), self.sample_1d(v));
+
+        (x, y, self.filter.weight(x, y) / (pdf_x * pdf_y * self.integral * self.integral))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn bounded() {
+        let filters = [
+            Filter::Box { radius: 0.5 },
+            Filter::Tent { radius: 1.0 },
+            Filter::Gaussian { radius: 1.5, alpha: 2.0 },
+            Filter::Mitchell { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 },
+        ];
+
+        for filter in filters {
+            assert_eq!(filter.weight(filter.radius() + 0.01, 0.0), 0.0);
+            assert_eq!(filter.weight(0.0, -filter.radius() - 0.01), 0.0);
+            assert!(filter.weight(0.0, 0.0) > 0.0);
+        }
+    }
+
+    #[test]
+    fn mitchell() {
+        let filter = Filter::Mitchell { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 };
+
+        assert!((filter.weight(0.0, 0.0) - (8.0 / 9.0) * (8.0 / 9.0)).abs() < 1e-9);
+        assert!(filter.weight(1.5, 0.0) < 0.0);
+    }
+
+    #[test]
+    fn sampler() {
+        let sampler = Filter::Mitchell { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 }.sampler();
+
+        // Weights average to 1, and are bounded, including in the negative lobes.
+        let mut rng = rand::thread_rng();
+        let count = 100000;
+        let mut sum = 0.0;
+        let mut negative = false;
+
+        for _i in 0..count {
+            let (x, y, weight) = sampler.sample(rng.gen(), rng.gen());
+
+            assert!(x.abs() <= 2.0 && y.abs() <= 2.0);
+            assert!(weight.abs() < 1.25);
+
+            negative |= weight < 0.0;
+            sum += weight;
+        }
+
+        assert!(negative);
+        assert!((sum / count as f64 - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn delta() {
+        // Radii of 0 or less sample only the pixel centre, at full weight.
+        for filter in [Filter::Box { radius: 0.0 }, Filter::Tent { radius: -1.0 }, Filter::Gaussian { radius: f64::NAN, alpha: 2.0 }] {
+            assert_eq!(filter.sampler().sample(0.1, 0.9), (0.0, 0.0, 1.0));
+        }
+    }
+}
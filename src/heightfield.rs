@@ -0,0 +1,381 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::image::Image;
+use crate::tri::moller_trumbore;
+
+use std::ops::*;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug)]
+/// Invalid terrain.
+pub struct HeightfieldError;
+impl fmt::Display for HeightfieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid terrain, please give at least 2 heights along each axis, in rows of equal length, and a positive size")
+    }
+}
+
+/// Terrain, from a grid of heights.
+///
+/// Each cell of the grid is split into two tris, with normals interpolated between the points of the grid.
+/// Rays are traced through a min-max mipmap of the heights, so only cells near the ray are tested.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heightfield<T> {
+    /// The lowest corner of the terrain.
+    pub origin: Vec3<T>,
+
+    /// The length of the terrain along x and z, and the height of a height of 1.
+    pub size: Vec3<T>,
+
+    /// Colour of the terrain.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+
+    /// The heights at each point of the grid, from 0 to 1, indexed by x then z.
+    heights: Vec<Vec<f64>>,
+
+    /// The lowest and highest heights within blocks of cells, for each level of the mipmap.
+    ///
+    /// The first level is of single cells, and each level after covers twice as many along each axis.
+    levels: Vec<Vec<Vec<(f64, f64)>>>,
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Heightfield<T> {
+    /// Creates terrain from heights, from 0 to 1, indexed by x then z.
+    ///
+    /// # Errors
+    /// Will error if there are fewer than 2 heights along either axis, the rows differ in length,
+    /// or any of the sizes are not positive.
+    pub fn new(heights: Vec<Vec<f64>>, origin: Vec3<T>, size: Vec3<T>, color: Color) -> Result<Self, HeightfieldError> {
+        let (width, depth) = (heights.len(), heights.first().map_or(0, |column| column.len()));
+        let extent = size.to_f64();
+
+        if width < 2 || depth < 2 || heights.iter().any(|column| column.len() != depth) || !(extent.x > 0.0 && extent.y > 0.0 && extent.z > 0.0) {
+            return Err(HeightfieldError);
+        }
+
+        let mut levels = vec![];
+
+        // The range of heights over each cell, from the points at its corners.
+        let mut level: Vec<Vec<(f64, f64)>> = (0..heights.len() - 1).map(|x| (0..heights[0].len() - 1).map(|z| {
+            let corners = [heights[x][z], heights[x + 1][z], heights[x][z + 1], heights[x + 1][z + 1]];
+
+            (corners.iter().copied().fold(f64::INFINITY, f64::min), corners.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+        }).collect()).collect();
+
+        loop {
+            let (width, depth) = (level.len(), level[0].len());
+            levels.push(level);
+
+            if width == 1 && depth == 1 {
+                break;
+            }
+
+            // Merges blocks of 2 by 2.
+            let below = &levels[levels.len() - 1];
+            level = (0..width.div_ceil(2)).map(|x| (0..depth.div_ceil(2)).map(|z| {
+                let mut range = (f64::INFINITY, f64::NEG_INFINITY);
+
+                for (x, z) in [(2 * x, 2 * z), (2 * x + 1, 2 * z), (2 * x, 2 * z + 1), (2 * x + 1, 2 * z + 1)] {
+                    if let Some(&(low, high)) = below.get(x).and_then(|column| column.get(z)) {
+                        range = (range.0.min(low), range.1.max(high));
+                    }
+                }
+
+                range
+            }).collect()).collect();
+        }
+
+        Ok(Heightfield { origin, size, color, roughness: 1.0, heights, levels })
+    }
+
+    /// Creates terrain from the brightness of an image, with rows of the image running along z.
+    ///
+    /// # Errors
+    /// Will error if the image is narrower than 2 pixels along either axis, or any of the sizes are not positive.
+    pub fn from_image<const WIDTH: usize, const HEIGHT: usize>(image: &Image<WIDTH, HEIGHT>, origin: Vec3<T>, size: Vec3<T>, color: Color) -> Result<Self, HeightfieldError> {
+        let heights = image.data.iter().map(|column| column.iter().map(|pixel| pixel.luminance().clamp(0.0, 1.0)).collect()).collect();
+
+        Heightfield::new(heights, origin, size, color)
+    }
+
+    /// Creates terrain from a function, taking x and z from 0 to 1 and giving the height from 0 to 1,
+    /// sampled at a number of points along each axis.
+    ///
+    /// # Errors
+    /// Will error if there are fewer than 2 points along either axis, or any of the sizes are not positive.
+    ///
+    /// ```
+    /// # use rusttracing::heightfield::*;
+    /// # use rusttracing::vector::*;
+    /// # use rusttracing::color::*;
+    /// let hills = Heightfield::from_fn(
+    ///     |x, z| ((x * 10.0).sin() * (z * 10.0).sin()) * 0.5 + 0.5,
+    ///     (256, 256),
+    ///     Vec3::new(-50.0, 0.0, -50.0),
+    ///     Vec3::new(100.0, 5.0, 100.0),
+    ///     Color::new(0.3, 0.6, 0.2),
+    /// ).unwrap();
+    ///
+    /// assert_eq!(hills.height(0, 0), 0.5);
+    /// ```
+    pub fn from_fn(function: impl Fn(f64, f64) -> f64, resolution: (usize, usize), origin: Vec3<T>, size: Vec3<T>, color: Color) -> Result<Self, HeightfieldError> {
+        let (width, depth) = resolution;
+
+        if width < 2 || depth < 2 {
+            return Err(HeightfieldError);
+        }
+
+        let heights = (0..width).map(|x| (0..depth).map(|z| {
+            function(x as f64 / (width - 1) as f64, z as f64 / (depth - 1) as f64)
+        }).collect()).collect();
+
+        Heightfield::new(heights, origin, size, color)
+    }
+
+    /// The height, from 0 to 1, at a point of the grid.
+    pub fn height(&self, x: usize, z: usize) -> f64 {
+        self.heights[x][z]
+    }
+
+    /// The number of cells along x and z.
+    fn cells(&self) -> (usize, usize) {
+        (self.heights.len() - 1, self.heights[0].len() - 1)
+    }
+
+    /// The scene distance between points of the grid along x and z, and the scene height of a height of 1.
+    fn spacing(&self) -> Vec3<f64> {
+        let (width, depth) = self.cells();
+        let size = self.size.to_f64();
+
+        Vec3::new(size.x / width as f64, size.y, size.z / depth as f64)
+    }
+
+    /// The unit normal at a point of the grid, from the slope to its neighbours.
+    fn vertex_normal(&self, x: usize, z: usize) -> Vec3<f64> {
+        let (width, depth) = self.cells();
+        let spacing = self.spacing();
+
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(width));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(depth));
+
+        let dx = (self.heights[x1][z] - self.heights[x0][z]) * spacing.y / ((x1 - x0) as f64 * spacing.x);
+        let dz = (self.heights[x][z1] - self.heights[x][z0]) * spacing.y / ((z1 - z0) as f64 * spacing.z);
+
+        Vec3::new(-dx, 1.0, -dz).unit()
+    }
+
+    /// Intersects the two tris of a cell, with the ray in grid space.
+    ///
+    /// Gives the distance, and the normal interpolated from the corners.
+    fn cell(&self, x: usize, z: usize, origin: Vec3<f64>, direction: Vec3<f64>) -> Option<(f64, Vec3<f64>)> {
+        let point = |x: usize, z: usize| Vec3::new(x as f64, self.heights[x][z], z as f64);
+
+        let corners = [(x, z), (x + 1, z), (x + 1, z + 1), (x, z + 1)];
+        let mut best: Option<(f64, Vec3<f64>)> = None;
+
+        for [a, b, c] in [[corners[0], corners[1], corners[2]], [corners[0], corners[2], corners[3]]] {
+            let Some((distance, u, v)) = moller_trumbore(origin, direction, [point(a.0, a.1), point(b.0, b.1), point(c.0, c.1)]) else {
+                continue;
+            };
+
+            if distance < 0.01 || best.is_some_and(|best| best.0 <= distance) {
+                continue;
+            }
+
+            let normal = self.vertex_normal(a.0, a.1) * (1.0 - u - v) + self.vertex_normal(b.0, b.1) * u + self.vertex_normal(c.0, c.1) * v;
+
+            best = Some((distance, normal.unit()));
+        }
+
+        best
+    }
+
+    /// The distances along the ray, in grid space, at which it enters and leaves a block of the mipmap.
+    fn block(&self, level: usize, x: usize, z: usize, origin: Vec3<f64>, direction: Vec3<f64>) -> Option<(f64, f64)> {
+        let (width, depth) = self.cells();
+        let (low, high) = self.levels[level][x][z];
+
+        let bounds = [
+            ((x << level) as f64, (((x + 1) << level).min(width)) as f64, origin.x, direction.x),
+            (low, high, origin.y, direction.y),
+            ((z << level) as f64, (((z + 1) << level).min(depth)) as f64, origin.z, direction.z),
+        ];
+
+        let mut near = f64::NEG_INFINITY;
+        let mut far = f64::INFINITY;
+
+        for (min, max, o, d) in bounds {
+            if d == 0.0 {
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let t1 = (min - o) / d;
+            let t2 = (max - o) / d;
+
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+
+        if near > far || far < 0.01 {
+            return None;
+        }
+
+        Some((near, far))
+    }
+
+    /// Searches a block of the mipmap, nearest blocks first, for the nearest hit closer than the best so far.
+    fn search(&self, level: usize, x: usize, z: usize, origin: Vec3<f64>, direction: Vec3<f64>, best: &mut Option<(f64, Vec3<f64>)>) {
+        if level == 0 {
+            if let Some(hit) = self.cell(x, z, origin, direction) {
+                if best.is_none_or(|best| hit.0 < best.0) {
+                    *best = Some(hit);
+                }
+            }
+            return;
+        }
+
+        let below = &self.levels[level - 1];
+
+        let mut children: Vec<(f64, usize, usize)> = [(2 * x, 2 * z), (2 * x + 1, 2 * z), (2 * x, 2 * z + 1), (2 * x + 1, 2 * z + 1)]
+            .into_iter()
+            .filter(|&(x, z)| x < below.len() && z < below[0].len())
+            .filter_map(|(x, z)| Some((self.block(level - 1, x, z, origin, direction)?.0, x, z)))
+            .collect();
+
+        children.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (near, x, z) in children {
+            if best.is_some_and(|best| best.0 < near) {
+                break;
+            }
+
+            self.search(level - 1, x, z, origin, direction, best);
+        }
+    }
+
+    /// The distance along the ray to the terrain, and the normal there.
+    fn hit(&self, ray: &Ray<T>) -> Option<(f64, Vec3<f64>)> {
+        let spacing = self.spacing();
+        let scale = |vec: Vec3<f64>| Vec3::new(vec.x / spacing.x, vec.y / spacing.y, vec.z / spacing.z);
+
+        // Grid space, where points of the grid are a unit apart and heights are from 0 to 1.
+        // Scaling keeps distances along the ray the same.
+        let origin = scale(ray.origin.to_f64() - self.origin.to_f64());
+        let direction = scale(ray.direction.to_f64());
+
+        let top = self.levels.len() - 1;
+        self.block(top, 0, 0, origin, direction)?;
+
+        let mut best = None;
+        self.search(top, 0, 0, origin, direction, &mut best);
+
+        best
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Heightfield<T> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.hit(ray)?.0.into())
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let (distance, normal) = self.hit(ray)?;
+
+        Some(Ray::new(ray.at(T::from(distance)), Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), normal, self.roughness))))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(Vec3::from_f64(self.hit(ray)?.1))
+    }
+
+    /// From 0 to 1 across the terrain along x and z.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let relative = self.intersects_at(ray)?.to_f64() - self.origin.to_f64();
+        let size = self.size.to_f64();
+
+        Some((relative.x / size.x, relative.z / size.z))
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let normal = self.hit(ray)?.1;
+        reflection_pdf(ray.direction.to_f64(), normal, self.roughness, direction.to_f64())
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mipmap() {
+        let terrain: Heightfield<f64> = Heightfield::from_fn(|x, z| x * z, (6, 4), Vec3::new(0.0, 0.0, 0.0), Vec3::new(5.0, 1.0, 3.0), Color::new(1.0, 1.0, 1.0)).unwrap();
+
+        let sizes: Vec<(usize, usize)> = terrain.levels.iter().map(|level| (level.len(), level[0].len())).collect();
+        assert_eq!(sizes, vec![(5, 3), (3, 2), (2, 1), (1, 1)]);
+
+        assert_eq!(terrain.levels[3][0][0], (0.0, 1.0));
+        assert_eq!(terrain.levels[0][4][2], (0.8 * 2.0 / 3.0, 1.0));
+    }
+
+    #[test]
+    fn intersects() {
+        // A slope, rising by 1 for every 2 along x, and wavy along z.
+        let slope = |x: f64, z: f64| 0.5 * x + 0.1 * (z * 20.0).sin();
+        let terrain = Heightfield::from_fn(slope, (65, 65), Vec3::new(-10.0, 0.0, -10.0), Vec3::new(20.0, 10.0, 20.0), Color::new(1.0, 1.0, 1.0)).unwrap();
+
+        for (x, z) in [(0.25, 0.5), (0.7, 0.3), (0.5, 0.5)] {
+            // Straight down onto a point of the grid.
+            let (x, z): (f64, f64) = ((x * 64.0_f64).round() / 64.0, (z * 64.0_f64).round() / 64.0);
+            let ray = Ray::new(Vec3::new(x * 20.0 - 10.0, 20.0, z * 20.0 - 10.0), Vec3::new(0.0, -1.0, 0.0));
+
+            let expected = 20.0 - slope(x, z) * 10.0;
+            assert!((terrain.intersects_along(&ray).unwrap() - expected).abs() < 1e-9);
+        }
+
+        // The normal leans back against the slope, of 1 in 4 within the scene.
+        let ray = Ray::new(Vec3::new(0.0, 20.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let normal = terrain.normal(&ray).unwrap();
+        assert!((normal.x / normal.y + 0.25).abs() < 1e-2);
+
+        // Along the slope, from below it, and away from it.
+        let ray = Ray::new(Vec3::new(-20.0, 1.0, std::f64::consts::PI - 10.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!((terrain.intersects_along(&ray).unwrap() - 14.0).abs() < 0.1);
+
+        let ray = Ray::new(Vec3::new(0.0, 20.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(terrain.intersects_along(&ray), None);
+    }
+
+    #[test]
+    fn degenerate() {
+        let (origin, size, color) = (Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Color::new(1.0, 1.0, 1.0));
+
+        // Too few points to make a cell along an axis.
+        assert!(Heightfield::from_fn(|_x, _z| 0.5, (1, 8), origin, size, color).is_err());
+        assert!(Heightfield::from_fn(|_x, _z| 0.5, (8, 0), origin, size, color).is_err());
+        assert!(Heightfield::new(vec![], origin, size, color).is_err());
+        assert!(Heightfield::from_image(&Image::<1, 8>::new(), origin, size, color).is_err());
+
+        // Uneven rows, and flat or inverted sizes.
+        assert!(Heightfield::new(vec![vec![0.0, 0.0], vec![0.0]], origin, size, color).is_err());
+        assert!(Heightfield::from_fn(|_x, _z| 0.5, (2, 2), origin, Vec3::new(1.0, 0.0, 1.0), color).is_err());
+        assert!(Heightfield::from_fn(|_x, _z| 0.5, (2, 2), origin, Vec3::new(-1.0, 1.0, 1.0), color).is_err());
+
+        // A single cell is enough.
+        let terrain = Heightfield::from_image(&Image::<2, 2>::new(), origin, size, color).unwrap();
+        assert_eq!(terrain.levels.len(), 1);
+    }
+}
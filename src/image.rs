@@ -1,6 +1,81 @@
 use crate::color::*;
+use crate::inflate::*;
 
 use std::ops::*;
+use std::fmt;
+
+/// The B3 spline kernel, spaced further apart on each iteration of [Image::denoise].
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// How strongly differences in lighting stop the denoiser blurring between pixels.
+const LIGHTING_SIGMA: f64 = 2.0;
+
+/// How strongly differences in normal stop the denoiser blurring between pixels.
+const NORMAL_SIGMA: f64 = 0.3;
+
+/// How strongly differences in albedo stop the denoiser blurring between pixels.
+const ALBEDO_SIGMA: f64 = 0.1;
+
+/// How strongly differences in depth, relative to depth, stop the denoiser blurring between pixels.
+const DEPTH_SIGMA: f64 = 0.05;
+
+#[derive(Clone, Copy, Debug)]
+/// Invalid or unsupported image data, or an image of the wrong size.
+pub struct DecodeError;
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid, unsupported, or incorrectly sized image")
+    }
+}
+
+/// Reads a line of ASCII text, returning it and the remaining bytes.
+fn read_line(bytes: &[u8]) -> Result<(&str, &[u8]), DecodeError> {
+    let end = bytes.iter().position(|&x| x == b'\n').ok_or(DecodeError)?;
+    let line = std::str::from_utf8(&bytes[..end]).map_err(|_| DecodeError)?;
+
+    Ok((line.trim(), &bytes[end + 1..]))
+}
+
+/// Reads a whitespace separated ASCII token, skipping comments, returning it and the remaining bytes.
+fn read_token(bytes: &[u8]) -> Result<(&str, &[u8]), DecodeError> {
+    let mut rest = bytes;
+
+    loop {
+        match rest.first() {
+            Some(x) if x.is_ascii_whitespace() => rest = &rest[1..],
+            Some(b'#') => {
+                let end = rest.iter().position(|&x| x == b'\n').ok_or(DecodeError)?;
+                rest = &rest[end..];
+            },
+            Some(_) => break,
+            None => return Err(DecodeError),
+        }
+    }
+
+    let end = rest.iter().position(|x| x.is_ascii_whitespace()).unwrap_or(rest.len());
+    let token = std::str::from_utf8(&rest[..end]).map_err(|_| DecodeError)?;
+
+    Ok((token, &rest[end..]))
+}
+
+/// Reads a big endian integer of a number of bytes.
+fn read_be(bytes: &[u8], start: usize, length: usize) -> Result<u32, DecodeError> {
+    let bytes = bytes.get(start..start + length).ok_or(DecodeError)?;
+
+    Ok(bytes.iter().fold(0, |out, &x| out << 8 | x as u32))
+}
+
+fn distance_squared(a: Color, b: Color) -> f64 {
+    (a.r - b.r) * (a.r - b.r) + (a.g - b.g) * (a.g - b.g) + (a.b - b.b) * (a.b - b.b)
+}
+
+fn depth_weight(a: f64, b: f64, step: f64) -> f64 {
+    if a.is_infinite() || b.is_infinite() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    (-(a - b).abs() / (DEPTH_SIGMA * step * a.abs().max(0.001))).exp()
+}
 
 /// An image struct.
 ///
@@ -20,6 +95,440 @@ impl<const WIDTH: usize, const HEIGHT: usize> Image<WIDTH, HEIGHT> {
         }
     }
 
+    /// Checks that a decoded size matches the image size.
+    fn check_size(width: usize, height: usize) -> Result<(), DecodeError> {
+        if width != WIDTH || height != HEIGHT {
+            return Err(DecodeError);
+        }
+
+        Ok(())
+    }
+
+    /// Creates an image from a byte array, in the PPM format.
+    ///
+    /// Both ASCII (P3) and binary (P6) images are supported, with any maximum value.
+    ///
+    /// ```
+    /// # use rusttracing::image::*;
+    /// # use rusttracing::color::*;
+    /// let img = Image::<2, 1>::from_ppm(b"P3\n# A comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+    /// assert_eq!(img[1][0], Color::new(0.0, 0.0, 1.0));
+    /// ```
+    ///
+    /// # Errors
+    /// Will error if the data is invalid, or the image is not WIDTH by HEIGHT.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (magic, rest) = read_token(bytes)?;
+        let (width, rest) = read_token(rest)?;
+        let (height, rest) = read_token(rest)?;
+        let (max, rest) = read_token(rest)?;
+
+        let width: usize = width.parse().map_err(|_| DecodeError)?;
+        let height: usize = height.parse().map_err(|_| DecodeError)?;
+        let max: u32 = max.parse().map_err(|_| DecodeError)?;
+        Self::check_size(width, height)?;
+
+        if max == 0 || max > 65535 {
+            return Err(DecodeError);
+        }
+
+        let values: Vec<u32> = match magic {
+            "P3" => {
+                let mut values = Vec::with_capacity(WIDTH * HEIGHT * 3);
+                let mut rest = rest;
+
+                for _i in 0..WIDTH * HEIGHT * 3 {
+                    let (value, next) = read_token(rest)?;
+                    values.push(value.parse().map_err(|_| DecodeError)?);
+                    rest = next;
+                }
+
+                values
+            },
+            "P6" => {
+                // A single whitespace character separates the header from the data.
+                let rest = rest.get(1..).ok_or(DecodeError)?;
+                let size = if max < 256 { 1 } else { 2 };
+
+                (0..WIDTH * HEIGHT * 3).map(|i| read_be(rest, i * size, size)).collect::<Result<_, _>>()?
+            },
+            _ => return Err(DecodeError),
+        };
+
+        let mut img = Self::new();
+        let max = max as f64;
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let i = (y * WIDTH + x) * 3;
+
+                img.data[x][y] = Color { r: values[i] as f64 / max, g: values[i + 1] as f64 / max, b: values[i + 2] as f64 / max };
+            }
+        }
+
+        Ok(img)
+    }
+
+    /// Creates an image from a byte array, in the PNG format.
+    ///
+    /// All color types and bit depths are supported, but interlaced images are not.
+    /// Transparency is ignored.
+    ///
+    /// # Errors
+    /// Will error if the data is invalid, or the image is not WIDTH by HEIGHT.
+    pub fn from_png(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.get(..8) != Some(&[137, 80, 78, 71, 13, 10, 26, 10]) {
+            return Err(DecodeError);
+        }
+
+        let mut header = None;
+        let mut palette = vec![];
+        let mut compressed = vec![];
+
+        // Chunks, each of a length, type, data and checksum.
+        let mut position = 8;
+        loop {
+            let length = read_be(bytes, position, 4)? as usize;
+            let kind = bytes.get(position + 4..position + 8).ok_or(DecodeError)?;
+            let data = bytes.get(position + 8..position + 8 + length).ok_or(DecodeError)?;
+            position += length + 12;
+
+            match kind {
+                b"IHDR" => {
+                    if data.len() != 13 {
+                        return Err(DecodeError);
+                    }
+                    header = Some(data);
+                },
+                b"PLTE" => palette = data.chunks_exact(3).map(|x| [x[0], x[1], x[2]]).collect(),
+                b"IDAT" => compressed.extend_from_slice(data),
+                b"IEND" => break,
+                _ => {},
+            }
+        }
+
+        let header = header.ok_or(DecodeError)?;
+        let width = read_be(header, 0, 4)? as usize;
+        let height = read_be(header, 4, 4)? as usize;
+        let (depth, color_type) = (header[8] as usize, header[9]);
+        Self::check_size(width, height)?;
+
+        // Compression and filter methods must be 0, and the image not interlaced.
+        if header[10] != 0 || header[11] != 0 || header[12] != 0 {
+            return Err(DecodeError);
+        }
+
+        let channels = match (color_type, depth) {
+            (0, 1 | 2 | 4 | 8 | 16) => 1,
+            (2, 8 | 16) => 3,
+            (3, 1 | 2 | 4 | 8) => 1,
+            (4, 8 | 16) => 2,
+            (6, 8 | 16) => 4,
+            _ => return Err(DecodeError),
+        };
+
+        if color_type == 3 && palette.is_empty() {
+            return Err(DecodeError);
+        }
+
+        let raw = zlib(&compressed).map_err(|_| DecodeError)?;
+
+        // Filters work on whole bytes, comparing against the pixel to the left, or the byte to the left if pixels are smaller.
+        let stride = (WIDTH * channels * depth).div_ceil(8);
+        let left = (channels * depth).div_ceil(8);
+        if raw.len() < (stride + 1) * HEIGHT {
+            return Err(DecodeError);
+        }
+
+        let mut previous = vec![0u8; stride];
+        let mut img = Self::new();
+
+        for y in 0..HEIGHT {
+            let filter = raw[y * (stride + 1)];
+            let mut row = raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)].to_vec();
+
+            for i in 0..stride {
+                let a = if i >= left { row[i - left] } else { 0 };
+                let b = previous[i];
+                let c = if i >= left { previous[i - left] } else { 0 };
+
+                let prediction = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    4 => {
+                        let p = a as i16 + b as i16 - c as i16;
+                        let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+
+                        if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+                    },
+                    _ => return Err(DecodeError),
+                };
+
+                row[i] = row[i].wrapping_add(prediction);
+            }
+
+            // A sample at an index along the row, packed most significant bit first when smaller than a byte.
+            let sample = |i: usize| -> u32 {
+                match depth {
+                    16 => (row[i * 2] as u32) << 8 | row[i * 2 + 1] as u32,
+                    8 => row[i] as u32,
+                    _ => (row[i * depth / 8] >> (8 - depth - i * depth % 8)) as u32 & ((1 << depth) - 1),
+                }
+            };
+            let max = ((1u32 << depth) - 1) as f64;
+
+            for x in 0..WIDTH {
+                let i = x * channels;
+
+                img.data[x][y] = match color_type {
+                    3 => {
+                        let [r, g, b] = *palette.get(sample(i) as usize).ok_or(DecodeError)?;
+
+                        Color { r: r as f64 / 255.0, g: g as f64 / 255.0, b: b as f64 / 255.0 }
+                    },
+                    0 | 4 => {
+                        let value = sample(i) as f64 / max;
+
+                        Color { r: value, g: value, b: value }
+                    },
+                    _ => Color { r: sample(i) as f64 / max, g: sample(i + 1) as f64 / max, b: sample(i + 2) as f64 / max },
+                };
+            }
+
+            previous = row;
+        }
+
+        Ok(img)
+    }
+
+    /// Creates an image from a byte array, in the Radiance HDR (RGBE) format.
+    ///
+    /// # Errors
+    /// Will error if the data is invalid, or the image is not WIDTH by HEIGHT.
+    pub fn from_hdr(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (magic, mut rest) = read_line(bytes)?;
+        if !magic.starts_with("#?") {
+            return Err(DecodeError);
+        }
+
+        // Header variables, ending with an empty line.
+        loop {
+            let (line, next) = read_line(rest)?;
+            rest = next;
+
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+                return Err(DecodeError);
+            }
+        }
+
+        // Only the standard orientation, -Y HEIGHT +X WIDTH, is supported.
+        let (resolution, mut rest) = read_line(rest)?;
+        let parts: Vec<&str> = resolution.split_whitespace().collect();
+        if parts.len() != 4 || parts[0] != "-Y" || parts[2] != "+X" {
+            return Err(DecodeError);
+        }
+
+        let height: usize = parts[1].parse().map_err(|_| DecodeError)?;
+        let width: usize = parts[3].parse().map_err(|_| DecodeError)?;
+        Self::check_size(width, height)?;
+
+        let mut img = Self::new();
+        let mut scanline = vec![[0u8; 4]; WIDTH];
+
+        for y in 0..HEIGHT {
+            if WIDTH >= 8 && WIDTH < 32768 && rest.len() >= 4 && rest[0] == 2 && rest[1] == 2 && rest[2] < 128 {
+                // Run length encoded, with each channel stored separately.
+                if ((rest[2] as usize) << 8 | rest[3] as usize) != WIDTH {
+                    return Err(DecodeError);
+                }
+                rest = &rest[4..];
+
+                for channel in 0..4 {
+                    let mut x = 0;
+                    while x < WIDTH {
+                        let count = *rest.first().ok_or(DecodeError)? as usize;
+
+                        if count > 128 {
+                            let count = count - 128;
+                            if x + count > WIDTH || rest.len() < 2 {
+                                return Err(DecodeError);
+                            }
+                            for pixel in &mut scanline[x..x + count] {
+                                pixel[channel] = rest[1];
+                            }
+                            rest = &rest[2..];
+                            x += count;
+                        } else {
+                            if count == 0 || x + count > WIDTH || rest.len() < count + 1 {
+                                return Err(DecodeError);
+                            }
+                            for (pixel, &value) in scanline[x..x + count].iter_mut().zip(&rest[1..count + 1]) {
+                                pixel[channel] = value;
+                            }
+                            rest = &rest[count + 1..];
+                            x += count;
+                        }
+                    }
+                }
+            } else {
+                // Flat, with each pixel stored as 4 bytes.
+                if rest.len() < WIDTH * 4 {
+                    return Err(DecodeError);
+                }
+                for (x, pixel) in scanline.iter_mut().enumerate() {
+                    pixel.copy_from_slice(&rest[x * 4..x * 4 + 4]);
+                }
+                rest = &rest[WIDTH * 4..];
+            }
+
+            for (x, pixel) in scanline.iter().enumerate() {
+                // The shared exponent, offset by 128, and by 8 for the mantissa.
+                let scale = if pixel[3] == 0 { 0.0 } else { 2.0_f64.powi(pixel[3] as i32 - 136) };
+
+                img.data[x][y] = Color { r: pixel[0] as f64 * scale, g: pixel[1] as f64 * scale, b: pixel[2] as f64 * scale };
+            }
+        }
+
+        Ok(img)
+    }
+
+    /// Creates an image from a byte array, in the PFM format.
+    ///
+    /// Both color (PF) and greyscale (Pf) images are supported.
+    ///
+    /// # Errors
+    /// Will error if the data is invalid, or the image is not WIDTH by HEIGHT.
+    pub fn from_pfm(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (magic, rest) = read_line(bytes)?;
+        let channels = match magic {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(DecodeError),
+        };
+
+        let (size, rest) = read_line(rest)?;
+        let parts: Vec<&str> = size.split_whitespace().collect();
+        if parts.len() != 2 {
+            return Err(DecodeError);
+        }
+
+        let width: usize = parts[0].parse().map_err(|_| DecodeError)?;
+        let height: usize = parts[1].parse().map_err(|_| DecodeError)?;
+        Self::check_size(width, height)?;
+
+        // A negative scale marks little endian data.
+        let (scale, rest) = read_line(rest)?;
+        let scale: f64 = scale.parse().map_err(|_| DecodeError)?;
+
+        if rest.len() < WIDTH * HEIGHT * channels * 4 {
+            return Err(DecodeError);
+        }
+
+        let float = |i: usize| {
+            let raw: [u8; 4] = rest[i * 4..i * 4 + 4].try_into().unwrap();
+
+            if scale < 0.0 { f32::from_le_bytes(raw) as f64 } else { f32::from_be_bytes(raw) as f64 }
+        };
+
+        let mut img = Self::new();
+
+        // Rows are stored from bottom to top
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let i = ((HEIGHT - 1 - y) * WIDTH + x) * channels;
+
+                img.data[x][y] = match channels {
+                    3 => Color { r: float(i), g: float(i + 1), b: float(i + 2) },
+                    _ => Color { r: float(i), g: float(i), b: float(i) },
+                };
+            }
+        }
+
+        Ok(img)
+    }
+
+    /// Denoises the image with an edge avoiding à-trous wavelet filter.
+    ///
+    /// The normal, albedo and depth images, such as the passes from [crate::scene::Scene::render],
+    /// guide the filter so that it does not blur across the edges of objects.
+    /// Lighting is separated from albedo while filtering, so that texture detail is kept.
+    ///
+    /// Each iteration doubles the spacing of the filter, so 5 iterations cover a 125 pixel wide area.
+    pub fn denoise(&self, normal: &Self, albedo: &Self, depth: &Self, iterations: usize) -> Self {
+        let demodulate = |x: f64, a: f64| if a.abs() > 0.001 { x / a } else { x };
+
+        let mut lighting = Self::new();
+        for x in 0..WIDTH {
+            for y in 0..HEIGHT {
+                let color = self.data[x][y];
+                let a = albedo.data[x][y];
+
+                lighting.data[x][y] = Color { r: demodulate(color.r, a.r), g: demodulate(color.g, a.g), b: demodulate(color.b, a.b) };
+            }
+        }
+
+        for i in 0..iterations {
+            let step = 1 << i;
+
+            // Lighting becomes smoother each iteration, so is compared more strictly.
+            let lighting_sigma = LIGHTING_SIGMA / step as f64;
+
+            let mut out = Self::new();
+
+            for x in 0..WIDTH {
+                for y in 0..HEIGHT {
+                    let mut sum = Color { r: 0.0, g: 0.0, b: 0.0 };
+                    let mut weights = 0.0;
+
+                    for (kx, hx) in KERNEL.iter().enumerate() {
+                        for (ky, hy) in KERNEL.iter().enumerate() {
+                            let qx = x as isize + (kx as isize - 2) * step;
+                            let qy = y as isize + (ky as isize - 2) * step;
+
+                            if qx < 0 || qy < 0 || qx >= WIDTH as isize || qy >= HEIGHT as isize {
+                                continue;
+                            }
+
+                            let (qx, qy) = (qx as usize, qy as usize);
+
+                            let weight = hx * hy
+                                * (-distance_squared(lighting.data[x][y], lighting.data[qx][qy]) / (lighting_sigma * lighting_sigma)).exp()
+                                * (-distance_squared(normal.data[x][y], normal.data[qx][qy]) / (NORMAL_SIGMA * NORMAL_SIGMA)).exp()
+                                * (-distance_squared(albedo.data[x][y], albedo.data[qx][qy]) / (ALBEDO_SIGMA * ALBEDO_SIGMA)).exp()
+                                * depth_weight(depth.data[x][y].r, depth.data[qx][qy].r, step as f64);
+
+                            sum = sum + lighting.data[qx][qy] * weight;
+                            weights += weight;
+                        }
+                    }
+
+                    // The centre pixel always has a weight, so this is never 0.
+                    out.data[x][y] = sum / weights;
+                }
+            }
+
+            lighting = out;
+        }
+
+        let remodulate = |x: f64, a: f64| if a.abs() > 0.001 { x * a } else { x };
+
+        for x in 0..WIDTH {
+            for y in 0..HEIGHT {
+                let color = lighting.data[x][y];
+                let a = albedo.data[x][y];
+
+                lighting.data[x][y] = Color { r: remodulate(color.r, a.r), g: remodulate(color.g, a.g), b: remodulate(color.b, a.b) };
+            }
+        }
+
+        lighting
+    }
+
     /// Turns the image into a PPM compatible byte vec.
     pub fn to_ppm(&self) -> Vec<u8> {
         let mut out: Vec<u8> = vec![];
@@ -47,6 +556,34 @@ impl<const WIDTH: usize, const HEIGHT: usize> Image<WIDTH, HEIGHT> {
 
         out
     }
+
+    /// Turns the image into a PFM compatible byte vec.
+    ///
+    /// Unlike PPM, colors are stored as floats, so are not limited to between 0 and 1.
+    pub fn to_pfm(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = vec![];
+
+        // PFM header, where the negative scale marks little endian data
+        out.extend_from_slice(format!("PF\n{} {}\n-1.0\n", WIDTH, HEIGHT).as_bytes());
+
+        // Rows are stored from bottom to top
+        for y in (0..HEIGHT).rev() {
+            for x in 0..WIDTH {
+                let color = self.data[x][y];
+                out.extend_from_slice(&(color.r as f32).to_le_bytes());
+                out.extend_from_slice(&(color.g as f32).to_le_bytes());
+                out.extend_from_slice(&(color.b as f32).to_le_bytes());
+            }
+        }
+
+        out
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Default for Image<WIDTH, HEIGHT> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<const WIDTH: usize, const HEIGHT: usize> Index<usize> for Image<WIDTH, HEIGHT> {
@@ -64,3 +601,131 @@ impl<const WIDTH: usize, const HEIGHT: usize> IndexMut<usize> for Image<WIDTH, H
         &mut self.data[i]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denoise() {
+        let mut img: Image<8, 8> = Image::new();
+        let mut normal: Image<8, 8> = Image::new();
+        let albedo: Image<8, 8> = Image { data: vec![vec![Color::new(0.5, 0.5, 0.5); 8]; 8] };
+        let depth: Image<8, 8> = Image { data: vec![vec![Color::new(1.0, 1.0, 1.0); 8]; 8] };
+
+        // Left half noisy, right half a separate flat surface.
+        for x in 0..8 {
+            for y in 0..8 {
+                img[x][y] = if x < 4 { Color::new(0.1 * ((x + y) % 2) as f64 + 0.2, 0.2, 0.2) } else { Color::new(0.9, 0.9, 0.9) };
+                normal[x][y] = if x < 4 { Color::new(0.0, 1.0, 0.0) } else { Color::new(1.0, 0.0, 0.0) };
+            }
+        }
+
+        let out = img.denoise(&normal, &albedo, &depth, 3);
+
+        assert!((out[1][1].r - out[1][2].r).abs() < (img[1][1].r - img[1][2].r).abs());
+        assert!((out[6][6].r - 0.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pfm() {
+        let mut img: Image<3, 2> = Image::new();
+        img[2][0] = Color { r: 1.5, g: -2.0, b: 100.0 };
+        img[0][1] = Color::new(0.25, 0.5, 0.75);
+
+        let decoded = Image::<3, 2>::from_pfm(&img.to_pfm()).unwrap();
+
+        assert_eq!(decoded.data, img.data);
+        assert!(Image::<2, 3>::from_pfm(&img.to_pfm()).is_err());
+    }
+
+    #[test]
+    fn hdr() {
+        let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+
+        // Run length encoded scanline, with blue stored as literals and all other channels as runs.
+        bytes.extend_from_slice(&[2, 2, 0, 8]);
+        bytes.extend_from_slice(&[136, 128]);
+        bytes.extend_from_slice(&[132, 64, 132, 0]);
+        bytes.extend_from_slice(&[8, 0, 0, 0, 0, 0, 0, 0, 255]);
+        bytes.extend_from_slice(&[136, 129]);
+
+        let img = Image::<8, 1>::from_hdr(&bytes).unwrap();
+
+        assert_eq!(img[0][0], Color { r: 1.0, g: 0.5, b: 0.0 });
+        assert_eq!(img[7][0], Color { r: 1.0, g: 0.0, b: 1.9921875 });
+    }
+
+    #[test]
+    fn ppm() {
+        let mut img: Image<3, 2> = Image::new();
+        img[2][0] = Color::new(0.2, 0.4, 1.0);
+        img[0][1] = Color::new(1.0, 0.0, 0.6);
+
+        let decoded = Image::<3, 2>::from_ppm(&img.to_ppm()).unwrap();
+        assert_eq!(decoded.data, img.data);
+
+        // Two bytes per sample above a maximum of 255.
+        let mut bytes = b"P6 1 1 65535\n".to_vec();
+        bytes.extend_from_slice(&[255, 255, 0, 0, 128, 0]);
+
+        let decoded = Image::<1, 1>::from_ppm(&bytes).unwrap();
+        assert_eq!(decoded[0][0], Color { r: 1.0, g: 0.0, b: 32768.0 / 65535.0 });
+        assert!(Image::<2, 1>::from_ppm(&bytes).is_err());
+    }
+
+    #[test]
+    fn png() {
+        // Each row uses a different filter.
+        let bytes = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 3, 0, 0, 0, 5,
+            8, 2, 0, 0, 0, 15, 19, 193, 245, 0, 0, 0, 45, 73, 68, 65, 84, 120, 218, 99, 96, 144, 179, 9,
+            200, 235, 89, 176, 239, 14, 163, 81, 64, 94, 0, 24, 48, 25, 193, 0, 115, 74, 113, 147, 163, 163, 227, 65,
+            71, 71, 22, 48, 63, 32, 192, 200, 8, 0, 115, 178, 13, 216, 236, 96, 214, 48, 0, 0, 0, 0, 73, 69,
+            78, 68, 174, 66, 96, 130,
+        ];
+
+        let img = Image::<3, 5>::from_png(&bytes).unwrap();
+
+        for x in 0..3 {
+            for y in 0..5 {
+                let value = |c: usize| ((x * 80 + y * 50 + c * 30) % 256) as f64 / 255.0;
+
+                assert_eq!(img[x][y], Color { r: value(0), g: value(1), b: value(2) });
+            }
+        }
+    }
+
+    #[test]
+    fn png_palette() {
+        // 2 bit indices into a red, green, blue palette.
+        let bytes = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 3, 0, 0, 0, 1,
+            2, 3, 0, 0, 0, 102, 142, 252, 39, 0, 0, 0, 9, 80, 76, 84, 69, 255, 0, 0, 0, 255, 0, 0,
+            0, 255, 45, 74, 205, 138, 0, 0, 0, 10, 73, 68, 65, 84, 120, 218, 99, 104, 1, 0, 0, 134, 0, 133,
+            17, 125, 183, 91, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+        ];
+
+        let img = Image::<3, 1>::from_png(&bytes).unwrap();
+
+        assert_eq!(img[0][0], Color::new(0.0, 0.0, 1.0));
+        assert_eq!(img[1][0], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(img[2][0], Color::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn png_grey() {
+        // 16 bit greyscale.
+        let bytes = [
+            137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 1,
+            16, 0, 0, 0, 0, 129, 217, 252, 21, 0, 0, 0, 13, 73, 68, 65, 84, 120, 218, 99, 248, 255, 191, 129,
+            1, 0, 7, 254, 2, 127, 173, 131, 146, 37, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+        ];
+
+        let img = Image::<2, 1>::from_png(&bytes).unwrap();
+
+        assert_eq!(img[0][0], Color::new(1.0, 1.0, 1.0));
+        assert_eq!(img[1][0].g, 32768.0 / 65535.0);
+        assert!(Image::<1, 2>::from_png(&bytes).is_err());
+    }
+}
@@ -0,0 +1,318 @@
+use std::fmt;
+
+/// The base lengths of length codes 257 to 285.
+const LENGTH_BASE: [usize; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+
+/// The extra bits following length codes 257 to 285.
+const LENGTH_EXTRA: [u32; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+
+/// The base distances of distance codes 0 to 29.
+const DISTANCE_BASE: [usize; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+
+/// The extra bits following distance codes 0 to 29.
+const DISTANCE_EXTRA: [u32; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// The order in which code length code lengths are stored in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+#[derive(Clone, Copy, Debug)]
+/// Invalid or truncated compressed data.
+pub struct InflateError;
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid or truncated compressed data")
+    }
+}
+
+/// Reads bits from a byte array, least significant bit first.
+struct Bits<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Bits<'_> {
+    fn bit(&mut self) -> Result<u32, InflateError> {
+        let byte = *self.bytes.get(self.position / 8).ok_or(InflateError)?;
+        let bit = (byte >> (self.position % 8)) & 1;
+        self.position += 1;
+
+        Ok(bit as u32)
+    }
+
+    /// Reads a number of bits, with the first bit read as the least significant.
+    fn bits(&mut self, count: u32) -> Result<u32, InflateError> {
+        let mut out = 0;
+        for i in 0..count {
+            out |= self.bit()? << i;
+        }
+
+        Ok(out)
+    }
+
+    /// Skips to the start of the next byte.
+    fn align(&mut self) {
+        self.position = self.position.div_ceil(8) * 8;
+    }
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length,
+/// and the symbols in order of their codes.
+struct Huffman {
+    counts: [usize; 16],
+    symbols: Vec<usize>,
+}
+
+impl Huffman {
+    /// Creates a code from the length of the code for each symbol, where 0 is unused.
+    fn new(lengths: &[usize]) -> Result<Self, InflateError> {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[length] += 1;
+        }
+        counts[0] = 0;
+
+        // Over-subscribed codes cannot be decoded.
+        let mut left: isize = 1;
+        for &count in &counts[1..] {
+            left = left * 2 - count as isize;
+            if left < 0 {
+                return Err(InflateError);
+            }
+        }
+
+        let mut offsets = [0; 16];
+        for i in 1..15 {
+            offsets[i + 1] = offsets[i] + counts[i];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length]] = symbol;
+                offsets[length] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    /// Decodes one symbol, reading a bit at a time.
+    fn decode(&self, bits: &mut Bits) -> Result<usize, InflateError> {
+        // The first code of the current length, and the index of its symbol.
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+
+        for &count in &self.counts[1..] {
+            code |= bits.bit()? as usize;
+
+            if code < first + count {
+                return Ok(self.symbols[index + code - first]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(InflateError)
+    }
+}
+
+/// Decodes the literals and matches of a compressed block, until the end of block symbol.
+fn codes(bits: &mut Bits, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> Result<(), InflateError> {
+    loop {
+        let symbol = literals.decode(bits)?;
+
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let symbol = symbol - 257;
+                if symbol >= 29 {
+                    return Err(InflateError);
+                }
+                let length = LENGTH_BASE[symbol] + bits.bits(LENGTH_EXTRA[symbol])? as usize;
+
+                let symbol = distances.decode(bits)?;
+                if symbol >= 30 {
+                    return Err(InflateError);
+                }
+                let distance = DISTANCE_BASE[symbol] + bits.bits(DISTANCE_EXTRA[symbol])? as usize;
+
+                if distance > out.len() {
+                    return Err(InflateError);
+                }
+
+                // Copied a byte at a time, as the match may overlap itself.
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            },
+        }
+    }
+}
+
+/// Reads the code lengths of a dynamic block, returning the literal and distance codes.
+fn dynamic(bits: &mut Bits) -> Result<(Huffman, Huffman), InflateError> {
+    let literal_count = bits.bits(5)? as usize + 257;
+    let distance_count = bits.bits(5)? as usize + 1;
+    let code_length_count = bits.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[i] = bits.bits(3)? as usize;
+    }
+    let code_lengths = Huffman::new(&code_lengths)?;
+
+    let mut lengths = vec![];
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code_lengths.decode(bits)?;
+
+        let (length, repeat) = match symbol {
+            0..=15 => (symbol, 1),
+            16 => (*lengths.last().ok_or(InflateError)?, 3 + bits.bits(2)? as usize),
+            17 => (0, 3 + bits.bits(3)? as usize),
+            _ => (0, 11 + bits.bits(7)? as usize),
+        };
+
+        lengths.extend(std::iter::repeat_n(length, repeat));
+    }
+
+    if lengths.len() != literal_count + distance_count || lengths[256] == 0 {
+        return Err(InflateError);
+    }
+
+    Ok((Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?))
+}
+
+/// Decompresses raw DEFLATE data.
+///
+/// ```
+/// # use rusttracing::inflate::*;
+/// // A single stored block.
+/// let data = [1, 3, 0, 252, 255, 1, 2, 3];
+/// assert_eq!(inflate(&data).unwrap(), vec![1, 2, 3]);
+/// ```
+///
+/// # Errors
+/// Will error if the data is invalid or ends early.
+pub fn inflate(bytes: &[u8]) -> Result<Vec<u8>, InflateError> {
+    let mut bits = Bits { bytes, position: 0 };
+    let mut out = vec![];
+
+    loop {
+        let last = bits.bit()? == 1;
+
+        match bits.bits(2)? {
+            // Stored
+            0 => {
+                bits.align();
+                let start = bits.position / 8;
+                let header = bytes.get(start..start + 4).ok_or(InflateError)?;
+
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                let complement = u16::from_le_bytes([header[2], header[3]]);
+                if length != !complement {
+                    return Err(InflateError);
+                }
+
+                let data = bytes.get(start + 4..start + 4 + length as usize).ok_or(InflateError)?;
+                out.extend_from_slice(data);
+                bits.position = (start + 4 + length as usize) * 8;
+            },
+            // Fixed Huffman codes
+            1 => {
+                let mut lengths = [0; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+
+                codes(&mut bits, &mut out, &Huffman::new(&lengths)?, &Huffman::new(&[5; 30])?)?;
+            },
+            // Dynamic Huffman codes
+            2 => {
+                let (literals, distances) = dynamic(&mut bits)?;
+
+                codes(&mut bits, &mut out, &literals, &distances)?;
+            },
+            _ => return Err(InflateError),
+        }
+
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+/// Decompresses zlib wrapped DEFLATE data, checking the header and checksum.
+///
+/// # Errors
+/// Will error if the data is invalid, ends early, or fails the checksum.
+pub fn zlib(bytes: &[u8]) -> Result<Vec<u8>, InflateError> {
+    if bytes.len() < 6 {
+        return Err(InflateError);
+    }
+
+    // Only DEFLATE without a preset dictionary is supported.
+    let (method, flags) = (bytes[0], bytes[1]);
+    if method & 15 != 8 || flags & 32 != 0 || !((method as u16) << 8 | flags as u16).is_multiple_of(31) {
+        return Err(InflateError);
+    }
+
+    let out = inflate(&bytes[2..])?;
+
+    let checksum = u32::from_be_bytes(bytes[bytes.len() - 4..].try_into().unwrap());
+    if adler32(&out) != checksum {
+        return Err(InflateError);
+    }
+
+    Ok(out)
+}
+
+/// The Adler-32 checksum used by zlib.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed() {
+        let data = [120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 39, 1, 104, 3, 8, 177];
+
+        assert_eq!(zlib(&data).unwrap(), b"hello hello hello hello");
+    }
+
+    #[test]
+    fn dynamic() {
+        let data = [
+            120, 218, 5, 193, 9, 14, 0, 16, 12, 4, 192, 183, 174, 58, 86, 66, 85, 74, 125, 223, 12, 88, 61, 44,
+            65, 93, 31, 214, 217, 185, 220, 140, 185, 104, 42, 13, 189, 135, 4, 249, 10, 198, 7, 87, 20, 16, 177,
+        ];
+        let expected: Vec<u8> = (0..40).map(|i: usize| ((i * i * 7 + i / 3) % 23 + 97) as u8).collect();
+
+        assert_eq!(zlib(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut data = [120, 218, 203, 72, 205, 201, 201, 87, 200, 64, 39, 1, 104, 3, 8, 177];
+        data[15] ^= 1;
+
+        assert!(zlib(&data).is_err());
+        assert!(zlib(&data[..8]).is_err());
+        assert!(inflate(&[7]).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::matrix::Matrix;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::mesh::Material;
+use crate::medium::Medium;
+
+use std::ops::*;
+use std::sync::Arc;
+
+/// Applies a 4x4 affine matrix to a vector, with w of 1 for points and 0 for directions.
+fn apply<T: Copy + From<f64> + Add<Output = T> + Mul<Output = T>>(mat: &Matrix<T>, vec: Vec3<T>, w: f64) -> Vec3<T> {
+    let w: T = w.into();
+
+    Vec3::new(
+        mat[0][0] * vec.x + mat[0][1] * vec.y + mat[0][2] * vec.z + mat[0][3] * w,
+        mat[1][0] * vec.x + mat[1][1] * vec.y + mat[1][2] * vec.z + mat[1][3] * w,
+        mat[2][0] * vec.x + mat[2][1] * vec.y + mat[2][2] * vec.z + mat[2][3] * w,
+    )
+}
+
+/// The mirror reflection of a direction about a normal.
+fn reflect(direction: Vec3<f64>, normal: Vec3<f64>) -> Vec3<f64> {
+    let (direction, normal) = (direction.unit(), normal.unit());
+
+    direction - normal * (normal * direction * 2.0)
+}
+
+/// A copy of shared geometry, placed with its own transform.
+///
+/// Rays are transformed into the space of the geometry, so the geometry itself is never changed,
+/// and any number of instances can share one [Arc].
+///
+/// Implements the Raytrace trait.
+pub struct Instance<T, G: ?Sized> {
+    /// The shared geometry.
+    pub geometry: Arc<G>,
+
+    /// Replaces the color and roughness of the geometry, if set.
+    pub material: Option<Material>,
+
+    /// The 4x4 affine transform from the space of the geometry to the scene.
+    transform: Matrix<T>,
+
+    /// The inverse of transform, from the scene to the space of the geometry.
+    inverse: Matrix<T>,
+}
+
+impl<T: Copy + From<f64> + From<i32> + Into<f64> + Neg<Output = T> + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>, G: ?Sized> Instance<T, G> {
+    /// Creates an instance of the geometry, with no transform.
+    pub fn new(geometry: Arc<G>) -> Self {
+        Instance { geometry, material: None, transform: Matrix::ident(4), inverse: Matrix::ident(4) }
+    }
+
+    /// Sets the material, replacing that of the geometry.
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = Some(material);
+
+        self
+    }
+
+    /// Applies a transform after the current transform, given with its inverse.
+    fn then(mut self, mat: Matrix<T>, inverse: Matrix<T>) -> Self {
+        self.transform = (mat * self.transform).unwrap();
+        self.inverse = (self.inverse * inverse).unwrap();
+
+        self
+    }
+
+    /// Embeds a 3x3 matrix into a 4x4 affine matrix.
+    fn affine(mat: Matrix<T>) -> Matrix<T> {
+        let mut out = Matrix::ident(4);
+
+        for i in 0..3 {
+            for j in 0..3 {
+                out[i][j] = mat[i][j];
+            }
+        }
+
+        out
+    }
+
+    /// Moves the instance by a set vector.
+    pub fn translate(self, offset: Vec3<T>) -> Self {
+        let mut mat = Matrix::ident(4);
+        let mut inverse = Matrix::ident(4);
+
+        mat[0][3] = offset.x;
+        mat[1][3] = offset.y;
+        mat[2][3] = offset.z;
+
+        inverse[0][3] = -offset.x;
+        inverse[1][3] = -offset.y;
+        inverse[2][3] = -offset.z;
+
+        self.then(mat, inverse)
+    }
+
+    /// Scales the instance by a set vector from the origin.
+    pub fn scale(self, factor: Vec3<T>) -> Self {
+        let mut mat = Matrix::ident(4);
+        let mut inverse = Matrix::ident(4);
+
+        mat[0][0] = factor.x;
+        mat[1][1] = factor.y;
+        mat[2][2] = factor.z;
+
+        inverse[0][0] = T::from(1.0) / factor.x;
+        inverse[1][1] = T::from(1.0) / factor.y;
+        inverse[2][2] = T::from(1.0) / factor.z;
+
+        self.then(mat, inverse)
+    }
+
+    /// Rotates the instance by a rotation vector.
+    pub fn rotate(self, rot: Vec3<T>) -> Self {
+        let rotation = <Vec3<T> as Into<Matrix<T>>>::into(rot);
+
+        // The inverse of a rotation is its transpose.
+        let inverse = Self::affine(rotation.transpose());
+
+        self.then(Self::affine(rotation), inverse)
+    }
+
+    /// The 4x4 affine transform from the space of the geometry to the scene.
+    pub fn matrix(&self) -> &Matrix<T> {
+        &self.transform
+    }
+
+    /// The 4x4 affine transform from the scene to the space of the geometry.
+    pub fn inverse(&self) -> &Matrix<T> {
+        &self.inverse
+    }
+
+    /// The ray in the space of the geometry,
+    /// and the distance in that space covered by each unit of distance along the ray.
+    fn local(&self, ray: &Ray<T>) -> (Ray<T>, T) {
+        let direction = apply(&self.inverse, ray.direction, 0.0);
+
+        (Ray::new(apply(&self.inverse, ray.origin, 1.0), direction).with_time(ray.time), direction.length())
+    }
+
+    /// The determinant of the 3x3 part of the inverse, the change in volume into the space of the geometry.
+    fn inverse_determinant(&self) -> f64 {
+        let m = |i: usize, j: usize| -> f64 { self.inverse[i][j].into() };
+
+        m(0, 0) * (m(1, 1) * m(2, 2) - m(1, 2) * m(2, 1))
+            - m(0, 1) * (m(1, 0) * m(2, 2) - m(1, 2) * m(2, 0))
+            + m(0, 2) * (m(1, 0) * m(2, 1) - m(1, 1) * m(2, 0))
+    }
+
+    /// The mirror reflection in the scene, and the mirror reflection of the geometry transformed into the scene.
+    ///
+    /// These only differ when the transform does not keep angles, such as when scaling unevenly.
+    fn mirrors(&self, ray: &Ray<T>, local: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> where G: Raytrace<T> {
+        let local_normal = self.geometry.normal(local)?.to_f64();
+        let normal = apply(&self.inverse.transpose(), Vec3::from_f64(local_normal), 0.0).to_f64();
+
+        let mapped = apply(&self.transform, Vec3::from_f64(reflect(local.direction.to_f64(), local_normal)), 0.0).to_f64().unit();
+
+        Some((reflect(ray.direction.to_f64(), normal), mapped))
+    }
+}
+
+impl<T: PartialOrd + From<f64> + From<i32> + Into<f64> + Copy + Neg<Output = T> + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, G: Raytrace<T> + ?Sized> Raytrace<T> for Instance<T, G> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        let (local, scale) = self.local(ray);
+
+        Some(self.geometry.intersects_along(&local)? / scale)
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        let (local, scale) = self.local(ray);
+
+        Some(self.geometry.intervals(&local)?.into_iter().map(|(enter, exit)| (enter / scale, exit / scale)).collect())
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.geometry.medium()
+    }
+
+    /// Only given without a material, as the material replaces the surface of the geometry.
+    fn emission(&self) -> Option<Color> {
+        match self.material {
+            Some(_) => None,
+            None => self.geometry.emission(),
+        }
+    }
+
+    fn density(&self, point: &Vec3<T>, time: f64) -> Option<f64> {
+        self.geometry.density(&apply(&self.inverse, *point, 1.0), time)
+    }
+
+    /// Transmits in the space of the geometry, or reflects with the material if it is set.
+    ///
+    /// The direction is rotated so that mirror reflections stay correct in the scene.
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        if let Some(material) = self.material {
+            let pos = self.intersects_at(ray)?;
+            let normal = self.normal(ray)?.to_f64();
+
+            return Some(Ray::new(pos, Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), normal, material.roughness))).with_time(ray.time));
+        }
+
+        let (local, _scale) = self.local(ray);
+        let out = self.geometry.transmit(&local)?;
+
+        let mut direction = apply(&self.transform, out.direction, 0.0).to_f64();
+
+        if let Some((mirror, mapped)) = self.mirrors(ray, &local) {
+            direction = rotate_between(mapped, mirror, direction);
+        }
+
+        Some(Ray::new(apply(&self.transform, out.origin, 1.0), Vec3::from_f64(direction)).with_time(ray.time))
+    }
+
+    /// Transformed by the inverse transpose, to stay perpendicular to the surface.
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        let normal = self.geometry.normal(&self.local(ray).0)?;
+
+        Some(apply(&self.inverse.transpose(), normal, 0.0).unit())
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.geometry.uv(&self.local(ray).0)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        let (du, dv) = self.geometry.uv_tangents(&self.local(ray).0)?;
+
+        Some((
+            apply(&self.transform, Vec3::from_f64(du), 0.0).to_f64(),
+            apply(&self.transform, Vec3::from_f64(dv), 0.0).to_f64(),
+        ))
+    }
+
+    /// Includes the change in solid angle when transforming directions into the space of the geometry.
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        if let Some(material) = self.material {
+            let normal = self.normal(ray)?.to_f64();
+
+            return reflection_pdf(ray.direction.to_f64(), normal, material.roughness, direction.to_f64());
+        }
+
+        let (local, _scale) = self.local(ray);
+
+        let mut direction = direction.to_f64().unit();
+        if let Some((mirror, mapped)) = self.mirrors(ray, &local) {
+            direction = rotate_between(mirror, mapped, direction);
+        }
+
+        let local_direction = apply(&self.inverse, Vec3::from_f64(direction), 0.0).to_f64();
+        let length = local_direction.length();
+
+        let pdf = self.geometry.scatter_pdf(&local, &Vec3::from_f64(local_direction / length))?;
+
+        Some(pdf * self.inverse_determinant().abs() / (length * length * length))
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        match self.material {
+            Some(material) => color * material.color,
+            None => self.geometry.recolor(&self.local(ray).0, color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn sphere() {
+        let unit = Arc::new(Sphere { roughness: 0.0, ..Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0_f64, Color::new(0.5, 0.5, 0.5)) });
+        let instance = Instance::new(unit.clone()).scale(Vec3::new(2.0, 2.0, 2.0)).translate(Vec3::new(0.0, 0.0, 5.0));
+        let sphere = Sphere { roughness: 0.0, ..Sphere::new(Vec3::new(0.0, 0.0, 5.0), 2.0, Color::new(0.5, 0.5, 0.5)) };
+
+        assert_eq!(Arc::strong_count(&unit), 2);
+
+        let ray = Ray::new(Vec3::new(0.5, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        assert!((instance.intersects_along(&ray).unwrap() - sphere.intersects_along(&ray).unwrap()).abs() < 1e-9);
+        assert!((instance.normal(&ray).unwrap() - sphere.normal(&ray).unwrap()).length() < 1e-9);
+
+        let (a, b) = (instance.transmit(&ray).unwrap(), sphere.transmit(&ray).unwrap());
+        assert!((a.origin - b.origin).length() < 1e-9);
+        assert!((a.direction - b.direction).length() < 1e-9);
+
+        // Keeps the time of the ray, with or without a material.
+        let later = ray.with_time(0.4);
+        assert_eq!(instance.transmit(&later).unwrap().time, 0.4);
+        assert_eq!(instance.with_material(Material::new(Color::new(1.0, 1.0, 1.0), 0.0)).transmit(&later).unwrap().time, 0.4);
+    }
+
+    #[test]
+    fn pdf() {
+        let rough = Sphere { roughness: 0.5, ..Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, Color::new(0.5, 0.5, 0.5)) };
+        let instance = Instance::new(Arc::new(rough)).scale(Vec3::new(3.0, 3.0, 3.0)).rotate(Vec3::new(0.0, 90.0, 0.0)).translate(Vec3::new(1.0, 0.0, 5.0));
+        let sphere = Sphere { roughness: 0.5, ..Sphere::new(Vec3::new(1.0, 0.0, 5.0), 3.0, Color::new(0.5, 0.5, 0.5)) };
+
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let normal = sphere.normal(&ray).unwrap();
+        let direction = (reflect(ray.direction, normal) + Vec3::new(0.2, -0.1, 0.15)).unit();
+
+        let (a, b) = (instance.scatter_pdf(&ray, &direction).unwrap(), sphere.scatter_pdf(&ray, &direction).unwrap());
+        assert!(b > 0.0);
+        assert!((a - b).abs() < 1e-6 * b);
+    }
+
+    #[test]
+    fn uneven() {
+        let unit = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0_f64, Color::new(0.5, 0.5, 0.5)));
+        let instance = Instance::new(unit).scale(Vec3::new(4.0, 1.0, 1.0));
+
+        let along = Ray::new(Vec3::new(-10.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!((instance.intersects_along(&along).unwrap() - 6.0).abs() < 1e-9);
+
+        // The normal of the ellipsoid x²/16 + y² = 1, at (2, √3/2).
+        let ray = Ray::new(Vec3::new(2.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let expected = Vec3::new(2.0 / 16.0, 3.0_f64.sqrt() / 2.0, 0.0).unit();
+        assert!((instance.normal(&ray).unwrap() - expected).length() < 1e-9);
+
+        // A material with no roughness reflects as a mirror.
+        let mirror = instance.with_material(Material::new(Color::new(1.0, 1.0, 1.0), 0.0));
+        let out = mirror.transmit(&ray).unwrap();
+        assert!((out.direction - reflect(ray.direction, expected)).length() < 1e-9);
+    }
+}
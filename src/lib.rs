@@ -2,6 +2,10 @@
 
 #![allow(dead_code)]
 
+// Lints raised by long-standing code, left in its original style.
+#![allow(clippy::needless_lifetimes, clippy::redundant_field_names, clippy::new_without_default, clippy::erasing_op, clippy::identity_op)]
+#![allow(clippy::single_match, clippy::collapsible_match, clippy::needless_borrow, clippy::manual_map)]
+
 //! A library consisting of raytracing utility functions.
 
 /// A vector in 3 dimension, and utility functions.
@@ -19,12 +23,78 @@ pub mod sphere;
 /// A plane, with the Raytrace trait.
 pub mod plane;
 
+/// An exact axis-aligned box, with the Raytrace trait.
+pub mod cuboid;
+
+/// A flat disk, or annulus, with the Raytrace trait.
+pub mod disk;
+
+/// A capped cylinder, with the Raytrace trait.
+pub mod cylinder;
+
+/// A capped cone, or frustum, with the Raytrace trait.
+pub mod cone;
+
+/// A capsule, with the Raytrace trait.
+pub mod capsule;
+
+/// A torus, with the Raytrace trait.
+pub mod torus;
+
+/// A flat parallelogram, with the Raytrace trait.
+pub mod quad;
+
+/// A bilinear patch between four corners, with the Raytrace trait.
+pub mod bilinear;
+
+/// Area lights, giving off light from the surface of a shape.
+pub mod light;
+
 /// A triangle, storing a plane, with the Raytrace trait.
 pub mod tri;
 
+/// An axis-aligned bounding box, intersected by the slab method.
+pub mod aabb;
+
+/// A bounding volume hierarchy, over the bounds of many items.
+pub mod bvh;
+
+/// Cubic Bézier curves, such as hair and grass, with the Raytrace trait.
+pub mod curve;
+
+/// Terrain from a grid of heights, with the Raytrace trait.
+pub mod heightfield;
+
+/// Signed distance fields, rendered by sphere tracing.
+pub mod sdf;
+
 /// A collection of tris, which implement the Raytrace trait.
 pub mod object;
 
+/// Bicubic Bézier patches, tessellated into objects.
+pub mod bezier;
+
+/// Loop and Catmull-Clark subdivision of polygon meshes.
+pub mod subdivision;
+
+/// An indexed triangle mesh, sharing vertices between faces, with the Raytrace trait.
+pub mod mesh;
+
+/// Instances of shared geometry, each with its own transform.
+pub mod instance;
+
+/// Moving geometry, blurred across the shutter of the camera.
+pub mod motion;
+
+/// Constructive solid geometry, combining the volumes of objects.
+pub mod csg;
+
+/// Participating media, such as fog, which absorb and scatter light within a volume.
+pub mod medium;
+
+/// Voxel grids of density, such as smoke and clouds, with the Raytrace trait.
+pub mod voxel;
+
 /// A camera, consisting of an origin and a rotation matrix.
 pub mod camera;
 
@@ -36,8 +106,38 @@ pub mod scene;
 /// A matrix, of any size, and utility functions.
 pub mod matrix;
 
+/// Real roots of polynomials, used to intersect curved surfaces.
+pub mod polynomial;
+
 /// A color, in RGB.
 pub mod color;
 
 /// A 2D array of colors.
 pub mod image;
+
+/// DEFLATE and zlib decompression, used to decode PNG images.
+pub mod inflate;
+
+/// The light surrounding a scene, from a constant color, gradient, or image.
+pub mod environment;
+
+/// Textures, giving colors which vary across a surface.
+pub mod texture;
+
+/// Normal and bump maps, giving surface detail without geometry.
+pub mod bump;
+
+/// Seedable gradient noise, and solid textures built from it.
+pub mod noise;
+
+/// An analytic daylight sky and sun, for use as an environment.
+pub mod sky;
+
+/// Reconstruction filters, weighting samples within a pixel.
+pub mod filter;
+
+/// Sampling strategies, deciding how many samples each pixel takes.
+pub mod sampling;
+
+/// Auxiliary images, rendered alongside a scene.
+pub mod pass;
@@ -0,0 +1,179 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+
+use std::ops::*;
+
+/// Area light.
+///
+/// Gives off light evenly from the front of a shape, the side its normal faces,
+/// such as a [crate::quad::Quad] or a [crate::bilinear::BilinearPatch].
+///
+/// The light is sampled directly from every surface which gives its scatter_pdf,
+/// and combined with paths which hit it through multiple importance sampling.
+///
+/// Absorbs all light falling on it.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AreaLight<S> {
+    /// The shape giving off light.
+    pub shape: S,
+
+    /// The light given off, which may be brighter than 1, as with [Color::new_emission].
+    pub emission: Color,
+}
+
+impl<S> AreaLight<S> {
+    /// Default constructor.
+    pub fn new(shape: S, emission: Color) -> Self {
+        AreaLight { shape, emission }
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T> + SampleArea> Raytrace<T> for AreaLight<S> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        self.shape.intersects_along(ray)
+    }
+
+    fn transmit(&self, _ray: &Ray<T>) -> Option<Ray<T>> {
+        None
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.shape.normal(ray)
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.shape.uv(ray)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.shape.uv_tangents(ray)
+    }
+
+    fn emission(&self) -> Option<Color> {
+        Some(self.emission)
+    }
+
+    fn sample_light(&self, from: &Vec3<T>, u: f64, v: f64) -> Option<(Vec3<f64>, f64)> {
+        self.shape.sample_toward(from.to_f64(), u, v)
+    }
+
+    /// Converts the density over area of the shape to density over solid angle from the origin of the ray.
+    fn light_pdf(&self, ray: &Ray<T>) -> Option<f64> {
+        let (u, v) = self.shape.uv(ray)?;
+        let distance: f64 = self.shape.intersects_along(ray)?.into();
+        let normal = self.shape.normal(ray)?.to_f64();
+
+        let cos = (normal * ray.direction.to_f64().unit()).abs();
+        if cos == 0.0 {
+            return None;
+        }
+
+        let length = distance * ray.direction.to_f64().length();
+
+        Some(self.shape.pdf_area(u, v) * length * length / cos)
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, _color: Color) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quad::Quad;
+    use crate::bilinear::BilinearPatch;
+
+    #[test]
+    fn light_pdf() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let from = Vec3::new(0.3, -2.0, 0.4);
+
+        let quad = AreaLight::new(Quad::new(Vec3::new(-1.0, 1.0, -1.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.5, 2.0), white), white);
+        let patch = AreaLight::new(BilinearPatch::new(
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.5, -1.0),
+            Vec3::new(-1.0, 1.0, 1.0),
+            Vec3::new(1.0, 2.5, 1.0),
+            white,
+        ), white);
+
+        let lights: [&dyn Raytrace<f64>; 2] = [&quad, &patch];
+
+        // The density of choosing a direction is found again from a ray along it.
+        for light in lights {
+            for (u, v) in [(0.1, 0.2), (0.5, 0.5), (0.9, 0.7)] {
+                let (direction, pdf) = light.sample_light(&from, u, v).unwrap();
+                let ray = Ray::new(from, direction);
+
+                assert!((light.light_pdf(&ray).unwrap() - pdf).abs() < pdf * 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn area_light() {
+        use crate::plane::Plane;
+        use crate::scene::Scene;
+        use crate::camera::Camera;
+        use crate::filter::Filter;
+        use crate::sampling::Sampling;
+
+        // Gives off the same light as the shape within, but cannot be sampled directly.
+        struct Unsampled<R>(R);
+
+        impl<R: Raytrace<f64>> Raytrace<f64> for Unsampled<R> {
+            fn intersects_along(&self, ray: &Ray<f64>) -> Option<f64> {
+                self.0.intersects_along(ray)
+            }
+
+            fn transmit(&self, ray: &Ray<f64>) -> Option<Ray<f64>> {
+                self.0.transmit(ray)
+            }
+
+            fn recolor(&self, ray: &Ray<f64>, color: Color) -> Color {
+                self.0.recolor(ray, color)
+            }
+
+            fn normal(&self, ray: &Ray<f64>) -> Option<Vec3<f64>> {
+                self.0.normal(ray)
+            }
+
+            fn emission(&self) -> Option<Color> {
+                self.0.emission()
+            }
+        }
+
+        // A floor lit only by a square light above, facing down.
+        let scene = |sampled: bool| {
+            let light = AreaLight::new(Quad::new(Vec3::new(-1.0, 1.0, -1.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), Color::new(1.0, 1.0, 1.0)), Color::new(1.0, 1.0, 1.0));
+            let light: Box<dyn Raytrace<f64> + Sync> = if sampled { Box::new(light) } else { Box::new(Unsampled(light)) };
+
+            Scene::<f64> {
+                objects: vec![Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(0.5, 0.5, 0.5))), light],
+                camera: Camera::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0)),
+                environment: Box::new(Color::new(0.0, 0.0, 0.0)),
+                filter: Filter::default(),
+                sampling: Sampling::Fixed,
+                fog: None,
+            }
+        };
+
+        let ray = Ray::new(Vec3::new(0.3, 0.5, 0.2), Vec3::new(0.0, -1.0, 0.0));
+        let mean = |scene: &Scene<f64>| (0..40000).map(|_i| scene.trace_bounce(ray, 4).r).sum::<f64>() / 40000.0;
+
+        // Sampling the light directly gives the same light as waiting for paths to hit it.
+        let (sampled, hit) = (mean(&scene(true)), mean(&scene(false)));
+
+        assert!(sampled > 0.1);
+        assert!((sampled - hit).abs() < sampled * 0.03);
+
+        // The back of the light is dark.
+        let above = Ray::new(Vec3::new(0.3, 3.0, 0.2), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(scene(true).trace_bounce(above, 4).r, 0.0);
+    }
+}
@@ -11,18 +11,27 @@ use rusttracing::scene::*;
 use rusttracing::camera::*;
 use rusttracing::sphere::*;
 use rusttracing::plane::*;
-use rusttracing::tri::*;
+use rusttracing::quad::*;
 use rusttracing::object::*;
+use rusttracing::cuboid::*;
 use rusttracing::image::*;
+use rusttracing::texture::*;
+use rusttracing::filter::*;
+use rusttracing::sampling::*;
+use rusttracing::pass::*;
 
 use std::fs;
+use std::env;
 
 /// Command line raytracer
 fn main() {
     let scene = Scene::<f64> {
         objects: vec![
             // Ground
-            Box::new(Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(0.1, 0.9, 0.1))),
+            Box::new(Textured::new(
+                Plane::new(Vec3::new(0.0, 1.0, 0.0), 0.0, Color::new(1.0, 1.0, 1.0)),
+                Checker { even: Color::new(0.1, 0.9, 0.1), odd: Color::new(0.1, 0.6, 0.1), scale: 1.0 },
+            )),
 
             // Light
             Box::new(Sphere::<_> {
@@ -33,7 +42,7 @@ fn main() {
             }),
 
             // Cube
-            Box::new(Object::new_box(Vec3::new(2.0, 1.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Color::new(0.9, 0.1, 0.9), 1.0)),
+            Box::new(Cuboid::from_center(Vec3::new(2.0, 1.0, 0.0), Vec3::new(1.0, 1.0, 1.0), Color::new(0.9, 0.1, 0.9))),
 
             // Sphere
             Box::new(Sphere::<_> {
@@ -50,6 +59,7 @@ fn main() {
                     Color::new(0.9, 0.9, 0.9),
                     1.0,
                 ).unwrap()
+                    .compute_smooth_normals(60.0)
                     .unit()
                     .scale(Vec3::new(2.0, 2.0, 2.0))
                     .rotate(Vec3::new(-90.0, 0.0, 180.0))
@@ -70,25 +80,23 @@ fn main() {
             ),
 
             // Mirror
-            Box::new(Tri::new(
-                Vec3::new(-2.0, 0.0, -5.0),
-                Vec3::new(-2.0, 0.0, 5.0),
-                Vec3::new(-2.0, 5.0, 5.0),
-                Color::new(0.9, 0.9, 0.9),
-                0.01,
-            )),
-            Box::new(Tri::new(
-                Vec3::new(-2.0, 5.0, -5.0),
-                Vec3::new(-2.0, 0.0, -5.0),
-                Vec3::new(-2.0, 5.0, 5.0),
-                Color::new(0.9, 0.9, 0.9),
-                0.01,
-            )),
+            Box::new(Quad {
+                origin: Vec3::new(-2.0, 0.0, -5.0),
+                u: Vec3::new(0.0, 0.0, 10.0),
+                v: Vec3::new(0.0, 5.0, 0.0),
+                color: Color::new(0.9, 0.9, 0.9),
+                roughness: 0.01,
+            }),
         ],
 
-        environment: Color::new_emission(0.9, 0.8, 1.0, 1000.0),
+        environment: Box::new(Color::new_emission(0.9, 0.8, 1.0, 1000.0)),
 
         camera: Camera::new(Vec3::new(2.0, 4.0, -2.0), Vec3::new(-45.0, -45.0, 0.0)),
+
+        filter: Filter::Mitchell { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 },
+
+        sampling: Sampling::Adaptive { threshold: 0.05, max: SAMPLES * 4 },
+        fog: None,
     };
 
     const WIDTH: usize = 192*2;
@@ -96,15 +104,42 @@ fn main() {
     const SAMPLES: usize = 16;
     const FOV: f64 = 110.0;
 
+    // Bounces before Russian roulette starts, rather than a limit on bounces.
+    const DEPTH: usize = 4;
+
+    // Each `--pass <name>` renders an additional pass, such as `--pass samples` for a heatmap of samples per pixel.
+    let args: Vec<String> = env::args().collect();
+
+    let mut passes = vec![];
+    for i in 1..args.len() {
+        if args[i - 1] == "--pass" {
+            match Pass::from_name(&args[i]) {
+                Some(pass) => passes.push(pass),
+                None => panic!("Unknown pass {}", args[i]),
+            }
+        }
+    }
+
+    // `--denoise` filters the image, guided by the passes it requires.
+    let denoise = args.iter().any(|arg| arg == "--denoise");
+
+    if denoise {
+        for pass in [Pass::Normal, Pass::Albedo, Pass::Depth] {
+            if !passes.contains(&pass) {
+                passes.push(pass);
+            }
+        }
+    }
+
     let start = Instant::now();
     println!("Starting render");
 
-    let mut img: Image<WIDTH, HEIGHT> = Image::new();
+    let mut render: Render<WIDTH, HEIGHT> = Render { image: Image::new(), passes: vec![] };
     let mut counter = 0;
     thread::scope(|s| {
         let (tx, rx) = mpsc::channel();
         s.spawn(|| {
-            img = scene.raytrace::<WIDTH, HEIGHT>(SAMPLES, 16, FOV, Some(tx));
+            render = scene.render::<WIDTH, HEIGHT>(SAMPLES, DEPTH, FOV, &passes, Some(tx));
         });
         for _batch in rx {
             counter += 1;
@@ -116,6 +151,25 @@ fn main() {
     let time = start.elapsed();
     println!("Rendering took {}ms", time.as_millis());
 
-    let data = img.to_ppm();
+    if denoise {
+        let start = Instant::now();
+
+        render.image = render.image.denoise(
+            render.pass(Pass::Normal).unwrap(),
+            render.pass(Pass::Albedo).unwrap(),
+            render.pass(Pass::Depth).unwrap(),
+            5,
+        );
+
+        let time = start.elapsed();
+        println!("Denoising took {}ms", time.as_millis());
+    }
+
+    let data = render.image.to_ppm();
     fs::write("image.ppm", data).unwrap();
+
+    for (pass, img) in &render.passes {
+        fs::write(pass.name().to_owned() + ".ppm", img.to_ppm()).unwrap();
+        fs::write(pass.name().to_owned() + ".pfm", img.to_pfm()).unwrap();
+    }
 }
@@ -218,6 +218,34 @@ impl<T: Copy + From<f64> + Into<f64>
     }
 }
 
+impl<T: Copy + From<f64> + Into<f64> + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>> Matrix<T> {
+    /// Transforms a normal by the cofactors of the upper 3x3 of the matrix,
+    /// which keeps it perpendicular to a surface transformed by the matrix.
+    ///
+    /// Returns a unit vector.
+    ///
+    /// ```
+    /// # use rusttracing::matrix::*;
+    /// # use rusttracing::vector::*;
+    /// let stretch = matrix![
+    ///     2.0, 0.0, 0.0;
+    ///     0.0, 1.0, 0.0;
+    ///     0.0, 0.0, 1.0;
+    /// ];
+    ///
+    /// assert_eq!(stretch.transform_normal(Vec3::new(1.0, 1.0, 0.0)), Vec3::new(1.0, 2.0, 0.0).unit());
+    /// ```
+    pub fn transform_normal(&self, normal: Vec3<T>) -> Vec3<T> {
+        let (a, b, c) = (
+            Vec3::new(self[0][0], self[1][0], self[2][0]),
+            Vec3::new(self[0][1], self[1][1], self[2][1]),
+            Vec3::new(self[0][2], self[1][2], self[2][2]),
+        );
+
+        (b.cross(&c) * normal.x + c.cross(&a) * normal.y + a.cross(&b) * normal.z).unit()
+    }
+}
+
 impl<T: Copy> Index<usize> for Matrix<T> {
     type Output = Vec<T>;
 
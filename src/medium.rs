@@ -0,0 +1,301 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+
+use std::ops::*;
+use std::f64::consts::PI;
+
+/// A homogeneous participating medium, such as fog, smoke or murky water,
+/// which absorbs and scatters light evenly throughout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Medium {
+    /// The rate at which light is absorbed, per unit distance.
+    pub absorption: f64,
+
+    /// The rate at which light is scattered, per unit distance.
+    pub scattering: f64,
+
+    /// Colour of the scattered light.
+    pub color: Color,
+
+    /// The mean cosine of the angle light is scattered by, from -1 scattering back, through 0 scattering evenly, to 1 scattering forward.
+    pub asymmetry: f64,
+}
+
+impl Medium {
+    /// Default constructor, scattering evenly in every direction.
+    pub fn new(absorption: f64, scattering: f64, color: Color) -> Self {
+        Medium { absorption, scattering, color, asymmetry: 0.0 }
+    }
+
+    /// Sets the mean cosine of the angle light is scattered by.
+    pub fn with_asymmetry(mut self, asymmetry: f64) -> Self {
+        self.asymmetry = asymmetry;
+
+        self
+    }
+
+    /// The rate at which light is either absorbed or scattered, per unit distance.
+    pub fn extinction(&self) -> f64 {
+        self.absorption + self.scattering
+    }
+
+    /// The fraction of light which is scattered rather than absorbed, tinted by the colour.
+    pub fn albedo(&self) -> Color {
+        let extinction = self.extinction();
+
+        if extinction <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        self.color * (self.scattering / extinction)
+    }
+
+    /// The probability density, over solid angle, of light travelling in a unit direction being scattered into another,
+    /// by the Henyey-Greenstein phase function.
+    pub fn phase(&self, direction: Vec3<f64>, scattered: Vec3<f64>) -> f64 {
+        let g = self.asymmetry;
+        let cos = direction * scattered.unit();
+
+        let denominator = 1.0 + g * g - 2.0 * g * cos;
+
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    /// Chooses a direction for light travelling in a unit direction to be scattered into,
+    /// from two uniform random numbers between 0 and 1, with the density of [Medium::phase].
+    pub fn sample_phase(&self, direction: Vec3<f64>, u: f64, v: f64) -> Vec3<f64> {
+        let g = self.asymmetry;
+
+        let cos = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+
+            (1.0 + g * g - s * s) / (2.0 * g)
+        }.clamp(-1.0, 1.0);
+
+        let sin = (1.0 - cos * cos).sqrt();
+        let angle = 2.0 * PI * v;
+
+        let other = if direction.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let across = direction.cross(&other).unit();
+        let around = direction.cross(&across);
+
+        direction * cos + (across * angle.cos() + around * angle.sin()) * sin
+    }
+}
+
+/// Fog filling the scene, below a height.
+///
+/// An infinite height fills the whole scene, in which case only light from within the scene is seen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    /// The medium of the fog.
+    pub medium: Medium,
+
+    /// The height of the top of the fog, along y.
+    pub height: f64,
+}
+
+impl Fog {
+    /// Default constructor.
+    pub fn new(medium: Medium, height: f64) -> Self {
+        Fog { medium, height }
+    }
+
+    /// The interval of distance along the line of the ray which is within the fog.
+    pub fn interval(&self, ray: &Ray<f64>) -> Option<(f64, f64)> {
+        let (origin, direction) = (ray.origin.y, ray.direction.y);
+
+        if direction == 0.0 {
+            return if origin <= self.height { Some((f64::NEG_INFINITY, f64::INFINITY)) } else { None };
+        }
+
+        let crossing = (self.height - origin) / direction;
+
+        if crossing.is_nan() {
+            return Some((f64::NEG_INFINITY, f64::INFINITY));
+        }
+
+        if direction > 0.0 {
+            Some((f64::NEG_INFINITY, crossing))
+        } else {
+            Some((crossing, f64::INFINITY))
+        }
+    }
+}
+
+/// A medium along a ray, with the interval of distance it fills.
+pub type Span = (f64, f64, Medium);
+
+/// The fraction of light which passes through media along a ray, up to a distance,
+/// where the media are given by the intervals of distance they fill.
+pub fn transmittance(media: &[Span], limit: f64) -> f64 {
+    let depth: f64 = media.iter().map(|(start, end, medium)| {
+        let length = end.min(limit) - start.max(0.0);
+
+        if length > 0.0 && medium.extinction() > 0.0 { length * medium.extinction() } else { 0.0 }
+    }).sum();
+
+    (-depth).exp()
+}
+
+/// Chooses the distance along a ray at which light is scattered or absorbed, if before a limit,
+/// from two uniform random numbers between 0 and 1, where the media are given by the intervals of distance they fill.
+///
+/// Each distance is chosen in proportion to the light reaching it, so that no weighting is needed.
+/// Where media overlap, the medium it happens within is chosen in proportion to their extinction,
+/// and given by its index within the media.
+pub fn sample_distance(media: &[Span], limit: f64, u: f64, pick: f64) -> Option<(f64, usize)> {
+    if media.is_empty() {
+        return None;
+    }
+
+    // The depth, in mean free paths, at which light stops.
+    let target = -(1.0 - u).ln();
+
+    // Every distance at which a medium starts or ends, between which the extinction is constant.
+    let mut stops: Vec<f64> = vec![0.0, limit];
+    for &(start, end, _medium) in media {
+        stops.extend([start, end].into_iter().filter(|&x| x > 0.0 && x < limit));
+    }
+    stops.sort_by(|a, b| a.total_cmp(b));
+
+    let mut depth = 0.0;
+
+    for pair in stops.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let middle = if end.is_finite() { (start + end) * 0.5 } else { start + 1.0 };
+
+        let within: Vec<(usize, &Medium)> = media.iter().enumerate().filter(|x| x.1.0 <= middle && middle <= x.1.1).map(|x| (x.0, &x.1.2)).collect();
+        let extinction: f64 = within.iter().map(|x| x.1.extinction()).sum();
+
+        if extinction <= 0.0 {
+            continue;
+        }
+
+        if depth + extinction * (end - start) >= target {
+            let distance = start + (target - depth) / extinction;
+
+            let mut choice = pick * extinction;
+            for &(index, medium) in &within {
+                if choice < medium.extinction() {
+                    return Some((distance, index));
+                }
+                choice -= medium.extinction();
+            }
+
+            return Some((distance, within.last().unwrap().0));
+        }
+
+        depth += extinction * (end - start);
+    }
+
+    None
+}
+
+/// Volume.
+///
+/// A participating medium filling a closed shape, such as a [crate::sphere::Sphere] or a closed stl [crate::object::Object].
+///
+/// The surface of the shape is not seen, only the medium within it,
+/// so the shape must give its [Raytrace::intervals].
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Volume<S> {
+    /// The shape bounding the medium.
+    pub boundary: S,
+
+    /// The medium within the shape.
+    pub medium: Medium,
+}
+
+impl<S> Volume<S> {
+    /// Default constructor.
+    pub fn new(boundary: S, medium: Medium) -> Self {
+        Volume { boundary, medium }
+    }
+}
+
+impl<T: Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>> Raytrace<T> for Volume<S> {
+    /// Never hit, as light is only scattered within the medium.
+    fn intersects_along(&self, _ray: &Ray<T>) -> Option<T> {
+        None
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        self.boundary.intervals(ray)
+    }
+
+    fn transmit(&self, _ray: &Ray<T>) -> Option<Ray<T>> {
+        None
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        Some(self.medium)
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn phase() {
+        let mut rng = rand::thread_rng();
+        let direction = Vec3::new(0.0, 0.6, 0.8);
+
+        for g in [-0.5, 0.0, 0.7] {
+            let medium = Medium::new(0.0, 1.0, Color::new(1.0, 1.0, 1.0)).with_asymmetry(g);
+
+            // Integrates to 1 over every direction, and the mean cosine of samples is the asymmetry.
+            let count = 100000;
+            let (mut integral, mut cos) = (0.0, 0.0);
+            for _i in 0..count {
+                let z: f64 = rng.gen_range(-1.0..1.0);
+                let angle: f64 = rng.gen_range(0.0..(2.0 * PI));
+                let even = Vec3::new((1.0 - z * z).sqrt() * angle.cos(), (1.0 - z * z).sqrt() * angle.sin(), z);
+
+                integral += medium.phase(direction, even) * 4.0 * PI;
+                cos += medium.sample_phase(direction, rng.gen(), rng.gen()) * direction;
+            }
+
+            assert!((integral / count as f64 - 1.0).abs() < 0.05);
+            assert!((cos / count as f64 - g).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn distance() {
+        let thin = Medium::new(0.5, 0.5, Color::new(1.0, 1.0, 1.0));
+        let thick = Medium::new(1.0, 3.0, Color::new(1.0, 1.0, 1.0));
+
+        let media = [(-1.0, 2.0, thin), (1.0, 3.0, thick)];
+
+        // 1 from 0 to 1, 5 from 1 to 2, and 4 from 2 to 3.
+        assert!((transmittance(&media, 10.0) - (-10.0_f64).exp()).abs() < 1e-12);
+        assert!((transmittance(&media, 1.5) - (-3.5_f64).exp()).abs() < 1e-12);
+
+        let stop = |depth: f64| 1.0 - (-depth).exp();
+
+        let (along, index) = sample_distance(&media, 10.0, stop(0.5), 0.0).unwrap();
+        assert!((along - 0.5).abs() < 1e-9 && index == 0);
+        assert!((sample_distance(&media, 10.0, stop(3.5), 0.0).unwrap().0 - 1.5).abs() < 1e-9);
+        assert_eq!(sample_distance(&media, 10.0, stop(3.5), 0.5).unwrap().1, 1);
+        assert_eq!(sample_distance(&media, 1.5, stop(3.6), 0.0), None);
+        assert_eq!(sample_distance(&[], 10.0, 0.5, 0.0), None);
+
+        // Fog below a height, from a ray rising through its top.
+        let fog = Fog::new(thin, 2.0);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(fog.interval(&ray), Some((f64::NEG_INFINITY, 2.0)));
+    }
+}
@@ -0,0 +1,389 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::matrix::Matrix;
+use crate::raytrace::Raytrace;
+use crate::color::Color;
+use crate::tri::*;
+use crate::plane::Plane;
+use crate::object::*;
+use crate::aabb::Aabb;
+
+use std::ops::*;
+use std::collections::HashMap;
+
+/// The surface of a face within a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Material {
+    /// Colour of the surface.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl Material {
+    /// Default constructor.
+    pub fn new(color: Color, roughness: f64) -> Self {
+        Material { color, roughness }
+    }
+}
+
+/// An indexed triangle mesh.
+///
+/// Vertices are stored once and shared between faces,
+/// so transforms only need to move the vertices.
+///
+/// Implements the Raytrace trait, where each face behaves as a [Tri].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh<T> {
+    /// The positions of every vertex.
+    pub vertices: Vec<Vec3<T>>,
+
+    /// The indices into vertices of the 3 points of each face.
+    pub faces: Vec<[usize; 3]>,
+
+    /// The materials shared between faces.
+    pub materials: Vec<Material>,
+
+    /// The index into materials of each face.
+    pub face_materials: Vec<usize>,
+
+    /// The uv texture coordinates at the 3 points of each face.
+    pub face_uvs: Vec<Vec3<(f64, f64)>>,
+
+    /// The normals at the 3 points of each face, for smooth shading, or none for flat shading.
+    pub face_normals: Vec<Option<Vec3<Vec3<T>>>>,
+
+    /// The bounding box of the vertices, used to skip rays which miss the mesh.
+    ///
+    /// Must be recalculated with `mesh.recalculate_bounds()` upon mutating vertices directly.
+    pub bounds: Aabb<T>,
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Mesh<T> {
+    /// Creates a blank mesh.
+    pub fn new() -> Self {
+        Mesh {
+            vertices: vec![],
+            faces: vec![],
+            materials: vec![],
+            face_materials: vec![],
+            face_uvs: vec![],
+            face_normals: vec![],
+            bounds: Aabb::new(Vec3::new(0.0.into(), 0.0.into(), 0.0.into()), Vec3::new(0.0.into(), 0.0.into(), 0.0.into())),
+        }
+    }
+
+    /// Creates a new mesh from a byte array, in the stl format, with one material.
+    ///
+    /// Vertices shared between faces are stored once.
+    ///
+    /// # Errors
+    /// Will error when used with ascii stl (old, outdated version).
+    pub fn from_stl(bytes: Vec<u8>, material: Material) -> Result<Self, UnsupportedError> where f64: From<T> {
+        Ok(Object::from_stl(bytes, material.color, material.roughness)?.into())
+    }
+
+    /// Adds a face between 3 vertices, with a material.
+    ///
+    /// The face has the default uvs of a [Tri], and is flat shaded.
+    pub fn with_face(mut self, face: [usize; 3], material: usize) -> Self {
+        self.faces.push(face);
+        self.face_materials.push(material);
+        self.face_uvs.push(Vec3::new((0.0, 0.0), (1.0, 0.0), (0.0, 1.0)));
+        self.face_normals.push(None);
+
+        self
+    }
+
+    /// Sets the uv texture coordinates at each of the 3 points of the last face added.
+    pub fn with_uvs(mut self, uv1: (f64, f64), uv2: (f64, f64), uv3: (f64, f64)) -> Self {
+        if let Some(uvs) = self.face_uvs.last_mut() {
+            *uvs = Vec3::new(uv1, uv2, uv3);
+        }
+
+        self
+    }
+
+    /// Sets the normals at each of the 3 points of the last face added, for smooth shading.
+    pub fn with_normals(mut self, n1: Vec3<T>, n2: Vec3<T>, n3: Vec3<T>) -> Self {
+        if let Some(normals) = self.face_normals.last_mut() {
+            *normals = Some(Vec3::new(n1, n2, n3));
+        }
+
+        self
+    }
+
+    /// Recalculates the bounds of the mesh.
+    pub fn recalculate_bounds(&mut self) {
+        if let Some(bounds) = Aabb::from_points(self.vertices.iter().copied()) {
+            self.bounds = bounds;
+        }
+    }
+
+    /// Moves all vertices in the mesh by a set vector.
+    pub fn translate(mut self, offset: Vec3<T>) -> Self {
+        for vertex in &mut self.vertices {
+            *vertex = *vertex + offset;
+        }
+
+        self.recalculate_bounds();
+
+        self
+    }
+
+    /// Transforms the mesh with a Matrix transformation.
+    pub fn transform(mut self, mat: Matrix<T>) -> Self {
+        for normals in self.face_normals.iter_mut().flatten() {
+            *normals = Vec3::new(mat.transform_normal(normals.x), mat.transform_normal(normals.y), mat.transform_normal(normals.z));
+        }
+
+        for vertex in &mut self.vertices {
+            *vertex = Vec3::new(
+                mat[0][0] * vertex.x + mat[0][1] * vertex.y + mat[0][2] * vertex.z,
+                mat[1][0] * vertex.x + mat[1][1] * vertex.y + mat[1][2] * vertex.z,
+                mat[2][0] * vertex.x + mat[2][1] * vertex.y + mat[2][2] * vertex.z,
+            );
+        }
+
+        self.recalculate_bounds();
+
+        self
+    }
+
+    /// Scale all vertices in the mesh by a set vector from the origin.
+    pub fn scale(self, factor: Vec3<T>) -> Self where T: From<i32> {
+        let mut mat = Matrix::ident(3);
+        mat[0][0] = factor.x;
+        mat[1][1] = factor.y;
+        mat[2][2] = factor.z;
+
+        self.transform(mat)
+    }
+
+    /// Rotates the mesh by a rotation vector.
+    pub fn rotate(self, rot: Vec3<T>) -> Self where T: From<i32> + Neg<Output = T> {
+        let mat = <Vec3<T> as Into<Matrix<T>>>::into(rot);
+
+        self.transform(mat)
+    }
+
+    /// The face as a standalone tri, for shading.
+    pub fn tri(&self, face: usize) -> Tri<T> where f64: From<T> {
+        let [a, b, c] = self.faces[face];
+        let material = self.materials[self.face_materials[face]];
+
+        Tri {
+            uvs: self.face_uvs[face],
+            normals: self.face_normals[face],
+            ..Tri::new(self.vertices[a], self.vertices[b], self.vertices[c], material.color, material.roughness)
+        }
+    }
+
+    /// Gives the distance along a ray to a face, by the Möller-Trumbore method.
+    fn intersects_face(&self, ray: &Ray<T>, face: usize) -> Option<T> {
+        let points = self.faces[face].map(|i| self.vertices[i].to_f64());
+        let distance = moller_trumbore(ray.origin.to_f64(), ray.direction.to_f64(), points)?.0;
+
+        if distance < 0.01 {
+            return None;
+        }
+
+        Some(distance.into())
+    }
+
+    /// The plane of the intersected face which the ray is shaded by, smoothed by the normals of the face.
+    fn shading_plane(&self, ray: &Ray<T>) -> Option<Plane<T>> where f64: From<T> {
+        let (face, distance) = self.intersects(ray)?;
+
+        Some(self.tri(face).shading_plane_at(ray, ray.at(distance)))
+    }
+
+    /// Gives the closest face a ray intersects, and the distance along the ray.
+    fn intersects(&self, ray: &Ray<T>) -> Option<(usize, T)> {
+        if !self.bounds.hits(ray) {
+            return None;
+        }
+
+        let mut closest: Option<(usize, T)> = None;
+
+        for face in 0..self.faces.len() {
+            if let Some(x) = self.intersects_face(ray, face) {
+                if closest.is_none_or(|(_, lowest)| x < lowest) {
+                    closest = Some((face, x));
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Default for Mesh<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> From<Object<T>> for Mesh<T> {
+    /// Converts an object into a mesh, storing vertices and materials shared between tris once.
+    ///
+    /// The uvs and normals of each tri are kept with its face.
+    fn from(object: Object<T>) -> Self {
+        let mut mesh = Mesh::new();
+
+        let mut vertices: HashMap<[u64; 3], usize> = HashMap::new();
+        let mut vertex = |point: Vec3<T>, mesh: &mut Mesh<T>| {
+            let point_f64 = point.to_f64();
+
+            *vertices.entry([point_f64.x.to_bits(), point_f64.y.to_bits(), point_f64.z.to_bits()]).or_insert_with(|| {
+                mesh.vertices.push(point);
+
+                mesh.vertices.len() - 1
+            })
+        };
+
+        for tri in &object.tris {
+            let face = [vertex(tri.bounds.x, &mut mesh), vertex(tri.bounds.y, &mut mesh), vertex(tri.bounds.z, &mut mesh)];
+
+            let material = Material::new(tri.plane.color, tri.plane.roughness);
+            let index = match mesh.materials.iter().position(|&x| x == material) {
+                Some(x) => x,
+                None => {
+                    mesh.materials.push(material);
+
+                    mesh.materials.len() - 1
+                },
+            };
+
+            mesh = mesh.with_face(face, index).with_uvs(tri.uvs.x, tri.uvs.y, tri.uvs.z);
+            if let Some(normals) = tri.normals {
+                mesh = mesh.with_normals(normals.x, normals.y, normals.z);
+            }
+        }
+
+        mesh.recalculate_bounds();
+
+        mesh
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Mesh<T> where f64: From<T> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.intersects(ray)?.1)
+    }
+
+    /// Reflects from the intersected face, as its [Tri] does.
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        self.shading_plane(ray)?.transmit(ray)
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(self.shading_plane(ray)?.normal)
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.tri(self.intersects(ray)?.0).uv(ray)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.tri(self.intersects(ray)?.0).uv_tangents(ray)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.shading_plane(ray)?.scatter_pdf(ray, direction)
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        match self.intersects(ray) {
+            Some((face, _)) => self.tri(face).plane.recolor(ray, color),
+            None => color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_object() {
+        let color = Color::new(0.5, 0.5, 0.5);
+        let mesh: Mesh<f64> = Object::new_box(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0), color, 0.5).into();
+
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.faces.len(), 12);
+        assert_eq!(mesh.materials, vec![Material::new(color, 0.5)]);
+    }
+
+    #[test]
+    fn shading() {
+        let object = || {
+            let mut object: Object<f64> = Object::new_box(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0), Color::new(0.5, 0.5, 0.5), 0.5).compute_smooth_normals(120.0);
+            for tri in &mut object.tris {
+                tri.uvs = Vec3::new((0.2, 0.4), (0.6, 0.4), (0.2, 0.8));
+            }
+
+            object
+        };
+
+        let mesh: Mesh<f64> = object().into();
+        let object = object();
+        assert_eq!(mesh.vertices.len(), 8);
+        assert!(mesh.face_normals.iter().all(|x| x.is_some()));
+
+        let rays = [
+            Ray::new(Vec3::new(0.3, 0.6, -5.0), Vec3::new(0.0, 0.0, 1.0)),
+            Ray::new(Vec3::new(-4.0, 0.2, 0.1), Vec3::new(1.0, 0.1, 0.0)),
+        ];
+
+        for ray in rays {
+            assert!((mesh.normal(&ray).unwrap() - object.normal(&ray).unwrap()).length() < 1e-9);
+            assert!((mesh.normal(&ray).unwrap() - mesh.tri(mesh.intersects(&ray).unwrap().0).plane.normal).length() > 0.01);
+
+            let (a, b) = (mesh.uv(&ray).unwrap(), object.uv(&ray).unwrap());
+            assert!((a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9);
+        }
+
+        // Normals stay perpendicular to the surface when stretched.
+        let stretched = mesh.scale(Vec3::new(2.0, 1.0, 1.0));
+        let object = object.scale(Vec3::new(2.0, 1.0, 1.0));
+        let ray = Ray::new(Vec3::new(0.5, 0.6, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!((stretched.normal(&ray).unwrap() - object.normal(&ray).unwrap()).length() < 1e-9);
+    }
+
+    #[test]
+    fn intersects() {
+        let object: Object<f64> = Object::new_box(Vec3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 1.0, 3.0), Color::new(0.5, 0.5, 0.5), 0.5);
+        let mesh: Mesh<f64> = Object::new_box(Vec3::new(1.0, 2.0, 3.0), Vec3::new(2.0, 1.0, 3.0), Color::new(0.5, 0.5, 0.5), 0.5).into();
+
+        let rays = [
+            Ray::new(Vec3::new(1.2, 2.1, -5.0), Vec3::new(0.0, 0.0, 1.0)),
+            Ray::new(Vec3::new(-4.0, 0.0, 0.0), Vec3::new(1.0, 0.4, 0.6)),
+            Ray::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.3, -1.0, 0.2)),
+            Ray::new(Vec3::new(5.0, 5.0, 5.0), Vec3::new(1.0, 0.0, 0.0)),
+        ];
+
+        for ray in rays {
+            match (mesh.intersects_along(&ray), object.intersects_along(&ray)) {
+                (Some(a), Some(b)) => {
+                    assert!((a - b).abs() < 1e-9);
+                    assert!((mesh.normal(&ray).unwrap() - object.normal(&ray).unwrap()).length() < 1e-9);
+                },
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+
+    #[test]
+    fn transform() {
+        let mesh: Mesh<f64> = Object::new_box(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0), Color::new(0.5, 0.5, 0.5), 0.5).into();
+        let faces = mesh.faces.clone();
+
+        let moved = mesh.scale(Vec3::new(2.0, 1.0, 1.0)).translate(Vec3::new(0.0, 0.0, 5.0));
+
+        assert_eq!(moved.faces, faces);
+        assert_eq!(moved.bounds, Aabb::new(Vec3::new(-2.0, -1.0, 4.0), Vec3::new(2.0, 1.0, 6.0)));
+        assert_eq!(moved.intersects_along(&Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))), Some(4.0));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::matrix::Matrix;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::medium::Medium;
+
+use std::ops::*;
+
+/// Applies a 3x3 rotation matrix to a vector.
+fn rotate(mat: &Matrix<f64>, vec: Vec3<f64>) -> Vec3<f64> {
+    Vec3::new(
+        mat[0][0] * vec.x + mat[0][1] * vec.y + mat[0][2] * vec.z,
+        mat[1][0] * vec.x + mat[1][1] * vec.y + mat[1][2] * vec.z,
+        mat[2][0] * vec.x + mat[2][1] * vec.y + mat[2][2] * vec.z,
+    )
+}
+
+/// Applies the inverse of a 3x3 rotation matrix, its transpose, to a vector.
+fn unrotate(mat: &Matrix<f64>, vec: Vec3<f64>) -> Vec3<f64> {
+    Vec3::new(
+        mat[0][0] * vec.x + mat[1][0] * vec.y + mat[2][0] * vec.z,
+        mat[0][1] * vec.x + mat[1][1] * vec.y + mat[2][1] * vec.z,
+        mat[0][2] * vec.x + mat[1][2] * vec.y + mat[2][2] * vec.z,
+    )
+}
+
+/// Moving geometry.
+///
+/// Geometry which moves and rotates linearly from where it is at time 0 to where it is at time 1,
+/// so that it is blurred across the shutter of the camera.
+///
+/// Rays are moved into the space of the geometry where it is at the time of the ray,
+/// so any geometry can be moved, including [crate::instance::Instance]s.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Moving<T, G> {
+    /// The geometry, as it is with no translation or rotation.
+    pub geometry: G,
+
+    /// The offset of the geometry at times 0 and 1.
+    pub translation: (Vec3<T>, Vec3<T>),
+
+    /// The rotation of the geometry about its origin, in degrees, at times 0 and 1.
+    ///
+    /// Applied before the translation.
+    pub rotation: (Vec3<T>, Vec3<T>),
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, G> Moving<T, G> {
+    /// Creates the geometry, not yet moving.
+    pub fn new(geometry: G) -> Self {
+        let zero = Vec3::new(0.0.into(), 0.0.into(), 0.0.into());
+
+        Moving { geometry, translation: (zero, zero), rotation: (zero, zero) }
+    }
+
+    /// Sets the offset of the geometry at times 0 and 1.
+    pub fn with_translation(mut self, start: Vec3<T>, end: Vec3<T>) -> Self {
+        self.translation = (start, end);
+
+        self
+    }
+
+    /// Sets the rotation of the geometry about its origin, in degrees, at times 0 and 1.
+    pub fn with_rotation(mut self, start: Vec3<T>, end: Vec3<T>) -> Self {
+        self.rotation = (start, end);
+
+        self
+    }
+
+    /// The rotation matrix and offset of the geometry at a time.
+    fn at(&self, time: f64) -> (Matrix<f64>, Vec3<f64>) {
+        let lerp = |(start, end): (Vec3<T>, Vec3<T>)| start.to_f64() + (end.to_f64() - start.to_f64()) * time;
+
+        (Matrix::from(lerp(self.rotation)), lerp(self.translation))
+    }
+
+    /// The ray in the space of the geometry, where it is at the time of the ray.
+    ///
+    /// Distances are unchanged, as the geometry is only moved and rotated.
+    fn local(&self, ray: &Ray<T>) -> Ray<T> {
+        let (rotation, offset) = self.at(ray.time);
+
+        Ray {
+            origin: Vec3::from_f64(unrotate(&rotation, ray.origin.to_f64() - offset)),
+            direction: Vec3::from_f64(unrotate(&rotation, ray.direction.to_f64())),
+            time: ray.time,
+        }
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, G: Raytrace<T>> Raytrace<T> for Moving<T, G> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        self.geometry.intersects_along(&self.local(ray))
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        self.geometry.intervals(&self.local(ray))
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let (rotation, offset) = self.at(ray.time);
+        let out = self.geometry.transmit(&self.local(ray))?;
+
+        Some(Ray::new(
+            Vec3::from_f64(rotate(&rotation, out.origin.to_f64()) + offset),
+            Vec3::from_f64(rotate(&rotation, out.direction.to_f64())),
+        ).with_time(ray.time))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        let normal = self.geometry.normal(&self.local(ray))?;
+
+        Some(Vec3::from_f64(rotate(&self.at(ray.time).0, normal.to_f64())))
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.geometry.uv(&self.local(ray))
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        let (du, dv) = self.geometry.uv_tangents(&self.local(ray))?;
+        let rotation = self.at(ray.time).0;
+
+        Some((rotate(&rotation, du), rotate(&rotation, dv)))
+    }
+
+    fn albedo(&self, ray: &Ray<T>) -> Color {
+        self.geometry.albedo(&self.local(ray))
+    }
+
+    /// Unchanged by rotating the direction into the space of the geometry, as solid angles are kept.
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let local_direction = unrotate(&self.at(ray.time).0, direction.to_f64());
+
+        self.geometry.scatter_pdf(&self.local(ray), &Vec3::from_f64(local_direction))
+    }
+
+    fn medium(&self) -> Option<Medium> {
+        self.geometry.medium()
+    }
+
+    fn emission(&self) -> Option<Color> {
+        self.geometry.emission()
+    }
+
+    fn density(&self, point: &Vec3<T>, time: f64) -> Option<f64> {
+        let (rotation, offset) = self.at(time);
+
+        self.geometry.density(&Vec3::from_f64(unrotate(&rotation, point.to_f64() - offset)), time)
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        self.geometry.recolor(&self.local(ray), color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sphere::Sphere;
+
+    #[test]
+    fn translation() {
+        let sphere = Sphere { roughness: 0.0, ..Sphere::new(Vec3::new(0.0, 0.0, 5.0), 1.0_f64, Color::new(0.5, 0.5, 0.5)) };
+        let moving = Moving::new(sphere).with_translation(Vec3::new(0.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 0.0));
+
+        let ray = Ray::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+
+        // Halfway through, the sphere is centred on the ray, and missed at the start and end.
+        assert!((moving.intersects_along(&ray.with_time(0.5)).unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(moving.intersects_along(&ray), None);
+        assert_eq!(moving.intersects_along(&ray.with_time(1.0)), None);
+
+        let out = moving.transmit(&ray.with_time(0.5)).unwrap();
+        assert!((out.origin - Vec3::new(2.0, 0.0, 4.0)).length() < 1e-9);
+        assert!((out.direction - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+        assert_eq!(out.time, 0.5);
+    }
+
+    #[test]
+    fn rotation() {
+        // A sphere off the origin, swung around the y axis by a quarter turn.
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, 3.0), 1.0_f64, Color::new(0.5, 0.5, 0.5));
+        let moving = Moving::new(sphere).with_rotation(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 90.0, 0.0));
+
+        let down = |x: f64, z: f64| Ray::new(Vec3::new(x, 5.0, z), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!((moving.intersects_along(&down(0.0, 3.0)).unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(moving.intersects_along(&down(0.0, 3.0).with_time(1.0)), None);
+
+        // Ending up along x.
+        let hit = down(3.0, 0.0).with_time(1.0);
+        assert!((moving.intersects_along(&hit).unwrap() - 4.0).abs() < 1e-9);
+
+        // Normals turn with the sphere.
+        let side = Ray::new(Vec3::new(3.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0)).with_time(1.0);
+        assert!((moving.normal(&side).unwrap() - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+        assert!((moving.normal(&hit).unwrap() - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+    }
+}
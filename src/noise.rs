@@ -0,0 +1,236 @@
+use rand::SeedableRng;
+use rand::seq::SliceRandom;
+use rand::rngs::StdRng;
+
+use crate::vector::Vec3;
+use crate::color::Color;
+use crate::texture::Texture;
+
+/// Gradient noise, as described by Ken Perlin.
+///
+/// Smoothly varies between roughly -1 and 1, and is 0 at every whole numbered point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Perlin {
+    /// A shuffled permutation of 0 to 255, repeated twice to avoid wrapping.
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    /// Creates noise from a seed, where the same seed always gives the same noise.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut permutation: Vec<usize> = (0..256).collect();
+        permutation.shuffle(&mut rng);
+        permutation.extend_from_within(..);
+
+        Perlin { permutation }
+    }
+
+    /// The dot product of the offset with one of 12 gradients, chosen by the hash.
+    fn gradient(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+        let h = hash & 15;
+
+        let u = if h < 8 { x } else { y };
+        let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    }
+
+    /// The noise at a point.
+    ///
+    /// ```
+    /// # use rusttracing::noise::*;
+    /// # use rusttracing::vector::*;
+    /// let noise = Perlin::new(0);
+    /// assert_eq!(noise.noise(Vec3::new(1.0, 2.0, 3.0)), 0.0);
+    /// ```
+    pub fn noise(&self, p: Vec3<f64>) -> f64 {
+        let (fx, fy, fz) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (x, y, z) = (p.x - fx, p.y - fy, p.z - fz);
+        let (xi, yi, zi) = ((fx as i64 & 255) as usize, (fy as i64 & 255) as usize, (fz as i64 & 255) as usize);
+
+        // Quintic smoothing, so the noise has a continuous second derivative.
+        let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.permutation;
+        let a = p[xi] + yi;
+        let (aa, ab) = (p[a] + zi, p[a + 1] + zi);
+        let b = p[xi + 1] + yi;
+        let (ba, bb) = (p[b] + zi, p[b + 1] + zi);
+
+        lerp(w,
+            lerp(v,
+                lerp(u, Self::gradient(p[aa], x, y, z), Self::gradient(p[ba], x - 1.0, y, z)),
+                lerp(u, Self::gradient(p[ab], x, y - 1.0, z), Self::gradient(p[bb], x - 1.0, y - 1.0, z)),
+            ),
+            lerp(v,
+                lerp(u, Self::gradient(p[aa + 1], x, y, z - 1.0), Self::gradient(p[ba + 1], x - 1.0, y, z - 1.0)),
+                lerp(u, Self::gradient(p[ab + 1], x, y - 1.0, z - 1.0), Self::gradient(p[bb + 1], x - 1.0, y - 1.0, z - 1.0)),
+            ),
+        )
+    }
+
+    /// Fractal brownian motion, summing octaves of noise,
+    /// each at double the frequency and half the amplitude of the last.
+    pub fn fbm(&self, p: Vec3<f64>, octaves: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut p = p;
+
+        for _i in 0..octaves {
+            sum += self.noise(p) * amplitude;
+
+            amplitude *= 0.5;
+            p = p * 2.0;
+        }
+
+        sum
+    }
+
+    /// Turbulence, as with fbm, but summing the absolute value of each octave,
+    /// giving sharp creases where the noise crosses 0.
+    pub fn turbulence(&self, p: Vec3<f64>, octaves: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut p = p;
+
+        for _i in 0..octaves {
+            sum += self.noise(p).abs() * amplitude;
+
+            amplitude *= 0.5;
+            p = p * 2.0;
+        }
+
+        sum
+    }
+}
+
+/// A solid texture of fractal noise, blending between two colors.
+///
+/// Does not require uv coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fractal {
+    /// The noise.
+    pub noise: Perlin,
+
+    /// The frequency of the noise, per unit of space.
+    pub scale: f64,
+
+    /// The number of octaves of noise.
+    pub octaves: usize,
+
+    /// The color where the noise is lowest.
+    pub low: Color,
+
+    /// The color where the noise is highest.
+    pub high: Color,
+}
+
+impl Texture for Fractal {
+    fn color_at(&self, _uv: (f64, f64), position: Vec3<f64>) -> Color {
+        let t = (self.noise.fbm(position * self.scale, self.octaves) * 0.5 + 0.5).clamp(0.0, 1.0);
+
+        self.low * (1.0 - t) + self.high * t
+    }
+}
+
+/// A solid texture of marble, as veins along the x axis distorted by turbulence.
+///
+/// Does not require uv coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Marble {
+    /// The noise.
+    pub noise: Perlin,
+
+    /// The frequency of the veins, per unit of space.
+    pub scale: f64,
+
+    /// How far the veins are distorted.
+    pub turbulence: f64,
+
+    /// The color of the stone.
+    pub base: Color,
+
+    /// The color of the veins.
+    pub vein: Color,
+}
+
+impl Texture for Marble {
+    fn color_at(&self, _uv: (f64, f64), position: Vec3<f64>) -> Color {
+        let phase = position.x * self.scale + self.turbulence * self.noise.turbulence(position * self.scale, 6);
+
+        // Sharpens the veins, so that most of the surface is the base color.
+        let t = (1.0 - (0.5 + 0.5 * phase.sin())).powi(4);
+
+        self.base * (1.0 - t) + self.vein * t
+    }
+}
+
+/// A solid texture of wood, as rings around the y axis distorted by noise.
+///
+/// Does not require uv coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wood {
+    /// The noise.
+    pub noise: Perlin,
+
+    /// The number of rings per unit of space.
+    pub scale: f64,
+
+    /// How far the rings are distorted.
+    pub turbulence: f64,
+
+    /// The color between the rings.
+    pub light: Color,
+
+    /// The color of the rings.
+    pub dark: Color,
+}
+
+impl Texture for Wood {
+    fn color_at(&self, _uv: (f64, f64), position: Vec3<f64>) -> Color {
+        let distance = (position.x * position.x + position.z * position.z).sqrt();
+        let rings = distance * self.scale + self.turbulence * self.noise.fbm(position * 2.0, 4);
+
+        let t = rings - rings.floor();
+
+        self.light * (1.0 - t) + self.dark * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded() {
+        let p = Vec3::new(0.3, 1.7, -2.2);
+
+        assert_eq!(Perlin::new(7).noise(p), Perlin::new(7).noise(p));
+        assert_ne!(Perlin::new(7).noise(p), Perlin::new(8).noise(p));
+    }
+
+    #[test]
+    fn bounded() {
+        let noise = Perlin::new(1);
+
+        for i in 0..1000 {
+            let p = Vec3::new(i as f64 * 0.137, i as f64 * -0.071, i as f64 * 0.0293);
+
+            assert!(noise.noise(p).abs() <= 1.1);
+            assert!(noise.turbulence(p, 4) >= 0.0);
+            assert!(noise.fbm(p, 4).abs() <= 2.2);
+        }
+    }
+
+    #[test]
+    fn continuous() {
+        let noise = Perlin::new(2);
+        let p = Vec3::new(4.5, 0.25, 9.75);
+
+        assert!((noise.noise(p) - noise.noise(p + Vec3::new(0.0001, 0.0, 0.0))).abs() < 0.001);
+    }
+}
@@ -1,12 +1,16 @@
 use crate::vector::Vec3;
 use crate::ray::Ray;
 use crate::matrix::Matrix;
-use crate::raytrace::Raytrace;
+use crate::raytrace::*;
 use crate::color::Color;
-use crate::tri::Tri;
+use crate::tri::*;
+use crate::aabb::Aabb;
+use crate::bezier::BezierPatch;
+use crate::subdivision::*;
 
 use std::ops::*;
 use std::fmt;
+use std::collections::HashMap;
 
 macro_rules! offset_point_tri_helix {
     ( $pos: ident, $size: ident, $col: ident, $noise: ident, $t: ty, $( $p1: expr, $p2: expr, $p3: expr, $p4: expr, $p5: expr, $p6: expr, $p7: expr, $p8: expr, $p9: expr );+ ) => {
@@ -18,16 +22,16 @@ macro_rules! offset_point_tri_cube {
     ( $pos: ident, $size: ident, $col: ident, $noise: ident, $t: ty ) => {
         offset_point_tri_helix!($pos, $size, $col, $noise, $t,
             -1.0, -1.0, -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0;
-            -1.0, -1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0;
-            -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 1.0;
+            -1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0, -1.0;
+            -1.0, -1.0, 1.0, 1.0, 1.0, 1.0, -1.0, 1.0, 1.0;
             -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0;
             1.0, -1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0;
-            1.0, -1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, -1.0;
-            -1.0, -1.0, 1.0, -1.0, -1.0, -1.0, -1.0, 1.0, -1.0;
+            1.0, -1.0, 1.0, 1.0, 1.0, -1.0, 1.0, 1.0, 1.0;
+            -1.0, -1.0, 1.0, -1.0, 1.0, -1.0, -1.0, -1.0, -1.0;
             -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0;
             1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, -1.0, -1.0;
-            -1.0, -1.0, -1.0, -1.0, -1.0, 1.0, 1.0, -1.0, -1.0;
-            1.0, 1.0, 1.0, -1.0, 1.0, 1.0, 1.0, 1.0, -1.0;
+            -1.0, -1.0, -1.0, 1.0, -1.0, -1.0, -1.0, -1.0, 1.0;
+            1.0, 1.0, 1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0;
             -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0, 1.0, -1.0
         )
     }
@@ -59,7 +63,7 @@ pub struct Object<T> {
     /// Bounds cache must be recalculated or cleared upon mutating the object through a non built-in method:
     /// `obj.recalculate_bounds()`
     /// `obj.bounds_cache = None` (SLOW)
-    pub bounds_cache: Option<Vec<Tri<T>>>
+    pub bounds_cache: Option<Aabb<T>>
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -75,6 +79,13 @@ const HEADER_LEN: usize = 80;
 const PRECISION_LEN: usize = 4;
 const ATTR_LEN: usize = 2;
 
+/// Rounds a point to the nearest millionth of a unit, so that points within a millionth of each other are joined.
+fn weld_key(point: Vec3<f64>) -> (i64, i64, i64) {
+    let point = point * 1000000.0;
+
+    (point.x.round() as i64, point.y.round() as i64, point.z.round() as i64)
+}
+
 impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Object<T> {
     /// Creates a blank object.
     pub fn new() -> Self {
@@ -85,13 +96,7 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
     fn recalculate_bounds(&mut self) where f64: From<T> {
         let bounds = self.bounds();
 
-        let centre = (bounds.0 + bounds.1) * <_ as Into<T>>::into(0.5);
-        let size = bounds.1 - bounds.0;
-
-        let col = Color::new(0.0, 0.0, 0.0);
-        let noi = 0.0;
-
-        self.bounds_cache = Some(offset_point_tri_cube!(centre, size, col, noi, T));
+        self.bounds_cache = Some(Aabb::new(bounds.0, bounds.1));
     }
 
     fn vec3_from_f32(slice: &[u8]) -> Vec3<T> {
@@ -143,7 +148,127 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
         Ok(out)
     }
 
+    /// Gives each tri normals at its points, averaged from the tris sharing each point, for smooth shading.
+    ///
+    /// Points are shared when they are within a millionth of a unit of each other.
+    /// Tris which meet at more than the angle, in degrees, are not averaged together, keeping a sharp crease.
+    ///
+    /// Tris must be wound consistently, as in stl files, so that their normals all face outwards or all face inwards.
+    pub fn compute_smooth_normals(mut self, angle: f64) -> Self where f64: From<T> {
+        let threshold = angle.to_radians().cos();
+
+        let weld = |point: Vec3<T>| weld_key(point.to_f64());
+
+        let mut shared: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        for (i, tri) in self.tris.iter().enumerate() {
+            for point in [tri.bounds.x, tri.bounds.y, tri.bounds.z] {
+                shared.entry(weld(point)).or_default().push(i);
+            }
+        }
+
+        // Normals of each tri, with the same orientation as its plane, and length of twice its area.
+        let faces: Vec<Vec3<f64>> = self.tris.iter().map(|tri| {
+            let (p1, p2, p3) = (tri.bounds.x.to_f64(), tri.bounds.y.to_f64(), tri.bounds.z.to_f64());
+
+            (p1 - p2).cross(&(p1 - p3))
+        }).collect();
+
+        let unit = |x: Vec3<f64>| if x.length() > 0.0 { x.unit() } else { x };
+
+        let normals: Vec<Vec3<Vec3<T>>> = self.tris.iter().enumerate().map(|(i, tri)| {
+            let face = unit(faces[i]);
+
+            // Larger tris are weighted more heavily.
+            let smooth = |point: Vec3<T>| {
+                let mut sum = Vec3::new(0.0, 0.0, 0.0);
+
+                for &j in &shared[&weld(point)] {
+                    if unit(faces[j]) * face >= threshold {
+                        sum = sum + faces[j];
+                    }
+                }
+
+                if sum.length() > 0.0 { Vec3::from_f64(sum.unit()) } else { tri.plane.normal }
+            };
+
+            Vec3::new(smooth(tri.bounds.x), smooth(tri.bounds.y), smooth(tri.bounds.z))
+        }).collect();
+
+        for (tri, normals) in self.tris.iter_mut().zip(normals) {
+            tri.normals = Some(normals);
+        }
+
+        self
+    }
+
+    /// Creates a new object by tessellating Bézier patches, such as those read by [BezierPatch::from_bpt].
+    ///
+    /// Each patch is split into more tris where it bends more, keeping within tolerance of the surface,
+    /// and is smooth shaded by the normals of the patch.
+    pub fn from_bezier(patches: &[BezierPatch<T>], tolerance: f64, color: Color, roughness: f64) -> Self where f64: From<T> {
+        let mut out = Self::new();
+
+        for patch in patches {
+            out.tris.extend(patch.tessellate(tolerance, color, roughness));
+        }
+
+        out.recalculate_bounds();
+
+        out
+    }
+
+    /// Subdivides the tris of the object a number of times, rounding it toward a smooth surface.
+    ///
+    /// Tris are joined where their points are within a millionth of a unit of each other,
+    /// and each keeps the color and roughness of the tri it was split from.
+    ///
+    /// Catmull-Clark gives quads, which are each split into 2 tris.
+    /// The result is flat shaded, so may be followed by [Object::compute_smooth_normals].
+    pub fn subdivide(self, scheme: Subdivision, levels: usize) -> Self where f64: From<T> {
+        let mut indices: HashMap<(i64, i64, i64), usize> = HashMap::new();
+        let mut vertices = vec![];
+        let mut faces = vec![];
+        let mut materials = vec![];
+
+        for tri in &self.tris {
+            let face: Vec<usize> = [tri.bounds.x, tri.bounds.y, tri.bounds.z].iter().map(|point| {
+                let point = point.to_f64();
+
+                *indices.entry(weld_key(point)).or_insert_with(|| {
+                    vertices.push(point);
+
+                    vertices.len() - 1
+                })
+            }).collect();
+
+            // Tris joined to a line or point have no area to subdivide.
+            if face[0] == face[1] || face[1] == face[2] || face[2] == face[0] {
+                continue;
+            }
+
+            faces.push(face);
+            materials.push((tri.plane.color, tri.plane.roughness));
+        }
+
+        let mesh = ControlMesh::new(vertices, faces).subdivide(scheme, levels).triangulate();
+
+        let mut out = Self::new();
+
+        for (face, group) in mesh.faces.iter().zip(mesh.groups) {
+            let (color, roughness) = materials[group];
+            let point = |i: usize| Vec3::from_f64(mesh.vertices[face[i]]);
+
+            out.tris.push(Tri::new(point(0), point(1), point(2), color, roughness));
+        }
+
+        out.recalculate_bounds();
+
+        out
+    }
+
     /// Returns a box, with center at origin.
+    ///
+    /// Made of 12 tris facing outwards, so can be transformed with the object; for an exact box see [crate::cuboid::Cuboid].
     pub fn new_box(origin: Vec3<T>, size: Vec3<T>, color: Color, roughness: f64) -> Self where T: Neg<Output = T>, f64: From<T> {
         let size = size * <_ as Into<T>>::into(0.5);
 
@@ -229,13 +354,17 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
     /// Moves all tris in an object by a set vector.
     pub fn translate(mut self, offset: Vec3<T>) -> Self where f64: From<T> {
         for i in 0..self.tris.len() {
-            self.tris[i] = Tri::new(
-                self.tris[i].bounds.x + offset,
-                self.tris[i].bounds.y + offset,
-                self.tris[i].bounds.z + offset,
-                self.tris[i].plane.color,
-                self.tris[i].plane.roughness,
-            );
+            self.tris[i] = Tri {
+                uvs: self.tris[i].uvs,
+                normals: self.tris[i].normals,
+                ..Tri::new(
+                    self.tris[i].bounds.x + offset,
+                    self.tris[i].bounds.y + offset,
+                    self.tris[i].bounds.z + offset,
+                    self.tris[i].plane.color,
+                    self.tris[i].plane.roughness,
+                )
+            };
         }
 
         self.recalculate_bounds();
@@ -246,13 +375,17 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
     /// Transforms the object with a Matrix transformation.
     pub fn transform(mut self, mat: Matrix<T>) -> Self where f64: From<T> {
         for i in 0..self.tris.len() {
-            self.tris[i] = Tri::new(
-                fast_transform!(self.tris[i].bounds.x, mat),
-                fast_transform!(self.tris[i].bounds.y, mat),
-                fast_transform!(self.tris[i].bounds.z, mat),
-                self.tris[i].plane.color,
-                self.tris[i].plane.roughness,
-            );
+            self.tris[i] = Tri {
+                uvs: self.tris[i].uvs,
+                normals: self.tris[i].normals.map(|n| Vec3::new(mat.transform_normal(n.x), mat.transform_normal(n.y), mat.transform_normal(n.z))),
+                ..Tri::new(
+                    fast_transform!(self.tris[i].bounds.x, mat),
+                    fast_transform!(self.tris[i].bounds.y, mat),
+                    fast_transform!(self.tris[i].bounds.z, mat),
+                    self.tris[i].plane.color,
+                    self.tris[i].plane.roughness,
+                )
+            };
         }
 
         self.recalculate_bounds();
@@ -285,15 +418,8 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
 impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Object<T> {
     fn intersects(&self, ray: &Ray<T>) -> (Option<&Tri<T>>, T) {
         match &self.bounds_cache {
-            Some(bound) => {
-                let mut within_bounds = false;
-                for tri in bound {
-                    if tri.intersects_along(&ray).is_some() {
-                        within_bounds = true;
-                        break;
-                    }
-                }
-                if !within_bounds {
+            Some(bounds) => {
+                if !bounds.hits(ray) {
                     return (None, <_ as Into<T>>::into(0.0));
                 }
             },
@@ -318,6 +444,46 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
 
         (closest_tri, lowest)
     }
+
+    /// Every distance along the line of the ray at which it crosses a tri, sorted,
+    /// with whether it enters the object there, by the direction the tri faces.
+    ///
+    /// Crossings through a shared edge are counted once,
+    /// and dropped where they enter one tri and leave another, as when grazing the silhouette.
+    fn crossings(&self, ray: &Ray<T>) -> Vec<(f64, bool)> {
+        let (origin, direction) = (ray.origin.to_f64(), ray.direction.to_f64());
+
+        let mut hits: Vec<(f64, bool)> = self.tris.iter().filter_map(|tri| {
+            let points = [tri.bounds.x.to_f64(), tri.bounds.y.to_f64(), tri.bounds.z.to_f64()];
+            let distance = moller_trumbore(origin, direction, points)?.0;
+
+            Some((distance, tri.plane.normal.to_f64() * direction < 0.0))
+        }).collect();
+
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Groups crossings at the same distance, keeping the net direction they cross in.
+        let mut crossings = Vec::new();
+        let mut i = 0;
+
+        while i < hits.len() {
+            let mut net = 0;
+            let mut j = i;
+
+            while j < hits.len() && hits[j].0 - hits[i].0 < 1e-9 {
+                net += if hits[j].1 { 1 } else { -1 };
+                j += 1;
+            }
+
+            if net != 0 {
+                crossings.push((hits[i].0, net > 0));
+            }
+
+            i = j;
+        }
+
+        crossings
+    }
 }
 
 impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Object<T> {
@@ -330,11 +496,123 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
         }
     }
 
+    /// Matches the crossings of the tris entering the object with those leaving it,
+    /// so the object must be closed, with its tris facing outwards, as in stl files.
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        if let Some(bounds) = &self.bounds_cache {
+            if bounds.slab(ray).is_none() {
+                return Some(Vec::new());
+            }
+        }
+
+        Some(crossing_intervals(self.crossings(ray)).into_iter().map(|(start, end)| (start.into(), end.into())).collect())
+    }
+
     fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
         self.intersects(ray).0?.transmit(ray)
     }
 
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.intersects(ray).0?.normal(ray)
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.intersects(ray).0?.uv(ray)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.intersects(ray).0?.uv_tangents(ray)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.intersects(ray).0?.scatter_pdf(ray, direction)
+    }
+
     fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
         self.intersects(_ray).0.unwrap().recolor(_ray, color)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_faces_outwards() {
+        let origin = Vec3::new(1.0, -2.0, 0.5);
+        let cube: Object<f64> = Object::new_box(origin, Vec3::new(2.0, 4.0, 1.0), Color::new(1.0, 1.0, 1.0), 1.0);
+
+        // Every tri faces away from the center, so crossings can be told apart as entering or leaving.
+        for tri in &cube.tris {
+            let center = (tri.bounds.x + tri.bounds.y + tri.bounds.z) / 3.0;
+
+            assert!(tri.plane.normal * (center - origin) > 0.0);
+        }
+    }
+
+    #[test]
+    fn smooth_normals() {
+        let cube: Object<f64> = Object::new_box(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0), Color::new(1.0, 1.0, 1.0), 1.0);
+
+        // Every edge of a cube is a crease at 30 degrees.
+        let creased = cube.compute_smooth_normals(30.0);
+        for tri in &creased.tris {
+            let normals = tri.normals.unwrap();
+
+            for normal in [normals.x, normals.y, normals.z] {
+                assert!((normal - tri.plane.normal).length() < 1e-9);
+            }
+        }
+
+        // An octahedron, wound consistently, has at most 109.5 degrees between faces around each point, so is rounded at 120 degrees.
+        let mut octahedron: Object<f64> = Object::new();
+        for sign in [(1.0, 1.0, 1.0), (-1.0, 1.0, 1.0), (1.0, -1.0, 1.0), (1.0, 1.0, -1.0), (-1.0, -1.0, 1.0), (-1.0, 1.0, -1.0), (1.0, -1.0, -1.0), (-1.0, -1.0, -1.0)] {
+            let (p1, p2, p3) = (Vec3::new(sign.0, 0.0, 0.0), Vec3::new(0.0, sign.1, 0.0), Vec3::new(0.0, 0.0, sign.2));
+            let color = Color::new(1.0, 1.0, 1.0);
+
+            octahedron.tris.push(if sign.0 * sign.1 * sign.2 > 0.0 { Tri::new(p1, p2, p3, color, 1.0) } else { Tri::new(p1, p3, p2, color, 1.0) });
+        }
+
+        let rounded = octahedron.compute_smooth_normals(120.0);
+        for tri in &rounded.tris {
+            let normals = tri.normals.unwrap();
+
+            for (point, normal) in [(tri.bounds.x, normals.x), (tri.bounds.y, normals.y), (tri.bounds.z, normals.z)] {
+                assert!((normal - point).length() < 1e-9);
+            }
+        }
+
+        // Normals are kept perpendicular to the surface when transformed.
+        let transformed = creased.scale(Vec3::new(3.0, 1.0, 0.5)).rotate(Vec3::new(30.0, 45.0, 0.0));
+        for tri in &transformed.tris {
+            assert!((tri.normals.unwrap().y - tri.plane.normal).length() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn intervals() {
+        let cube: Object<f64> = Object::new_box(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0), Color::new(1.0, 1.0, 1.0), 1.0);
+
+        // Through the diagonal of a face, crossing the edge shared by its two tris.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        let intervals = cube.intervals(&ray).unwrap();
+        assert_eq!(intervals.len(), 1);
+        assert!((intervals[0].0 - 4.0).abs() < 1e-9 && (intervals[0].1 - 6.0).abs() < 1e-9);
+
+        // From inside, the entry is behind the ray.
+        let inside = Ray::new(Vec3::new(0.2, 0.3, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!((cube.intervals(&inside).unwrap()[0].0 + 1.2).abs() < 1e-9);
+
+        assert_eq!(cube.intervals(&Ray::new(Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))).unwrap(), vec![]);
+
+        // Grazing the edge of one cube does not swap inside and outside for the next.
+        let mut cubes: Object<f64> = Object::new_box(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0), Color::new(1.0, 1.0, 1.0), 1.0);
+        cubes.tris.extend(Object::new_box(Vec3::new(-3.0, 0.0, -5.0), Vec3::new(2.0, 2.0, 2.0), Color::new(1.0, 1.0, 1.0), 1.0).tris);
+        cubes.recalculate_bounds();
+
+        let grazing = Ray::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, -1.0));
+        let intervals = cubes.intervals(&grazing).unwrap();
+        assert_eq!(intervals.len(), 1);
+        assert!((intervals[0].0 - 4.0 * 2.0_f64.sqrt()).abs() < 1e-9 && (intervals[0].1 - 6.0 * 2.0_f64.sqrt()).abs() < 1e-9);
+    }
+}
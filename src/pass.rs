@@ -0,0 +1,76 @@
+use crate::image::*;
+
+/// An auxiliary image which can be rendered alongside the scene.
+///
+/// Other than samples, passes are taken from the first intersection of a ray through each pixel's centre,
+/// and store raw values which may fall outside of 0 to 1, so are best saved with [Image::to_pfm].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pass {
+    /// The number of samples taken by each pixel, as a heatmap.
+    ///
+    /// Black is no samples, white is the maximum allowed by the scene's sampling.
+    Samples,
+
+    /// The distance from the camera, in every channel.
+    ///
+    /// Infinite where the ray escapes to the environment.
+    Depth,
+
+    /// The world space normal, with x, y and z stored as r, g and b.
+    Normal,
+
+    /// The color of the surface, independent of lighting.
+    Albedo,
+
+    /// The index of the object within the scene, in every channel.
+    ///
+    /// -1 where the ray escapes to the environment.
+    Object,
+
+    /// The world space position, with x, y and z stored as r, g and b.
+    Position,
+}
+
+impl Pass {
+    /// Every pass.
+    pub const ALL: [Pass; 6] = [Pass::Samples, Pass::Depth, Pass::Normal, Pass::Albedo, Pass::Object, Pass::Position];
+
+    /// The lowercase name of the pass.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::Samples => "samples",
+            Pass::Depth => "depth",
+            Pass::Normal => "normal",
+            Pass::Albedo => "albedo",
+            Pass::Object => "object",
+            Pass::Position => "position",
+        }
+    }
+
+    /// Finds a pass from its lowercase name.
+    ///
+    /// ```
+    /// # use rusttracing::pass::*;
+    /// assert_eq!(Pass::from_name("normal"), Some(Pass::Normal));
+    /// assert_eq!(Pass::from_name("beauty"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Pass> {
+        Pass::ALL.into_iter().find(|pass| pass.name() == name)
+    }
+}
+
+/// The result of rendering a scene.
+pub struct Render<const WIDTH: usize, const HEIGHT: usize> {
+    /// The rendered image.
+    pub image: Image<WIDTH, HEIGHT>,
+
+    /// Every requested pass, along with its image.
+    pub passes: Vec<(Pass, Image<WIDTH, HEIGHT>)>,
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Render<WIDTH, HEIGHT> {
+    /// Returns the image for a pass, if it was requested.
+    pub fn pass(&self, pass: Pass) -> Option<&Image<WIDTH, HEIGHT>> {
+        self.passes.iter().find(|x| x.0 == pass).map(|x| &x.1)
+    }
+}
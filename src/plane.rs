@@ -2,7 +2,7 @@ use rand::Rng;
 
 use crate::vector::Vec3;
 use crate::ray::Ray;
-use crate::raytrace::Raytrace;
+use crate::raytrace::*;
 use crate::color::Color;
 
 use std::ops::*;
@@ -43,6 +43,16 @@ impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output
         // r.n = d
         Plane::<T> { normal, offset: p1 * normal, color, roughness }
     }
+
+    /// Two unit directions within the plane, perpendicular to each other.
+    pub fn tangents(&self) -> (Vec3<f64>, Vec3<f64>) where T: Into<f64> {
+        let normal = self.normal.to_f64();
+
+        let other = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let tangent = normal.cross(&other).unit();
+
+        (tangent, normal.cross(&tangent))
+    }
 }
 
 impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Plane<T> {
@@ -62,6 +72,28 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
         Some(distance)
     }
 
+    /// The half of space behind the normal.
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        let divisor = ray.direction * self.normal;
+        let behind = self.offset - (ray.origin * self.normal);
+
+        if divisor == 0.0.into() {
+            if behind < 0.0.into() {
+                return Some(Vec::new());
+            }
+
+            return Some(vec![(f64::NEG_INFINITY.into(), f64::INFINITY.into())]);
+        }
+
+        let distance = behind / divisor;
+
+        if divisor > 0.0.into() {
+            Some(vec![(f64::NEG_INFINITY.into(), distance)])
+        } else {
+            Some(vec![(distance, f64::INFINITY.into())])
+        }
+    }
+
     fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
         // Uses ThreadRng::Default() so is not re-seeded.
         let mut rng = rand::thread_rng();
@@ -76,6 +108,32 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
         Some(Ray::new(pos, direction + random * <f64 as Into<T>>::into(self.roughness)))
     }
 
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.intersects_along(ray)?;
+
+        Some(self.normal)
+    }
+
+    /// Planar coordinates, tiling every unit along two directions within the plane.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let pos = self.intersects_at(ray)?.to_f64();
+        let (tangent, bitangent) = self.tangents();
+
+        Some((pos * tangent, pos * bitangent))
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.intersects_along(ray)?;
+
+        Some(self.tangents())
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.intersects_along(ray)?;
+
+        reflection_pdf(ray.direction.to_f64(), self.normal.to_f64(), self.roughness, direction.to_f64())
+    }
+
     fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
         let mut out = color;
 
@@ -0,0 +1,173 @@
+/// The real roots of `ax² + bx + c = 0`, in increasing order.
+///
+/// Falls back to the linear equation when a is 0.
+///
+/// ```
+/// # use rusttracing::polynomial::*;
+/// assert_eq!(quadratic(1.0, -3.0, 2.0), vec![1.0, 2.0]);
+/// assert_eq!(quadratic(1.0, 0.0, 1.0), vec![]);
+/// ```
+pub fn quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return Vec::new();
+        }
+
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant < 0.0 {
+        return Vec::new();
+    }
+
+    // Avoids cancellation between b and the root of the discriminant.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+
+    if q == 0.0 {
+        return vec![0.0, 0.0];
+    }
+
+    let (x1, x2) = (q / a, c / q);
+
+    if x1 < x2 {
+        vec![x1, x2]
+    } else {
+        vec![x2, x1]
+    }
+}
+
+/// Evaluates a polynomial, with coefficients from the highest power down.
+pub fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().fold(0.0, |total, coefficient| total * x + coefficient)
+}
+
+/// The derivative of a polynomial, with coefficients from the highest power down.
+fn derivative(coefficients: &[f64]) -> Vec<f64> {
+    let degree = coefficients.len() - 1;
+
+    coefficients[..degree].iter().enumerate().map(|(i, coefficient)| coefficient * (degree - i) as f64).collect()
+}
+
+/// Narrows down the root between two bounds, where the polynomial changes sign.
+///
+/// Takes Newton steps where they stay within the bounds, and bisects otherwise.
+fn refine(coefficients: &[f64], slope: &[f64], mut low: f64, mut high: f64) -> f64 {
+    let low_sign = evaluate(coefficients, low) < 0.0;
+    let mut x = 0.5 * (low + high);
+
+    for _i in 0..100 {
+        let value = evaluate(coefficients, x);
+
+        if value == 0.0 {
+            break;
+        }
+
+        if (value < 0.0) == low_sign {
+            low = x;
+        } else {
+            high = x;
+        }
+
+        let newton = x - value / evaluate(slope, x);
+        let next = if newton > low && newton < high { newton } else { 0.5 * (low + high) };
+
+        if (next - x).abs() <= 1e-14 * (1.0 + x.abs()) {
+            return next;
+        }
+
+        x = next;
+    }
+
+    x
+}
+
+/// The real roots of a polynomial of any degree, in increasing order,
+/// with coefficients from the highest power down.
+///
+/// Between each pair of roots of the derivative the polynomial only rises or falls,
+/// so holds at most one root, which is found by [refine].
+/// Used for the quartic of a [crate::torus::Torus].
+///
+/// Roots where the polynomial only touches zero, without crossing, may be missed.
+///
+/// ```
+/// # use rusttracing::polynomial::*;
+/// // (x + 3)(x + 1)(x - 2)(x - 4)
+/// let roots = roots(&[1.0, -2.0, -13.0, 14.0, 24.0]);
+///
+/// assert_eq!(roots.len(), 4);
+/// for (root, expected) in roots.iter().zip([-3.0, -1.0, 2.0, 4.0]) {
+///     assert!((root - expected).abs() < 1e-9);
+/// }
+/// ```
+pub fn roots(coefficients: &[f64]) -> Vec<f64> {
+    let leading = coefficients.iter().position(|&x| x != 0.0).unwrap_or(coefficients.len());
+    let coefficients = &coefficients[leading..];
+
+    match coefficients.len() {
+        0 | 1 => return Vec::new(),
+        2 => return quadratic(0.0, coefficients[0], coefficients[1]),
+        3 => return quadratic(coefficients[0], coefficients[1], coefficients[2]),
+        _ => {}
+    }
+
+    // Every root lies within the Cauchy bound.
+    let bound = 1.0 + coefficients[1..].iter().map(|x| (x / coefficients[0]).abs()).fold(0.0, f64::max);
+
+    let slope = derivative(coefficients);
+
+    let mut points = vec![-bound];
+    points.extend(roots(&slope).into_iter().filter(|x| x.abs() < bound));
+    points.push(bound);
+
+    let mut out = Vec::new();
+
+    for pair in points.windows(2) {
+        let (low, high) = (pair[0], pair[1]);
+        let (low_value, high_value) = (evaluate(coefficients, low), evaluate(coefficients, high));
+
+        if low_value == 0.0 {
+            out.push(low);
+        } else if (low_value < 0.0) != (high_value < 0.0) && high_value != 0.0 {
+            out.push(refine(coefficients, &slope, low, high));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quadratics() {
+        assert_eq!(quadratic(2.0, 0.0, -8.0), vec![-2.0, 2.0]);
+        assert_eq!(quadratic(0.0, 2.0, -1.0), vec![0.5]);
+        assert_eq!(quadratic(0.0, 0.0, 1.0), vec![]);
+
+        // Would lose the small root to cancellation if solved directly.
+        let roots = quadratic(1.0, -1e8, 1.0);
+        assert!((roots[0] - 1e-8).abs() < 1e-20);
+    }
+
+    #[test]
+    fn quartics() {
+        // (x² + 1)(x - 0.5)(x - 3), with only two real roots.
+        let found = roots(&[1.0, -3.5, 2.5, -3.5, 1.5]);
+        assert_eq!(found.len(), 2);
+        assert!((found[0] - 0.5).abs() < 1e-9);
+        assert!((found[1] - 3.0).abs() < 1e-9);
+
+        // Closely spaced roots, as where a ray grazes a torus.
+        let (a, b, c, d) = (-2.0, -1.999, 1.0, 1.001);
+        let coefficients = [1.0, -(a + b + c + d), a * b + a * c + a * d + b * c + b * d + c * d, -(a * b * c + a * b * d + a * c * d + b * c * d), a * b * c * d];
+        let found = roots(&coefficients);
+        assert_eq!(found.len(), 4);
+        for (root, expected) in found.iter().zip([a, b, c, d]) {
+            assert!((root - expected).abs() < 1e-9);
+        }
+    }
+}
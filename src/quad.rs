@@ -0,0 +1,170 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+
+use std::ops::*;
+
+/// Quad.
+///
+/// A flat parallelogram, from a corner and the two edges leaving it.
+///
+/// Facing along the cross product of the u and v edges.
+///
+/// Implements the Raytrace and SampleArea traits, so can be used as a rectangular [crate::light::AreaLight].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad<T> {
+    /// The corner at (0, 0) in uv coordinates.
+    pub origin: Vec3<T>,
+
+    /// The edge from the origin to (1, 0) in uv coordinates.
+    pub u: Vec3<T>,
+
+    /// The edge from the origin to (0, 1) in uv coordinates.
+    pub v: Vec3<T>,
+
+    /// Colour of the quad.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T> Quad<T> {
+    /// Default constructor.
+    pub fn new(origin: Vec3<T>, u: Vec3<T>, v: Vec3<T>, color: Color) -> Self {
+        Quad { origin, u, v, color, roughness: 1.0 }
+    }
+}
+
+impl<T: Copy + Into<f64>> Quad<T> {
+    /// The unit normal of the quad.
+    pub fn unit_normal(&self) -> Vec3<f64> {
+        self.u.to_f64().cross(&self.v.to_f64()).unit()
+    }
+
+    /// The area of the quad.
+    pub fn area(&self) -> f64 {
+        self.u.to_f64().cross(&self.v.to_f64()).length()
+    }
+
+    /// The distance along the ray to the quad, with the uv coordinates there.
+    fn hit(&self, ray: &Ray<T>) -> Option<(f64, (f64, f64))> {
+        let (u, v) = (self.u.to_f64(), self.v.to_f64());
+        let normal = u.cross(&v);
+
+        let divisor = ray.direction.to_f64() * normal;
+        if divisor == 0.0 {
+            return None;
+        }
+
+        let distance = ((self.origin.to_f64() - ray.origin.to_f64()) * normal) / divisor;
+        if distance < 0.01 {
+            return None;
+        }
+
+        // The coordinates of the intersection along each edge, by the area it makes with the other edge.
+        let offset = ray.origin.to_f64() + ray.direction.to_f64() * distance - self.origin.to_f64();
+        let area = normal * normal;
+
+        let a = offset.cross(&v) * normal / area;
+        let b = u.cross(&offset) * normal / area;
+
+        if !(0.0..=1.0).contains(&a) || !(0.0..=1.0).contains(&b) {
+            return None;
+        }
+
+        Some((distance, (a, b)))
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Quad<T> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.hit(ray)?.0.into())
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let distance = self.hit(ray)?.0;
+
+        Some(Ray::new(ray.at(T::from(distance)), Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), self.unit_normal(), self.roughness))))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.hit(ray)?;
+
+        Some(Vec3::from_f64(self.unit_normal()))
+    }
+
+    /// Covered from 0 to 1 along each edge.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        Some(self.hit(ray)?.1)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.hit(ray)?;
+
+        Some((self.u.to_f64(), self.v.to_f64()))
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.hit(ray)?;
+
+        let normal = self.unit_normal();
+        reflection_pdf(ray.direction.to_f64(), normal, self.roughness, direction.to_f64())
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+impl<T: Copy + Into<f64>> SampleArea for Quad<T> {
+    /// Uniform over the area of the quad.
+    fn sample_area(&self, u: f64, v: f64) -> (Vec3<f64>, Vec3<f64>, f64) {
+        let point = self.origin.to_f64() + self.u.to_f64() * u + self.v.to_f64() * v;
+
+        (point, self.unit_normal(), 1.0 / self.area())
+    }
+
+    fn pdf_area(&self, _u: f64, _v: f64) -> f64 {
+        1.0 / self.area()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        let quad = Quad::new(Vec3::new(-1.0, -1.0, 5.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Color::new(1.0, 1.0, 1.0));
+
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(quad.intersects_along(&ray), Some(5.0));
+        assert_eq!(quad.normal(&ray), Some(Vec3::new(0.0, 0.0, 1.0)));
+        assert_eq!(quad.uv(&ray), Some((0.25, 0.75)));
+
+        // Past each edge.
+        assert_eq!(quad.intersects_along(&Ray::new(Vec3::new(3.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0))), None);
+        assert_eq!(quad.intersects_along(&Ray::new(Vec3::new(0.0, -1.5, 0.0), Vec3::new(0.0, 0.0, 1.0))), None);
+
+        // Reflects from either side.
+        let out = Quad { roughness: 0.0, ..quad }.transmit(&ray).unwrap();
+        assert_eq!(out.direction, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn sample() {
+        let quad = Quad::new(Vec3::new(0.0, 4.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 3.0), Color::new(1.0, 1.0, 1.0));
+
+        let (point, normal, pdf) = quad.sample_area(0.5, 0.5);
+        assert_eq!(point, Vec3::new(1.0, 4.0, 1.5));
+        assert_eq!(normal, Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(pdf, 1.0 / 6.0);
+
+        // Straight up from below, 4 away, the density over solid angle is scaled by the squared distance.
+        let (direction, pdf) = quad.sample_toward(Vec3::new(1.0, 0.0, 1.5), 0.5, 0.5).unwrap();
+        assert_eq!(direction, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(pdf, 16.0 / 6.0);
+    }
+}
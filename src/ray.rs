@@ -2,14 +2,19 @@ use crate::vector::Vec3;
 
 use std::ops::*;
 
-/// A 3D ray object, that has an origin, and direction.
+/// A 3D ray object, that has an origin, direction, and time.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Ray<T> {
     /// The start position of the ray.
     pub origin: Vec3<T>,
 
     /// The direction of the ray as a unit vector.
-    pub direction: Vec3<T>
+    pub direction: Vec3<T>,
+
+    /// The time the ray is cast at, within the shutter of the camera.
+    ///
+    /// Moving objects are placed where they are at this time.
+    pub time: f64,
 }
 
 impl<T: Copy + Mul<Output = T> + Add<Output = T> + From<f64> + Into<f64>> Ray<T>
@@ -17,16 +22,27 @@ impl<T: Copy + Mul<Output = T> + Add<Output = T> + From<f64> + Into<f64>> Ray<T>
     /// A safe ray creation function,
     /// which transforms the direction into a unit vector.
     ///
+    /// The ray is cast at a time of 0.
+    ///
     /// ```
     /// # use rusttracing::vector::*;
     /// # use rusttracing::ray::*;
     /// assert_eq!(
     ///     Ray::new(Vec3::new(0.0, 1.0, 2.0), Vec3::new(1000.0, 0.0, 0.0)),
-    ///     Ray::<_> { origin: Vec3::new(0.0, 1.0, 2.0), direction: Vec3::new(1.0, 0.0, 0.0) }
+    ///     Ray::<_> { origin: Vec3::new(0.0, 1.0, 2.0), direction: Vec3::new(1.0, 0.0, 0.0), time: 0.0 }
     /// );
     /// ```
     pub fn new(origin: Vec3<T>, direction: Vec3<T>) -> Self {
-        Ray::<T> { origin: origin, direction: direction.unit() }
+        Ray::<T> { origin, direction: direction.unit(), time: 0.0 }
+    }
+}
+
+impl<T> Ray<T> {
+    /// Sets the time the ray is cast at.
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = time;
+
+        self
     }
 }
 
@@ -1,6 +1,9 @@
+use rand::Rng;
+
 use crate::vector::Vec3;
 use crate::ray::Ray;
 use crate::color::Color;
+use crate::medium::Medium;
 
 use std::ops::*;
 
@@ -22,4 +25,321 @@ pub trait Raytrace<T: Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T>
     fn intersects_at(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
         Some(ray.at(self.intersects_along(ray)?))
     }
+
+    /// Gives every interval of distance along the ray for which it is inside the object,
+    /// as sorted pairs of entry and exit distances.
+    ///
+    /// Covers the whole line of the ray, so distances may be negative or infinite.
+    ///
+    /// Returns None if the object does not enclose a volume, in which case it cannot be used within [crate::csg::Csg].
+    fn intervals(&self, _ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        None
+    }
+
+    /// Gives the unit normal of the surface where a ray intersects the object.
+    ///
+    /// Returns None if the ray does not intersect, or the object has no defined normal.
+    fn normal(&self, _ray: &Ray<T>) -> Option<Vec3<T>> {
+        None
+    }
+
+    /// Gives the uv texture coordinates of the surface where a ray intersects the object.
+    ///
+    /// Returns None if the ray does not intersect, or the object has no uv coordinates.
+    fn uv(&self, _ray: &Ray<T>) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// Gives the rate of change of position with the u and v texture coordinates,
+    /// where a ray intersects the object.
+    ///
+    /// Returns None if the ray does not intersect, or the object has no uv coordinates.
+    fn uv_tangents(&self, _ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        None
+    }
+
+    /// Gives the color of the surface where a ray intersects the object, independent of lighting.
+    fn albedo(&self, ray: &Ray<T>) -> Color {
+        self.recolor(ray, Color::new(1.0, 1.0, 1.0))
+    }
+
+    /// Gives the probability density, over solid angle, of transmit sending the ray out in a direction.
+    ///
+    /// Returns None if the density is not known, in which case the environment is not sampled directly from the object.
+    fn scatter_pdf(&self, _ray: &Ray<T>, _direction: &Vec3<T>) -> Option<f64> {
+        None
+    }
+
+    /// Gives the participating medium filling the object, such as fog within a [crate::medium::Volume].
+    ///
+    /// The medium fills the [Raytrace::intervals] of the object, so these must be given alongside it.
+    fn medium(&self) -> Option<Medium> {
+        None
+    }
+
+    /// Gives the density of the medium filling the object at a point and time, from 0 to 1, as a fraction of its [Raytrace::medium].
+    ///
+    /// Returns None if the medium is the same throughout.
+    fn density(&self, _point: &Vec3<T>, _time: f64) -> Option<f64> {
+        None
+    }
+
+    /// Gives the light given off by the front of the surface, the side its normal faces, such as by a [crate::light::AreaLight].
+    fn emission(&self) -> Option<Color> {
+        None
+    }
+
+    /// Chooses a direction from a point toward the surface, from two uniform random numbers between 0 and 1,
+    /// so that the light it gives off can be sampled directly.
+    ///
+    /// Returns the unit direction, and the probability density over solid angle of choosing it,
+    /// or None if the surface cannot be sampled.
+    fn sample_light(&self, _from: &Vec3<T>, _u: f64, _v: f64) -> Option<(Vec3<f64>, f64)> {
+        None
+    }
+
+    /// Gives the probability density, over solid angle, of sample_light choosing the direction of a ray from its origin,
+    /// where it intersects the object.
+    fn light_pdf(&self, _ray: &Ray<T>) -> Option<f64> {
+        None
+    }
+}
+
+/// The trait for surfaces which can be sampled by area, such as to be used as a [crate::light::AreaLight].
+pub trait SampleArea {
+    /// Chooses a point on the surface from two uniform random numbers between 0 and 1.
+    ///
+    /// Returns the point, the unit normal there, and the probability density over area of choosing it.
+    fn sample_area(&self, u: f64, v: f64) -> (Vec3<f64>, Vec3<f64>, f64);
+
+    /// The probability density over area of sample_area choosing the point at uv coordinates.
+    fn pdf_area(&self, u: f64, v: f64) -> f64;
+
+    /// Chooses a direction from a point toward the surface, as with sample_area.
+    ///
+    /// Returns the unit direction, and the probability density over solid angle of choosing it,
+    /// or None if the chosen point is seen edge on.
+    fn sample_toward(&self, from: Vec3<f64>, u: f64, v: f64) -> Option<(Vec3<f64>, f64)> {
+        let (point, normal, pdf) = self.sample_area(u, v);
+
+        let offset = point - from;
+        let distance_squared = offset * offset;
+        let direction = offset.unit();
+
+        let cos = (normal * direction).abs();
+        if cos == 0.0 || distance_squared == 0.0 {
+            return None;
+        }
+
+        // Converts from density over area to density over solid angle.
+        Some((direction, pdf * distance_squared / cos))
+    }
+}
+
+/// A closed shape found by solving for every crossing of its surface, shaded as a rough reflector,
+/// such as a [crate::cylinder::Cylinder] or a [crate::torus::Torus].
+///
+/// The Raytrace trait is then implemented by [solid_raytrace].
+pub(crate) trait Solid<T> {
+    /// Every distance along the ray at which it crosses the surface, in order, with the outward normal there.
+    fn hits(&self, ray: &Ray<T>) -> Vec<(f64, Vec3<f64>)>;
+
+    /// Colour of the surface.
+    fn color(&self) -> Color;
+
+    /// The uniformity of transmission.
+    fn roughness(&self) -> f64;
+
+    /// The first crossing of the surface in front of the ray.
+    fn first(&self, ray: &Ray<T>) -> Option<(f64, Vec3<f64>)> {
+        self.hits(ray).into_iter().find(|hit| hit.0 >= 0.01)
+    }
+}
+
+/// Implements the Raytrace trait for a type implementing [Solid].
+macro_rules! solid_raytrace {
+    ($solid:ident) => {
+        impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for $solid<T> {
+            fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+                Some(self.first(ray)?.0.into())
+            }
+
+            fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+                let direction = ray.direction.to_f64();
+                let crossings = self.hits(ray).into_iter().map(|(t, normal)| (t, normal * direction < 0.0));
+
+                Some($crate::raytrace::crossing_intervals(crossings).into_iter().map(|(start, end)| (start.into(), end.into())).collect())
+            }
+
+            fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+                let (distance, normal) = self.first(ray)?;
+
+                Some(Ray::new(ray.at(T::from(distance)), Vec3::from_f64($crate::raytrace::perturbed_reflection(ray.direction.to_f64(), normal, self.roughness()))))
+            }
+
+            fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+                Some(Vec3::from_f64(self.first(ray)?.1))
+            }
+
+            fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+                $crate::raytrace::reflection_pdf(ray.direction.to_f64(), self.first(ray)?.1, self.roughness(), direction.to_f64())
+            }
+
+            fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+                color * self.color()
+            }
+        }
+    };
+}
+
+pub(crate) use solid_raytrace;
+
+/// The intervals of distance inside a closed surface, from its crossings in order,
+/// each given with whether it enters the surface.
+///
+/// Crossings which do not change whether the ray is inside, such as grazing the silhouette, are skipped,
+/// so that the inside and outside are never swapped.
+pub(crate) fn crossing_intervals(crossings: impl IntoIterator<Item = (f64, bool)>) -> Vec<(f64, f64)> {
+    let mut intervals = Vec::new();
+
+    // How many surfaces the ray is within, and where it entered the outermost.
+    let mut depth = 0;
+    let mut start = 0.0;
+
+    for (distance, entering) in crossings {
+        if entering {
+            if depth == 0 {
+                start = distance;
+            }
+            depth += 1;
+        } else if depth > 0 {
+            depth -= 1;
+            if depth == 0 {
+                intervals.push((start, distance));
+            }
+        }
+    }
+
+    intervals
+}
+
+/// The probability density, over solid angle, of a reflection offset by a random vector
+/// from a cube scaled by roughness, as in the transmit of [crate::sphere::Sphere], leaving in a direction.
+///
+/// Returns None if roughness is 0, as only the exact reflection is possible.
+pub fn perturbed_pdf(reflected: Vec3<f64>, roughness: f64, direction: Vec3<f64>) -> Option<f64> {
+    if roughness <= 0.0 {
+        return None;
+    }
+
+    // The distances along the direction which fall within the cube of possible offsets.
+    let mut near: f64 = 0.0;
+    let mut far = f64::INFINITY;
+
+    for (r, d) in [(reflected.x, direction.x), (reflected.y, direction.y), (reflected.z, direction.z)] {
+        if d == 0.0 {
+            if (r.abs()) > roughness {
+                return Some(0.0);
+            }
+            continue;
+        }
+
+        let t1 = (r - roughness) / d;
+        let t2 = (r + roughness) / d;
+
+        near = near.max(t1.min(t2));
+        far = far.min(t1.max(t2));
+    }
+
+    if far <= near {
+        return Some(0.0);
+    }
+
+    // Integrates the uniform density of the cube along the direction.
+    Some((far * far * far - near * near * near) / 3.0 / (8.0 * roughness * roughness * roughness))
+}
+
+/// The probability density, over solid angle, of a ray in an incoming direction reflected about a unit normal,
+/// as by [perturbed_reflection], leaving in a direction.
+///
+/// Returns None if roughness is 0, as only the exact reflection is possible.
+pub fn reflection_pdf(incoming: Vec3<f64>, normal: Vec3<f64>, roughness: f64, direction: Vec3<f64>) -> Option<f64> {
+    perturbed_pdf(incoming - normal * (normal * incoming * 2.0), roughness, direction)
+}
+
+/// A mirror reflection about a unit normal, offset by a random vector from a cube scaled by roughness,
+/// as in the transmit of [crate::sphere::Sphere].
+///
+/// The density of the result is given by [perturbed_pdf].
+pub fn perturbed_reflection(direction: Vec3<f64>, normal: Vec3<f64>, roughness: f64) -> Vec3<f64> {
+    // Uses ThreadRng::Default() so is not re-seeded.
+    let mut rng = rand::thread_rng();
+
+    let reflected = direction - normal * (normal * direction * 2.0);
+    let random = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+
+    reflected + random * roughness
+}
+
+/// Rotates a vector by the rotation which takes one unit vector onto another.
+///
+/// ```
+/// # use rusttracing::raytrace::*;
+/// # use rusttracing::vector::*;
+/// let rotated = rotate_between(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 2.0));
+/// assert_eq!(rotated, Vec3::new(0.0, 0.0, 2.0));
+/// ```
+pub fn rotate_between(from: Vec3<f64>, to: Vec3<f64>, vec: Vec3<f64>) -> Vec3<f64> {
+    let axis = from.cross(&to);
+    let cos = from * to;
+    let sin_squared = axis * axis;
+
+    if sin_squared < 1e-18 {
+        return vec;
+    }
+
+    vec * cos + axis.cross(&vec) + axis * ((axis * vec) * (1.0 - cos) / sin_squared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perturbed() {
+        let mut rng = rand::thread_rng();
+
+        let reflected = Vec3::new(0.0, 0.6, 0.8);
+        let roughness = 0.7;
+
+        // Estimates the density within a small cone by sampling, as transmit does.
+        let direction = Vec3::new(0.1, 0.5, 0.8).unit();
+        let cos_angle: f64 = 0.995;
+        let solid_angle = 2.0 * std::f64::consts::PI * (1.0 - cos_angle);
+
+        let count = 400000;
+        let mut inside = 0;
+        for _i in 0..count {
+            let random = Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+            let out = (reflected + random * roughness).unit();
+
+            if out * direction > cos_angle {
+                inside += 1;
+            }
+        }
+
+        let estimate = inside as f64 / count as f64 / solid_angle;
+        let exact = perturbed_pdf(reflected, roughness, direction).unwrap();
+
+        assert!((estimate - exact).abs() < exact * 0.1);
+        assert_eq!(perturbed_pdf(reflected, 0.0, direction), None);
+    }
+
+    #[test]
+    fn crossings() {
+        // Nested surfaces are merged, and a grazing exit while outside is skipped.
+        let crossings = [(-1.0, false), (1.0, true), (2.0, true), (3.0, false), (4.0, false), (5.0, true), (6.0, false), (7.0, true)];
+
+        assert_eq!(crossing_intervals(crossings), vec![(1.0, 4.0), (5.0, 6.0)]);
+    }
 }
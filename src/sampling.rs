@@ -0,0 +1,133 @@
+/// How many samples are taken for each pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Sampling {
+    /// Every pixel takes exactly the requested number of samples.
+    #[default]
+    Fixed,
+
+    /// Every pixel takes at least the requested number of samples,
+    /// then continues sampling until its estimated error falls under the threshold,
+    /// or it reaches the maximum number of samples.
+    Adaptive {
+        /// The relative standard error under which a pixel is considered converged.
+        threshold: f64,
+
+        /// The most samples a single pixel may take.
+        max: usize,
+    },
+}
+
+impl Sampling {
+    /// The most samples a single pixel may take, given the requested samples.
+    pub fn max(&self, rays: usize) -> usize {
+        match *self {
+            Sampling::Fixed => rays,
+            Sampling::Adaptive { max, .. } => max.max(rays),
+        }
+    }
+
+    /// Whether a pixel should take another sample, given the requested samples.
+    pub fn more(&self, rays: usize, estimate: &Estimate) -> bool {
+        if estimate.count < rays {
+            return true;
+        }
+
+        match *self {
+            Sampling::Fixed => false,
+            Sampling::Adaptive { threshold, max } => estimate.count < max && estimate.error() >= threshold,
+        }
+    }
+}
+
+/// A running estimate of the mean and variance of a series of values.
+///
+/// Uses Welford's algorithm, so values do not need to be stored.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Estimate {
+    /// The number of values added.
+    pub count: usize,
+
+    /// The mean of all values added.
+    pub mean: f64,
+
+    /// The sum of squared differences from the mean.
+    m2: f64,
+}
+
+impl Estimate {
+    /// Creates an empty estimate.
+    pub fn new() -> Self {
+        Estimate::default()
+    }
+
+    /// Adds a value to the estimate.
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// The sample variance of the values added.
+    ///
+    /// ```
+    /// # use rusttracing::sampling::*;
+    /// let mut estimate = Estimate::new();
+    /// for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+    ///     estimate.add(value);
+    /// }
+    /// assert_eq!(estimate.mean, 5.0);
+    /// assert_eq!(estimate.variance(), 32.0 / 7.0);
+    /// ```
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+
+        self.m2 / (self.count - 1) as f64
+    }
+
+    /// The standard error of the mean, relative to the mean.
+    ///
+    /// Infinite until at least 2 values have been added.
+    pub fn error(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+
+        (self.variance() / self.count as f64).sqrt() / (self.mean.abs() + 0.001)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive() {
+        let sampling = Sampling::Adaptive { threshold: 0.01, max: 8 };
+
+        let mut estimate = Estimate::new();
+        while sampling.more(2, &estimate) {
+            estimate.add(1.0);
+        }
+        assert_eq!(estimate.count, 2);
+
+        let mut estimate = Estimate::new();
+        while sampling.more(2, &estimate) {
+            estimate.add((estimate.count % 2) as f64);
+        }
+        assert_eq!(estimate.count, 8);
+    }
+
+    #[test]
+    fn fixed() {
+        let mut estimate = Estimate::new();
+        while Sampling::Fixed.more(5, &estimate) {
+            estimate.add(estimate.count as f64);
+        }
+        assert_eq!(estimate.count, 5);
+        assert_eq!(estimate.mean, 2.0);
+    }
+}
@@ -1,3 +1,5 @@
+use rand::Rng;
+
 use std::thread;
 use std::sync::mpsc;
 
@@ -7,9 +9,24 @@ use crate::ray::*;
 use crate::camera::*;
 use crate::color::*;
 use crate::image::*;
+use crate::filter::*;
+use crate::sampling::*;
+use crate::pass::*;
+use crate::environment::*;
+use crate::medium::*;
 
 use std::ops::*;
 
+/// The most bounces a path may take, regardless of Russian roulette.
+///
+/// Only reached by paths which never lose energy, such as between two perfect mirrors.
+pub const MAX_BOUNCES: usize = 1024;
+
+/// The scale from light gathered along paths to the colour of the image.
+///
+/// Also applied to the environment in the albedo pass, so it matches the beauty pass.
+pub const EXPOSURE: f64 = 0.001;
+
 /// A collection of objects, camera, and background color.
 pub struct Scene<T> {
     /// All of the objects throughout the scene.
@@ -22,8 +39,21 @@ pub struct Scene<T> {
     /// The camera of the scene.
     pub camera: Camera<T>,
 
-    /// The color of the environment
-    pub environment: Color,
+    /// The light surrounding the scene, seen by rays which escape.
+    ///
+    /// A plain [Color] is the same in every direction.
+    pub environment: Box<dyn Environment + Sync>,
+
+    /// The reconstruction filter used to weight samples within each pixel.
+    pub filter: Filter,
+
+    /// How many samples are taken for each pixel.
+    pub sampling: Sampling,
+
+    /// Fog filling the scene, between and around the objects, if any.
+    ///
+    /// Media bounded by a shape are added as objects instead, with [crate::medium::Volume].
+    pub fog: Option<Fog>,
 }
 
 impl<T: Copy + From<f64> + From<i32> + Into<f64> + PartialOrd + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>> Scene<T> {
@@ -33,47 +63,360 @@ impl<T: Copy + From<f64> + From<i32> + Into<f64> + PartialOrd + Add<Output = T>
     /// and what it intersects with.
     ///
     /// Returns a tuple of the intersecting object and distance along ray.
-    pub fn trace(&self, ray: Ray<T>) -> (Option<&Box<dyn Raytrace<T> + Sync>>, T) {
+    pub fn trace(&self, ray: Ray<T>) -> (Option<&(dyn Raytrace<T> + Sync)>, T) {
+        let (index, lowest) = self.trace_index(ray);
+
+        (index.map(|i| self.objects[i].as_ref()), lowest)
+    }
+
+    /// Traces a ray through the scene, as with [Scene::trace].
+    ///
+    /// Returns a tuple of the index of the intersecting object and distance along ray.
+    pub fn trace_index(&self, ray: Ray<T>) -> (Option<usize>, T) {
         let mut lowest: T = 9999999999.0.into();
         let mut closest_obj = None;
 
-        for obj in &self.objects {
-            let distance = obj.intersects_along(&ray);
-            match distance {
-                Some(x) => {
-                    if x < lowest {
-                        lowest = x;
-                        closest_obj = Some(obj);
-                    }
-                },
-                None => ()
+        for (i, obj) in self.objects.iter().enumerate() {
+            if let Some(x) = obj.intersects_along(&ray) {
+                if x < lowest {
+                    lowest = x;
+                    closest_obj = Some(i);
+                }
             }
         }
 
         (closest_obj, lowest)
     }
 
-    /// Runs the trace function recurring.
+    /// Every medium along the line of a ray, with the interval of distance it fills,
+    /// from the fog and from objects with a [Raytrace::medium].
+    ///
+    /// Also gives the index of the object filled by each medium, or None for the fog.
+    fn media(&self, ray: &Ray<T>) -> (Vec<Span>, Vec<Option<usize>>) {
+        let (mut media, mut sources) = (vec![], vec![]);
+
+        if let Some(fog) = self.fog {
+            if let Some((start, end)) = fog.interval(&Ray::new(ray.origin.to_f64(), ray.direction.to_f64())) {
+                media.push((start, end, fog.medium));
+                sources.push(None);
+            }
+        }
+
+        for (i, obj) in self.objects.iter().enumerate() {
+            if let Some(medium) = obj.medium() {
+                for (start, end) in obj.intervals(ray).unwrap_or_default() {
+                    media.push((start.into(), end.into(), medium));
+                    sources.push(Some(i));
+                }
+            }
+        }
+
+        (media, sources)
+    }
+
+    /// The distance along a ray at which it is scattered or absorbed by the media of the scene, if before a limit,
+    /// and the medium it happens within.
+    ///
+    /// Media whose density varies are delta tracked, passing through null collisions where the density is below its highest.
+    fn free_flight(&self, ray: &Ray<T>, limit: f64) -> Option<(f64, Medium)> {
+        let mut rng = rand::thread_rng();
+        let (media, sources) = self.media(ray);
+
+        let mut along = 0.0;
+
+        loop {
+            let ahead: Vec<Span> = media.iter().map(|&(start, end, medium)| (start - along, end - along, medium)).collect();
+            let (distance, index) = sample_distance(&ahead, limit - along, rng.gen(), rng.gen())?;
+
+            along += distance;
+
+            match sources[index].and_then(|i| self.objects[i].density(&ray.at(T::from(along)), ray.time)) {
+                Some(density) if rng.gen::<f64>() >= density => continue,
+                _ => return Some((along, media[index].2)),
+            }
+        }
+    }
+
+    /// The fraction of light which passes through the media of the scene along a ray, up to a distance.
+    ///
+    /// Media whose density varies are estimated by ratio tracking, so the fraction is random, but correct on average.
+    pub fn transmittance(&self, ray: &Ray<T>, distance: f64) -> f64 {
+        let mut rng = rand::thread_rng();
+        let (media, sources) = self.media(ray);
+
+        media.iter().zip(sources).map(|(&(start, end, medium), source)| {
+            let (start, end) = (start.max(0.0), end.min(distance));
+            let extinction = medium.extinction();
+
+            if start >= end || extinction <= 0.0 {
+                return 1.0;
+            }
+
+            let middle = if end.is_finite() { (start + end) * 0.5 } else { start + 1.0 };
+            let varying = source.map(|i| self.objects[i].as_ref()).filter(|obj| obj.density(&ray.at(T::from(middle)), ray.time).is_some());
+
+            let Some(obj) = varying else {
+                return (-(end - start) * extinction).exp();
+            };
+
+            let mut along = start;
+            let mut fraction = 1.0;
+
+            loop {
+                along -= (1.0 - rng.gen::<f64>()).ln() / extinction;
+
+                if along >= end || fraction <= 0.0 {
+                    return fraction;
+                }
+
+                fraction *= 1.0 - obj.density(&ray.at(T::from(along)), ray.time).unwrap_or(1.0);
+            }
+        }).product()
+    }
+
+    /// The light given off by an object toward a ray which hits it, from the front of its surface.
+    fn emitted(obj: &(dyn Raytrace<T> + Sync), ray: &Ray<T>) -> Option<Color> {
+        let emission = obj.emission()?;
+
+        if obj.normal(ray)?.to_f64() * ray.direction.to_f64() < 0.0 {
+            Some(emission)
+        } else {
+            None
+        }
+    }
+
+    /// Chooses one of the lights, given by their index within objects, and a direction from a point toward it,
+    /// as with [Raytrace::sample_light].
+    ///
+    /// Returns the unit direction, the light arriving along it, less that lost to the media on the way,
+    /// and the probability density over solid angle of choosing it.
+    /// Returns None if the light is hidden or faces away.
+    fn sample_lights(&self, lights: &[usize], from: Vec3<T>, time: f64) -> Option<(Vec3<f64>, Color, f64)> {
+        let mut rng = rand::thread_rng();
+
+        if lights.is_empty() {
+            return None;
+        }
+
+        let light = lights[rng.gen_range(0..lights.len())];
+
+        let (direction, pdf) = self.objects[light].sample_light(&from, rng.gen(), rng.gen())?;
+        let shadow = Ray::new(from, Vec3::from_f64(direction)).with_time(time);
+
+        let (hit, distance) = self.trace_index(shadow);
+        if hit != Some(light) || pdf <= 0.0 {
+            return None;
+        }
+
+        let emitted = Self::emitted(self.objects[light].as_ref(), &shadow)?;
+
+        Some((direction, emitted * self.transmittance(&shadow, distance.into()), pdf / lights.len() as f64))
+    }
+
+    /// Traces a path through the scene, bouncing between objects until it escapes to the environment.
+    ///
+    /// After depth bounces, paths are randomly terminated with Russian roulette
+    /// based on their throughput, and the surviving paths are weighted up to remain unbiased.
+    ///
+    /// At each object which gives its scatter_pdf, the environment is also sampled directly,
+    /// and combined with the bounced path through multiple importance sampling.
+    ///
+    /// Objects which give off light, such as [crate::light::AreaLight]s, are sampled directly in the same way,
+    /// and combined with paths which hit them.
+    ///
+    /// Within fog or a volume, the distance to the next scatter is sampled from the media along the ray,
+    /// and paths scattered before reaching an object continue in a direction chosen by the phase function.
+    ///
+    /// Every bounce keeps the time of the ray, so the whole path sees moving objects at one instant.
     pub fn trace_bounce(&self, ray: Ray<T>, depth: usize) -> Color {
-        if depth == 0 {
-            return Color::new(1.0, 1.0, 1.0);
+        // Uses ThreadRng::Default() so is not re-seeded.
+        let mut rng = rand::thread_rng();
+
+        let mut ray = ray;
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut color = Color::new(0.0, 0.0, 0.0);
+
+        // The density of the last bounce choosing the current direction, if known.
+        let mut last_pdf: Option<f64> = None;
+
+        let lights: Vec<usize> = (0..self.objects.len()).filter(|&i| self.objects[i].emission().is_some()).collect();
+
+        for bounce in 0..MAX_BOUNCES {
+            let (index, distance) = self.trace_index(ray);
+            let hit = index.map(|i| self.objects[i].as_ref());
+            let limit = hit.map_or(f64::INFINITY, |_obj| distance.into());
+
+            if let Some((along, medium)) = self.free_flight(&ray, limit) {
+                let pos = ray.at(T::from(along));
+                let direction = ray.direction.to_f64();
+
+                throughput = throughput * medium.albedo();
+
+                // Samples the environment directly, weighted by the light lost to the media on the way.
+                if let Some((out, environment_pdf)) = self.environment.sample(rng.gen(), rng.gen()) {
+                    let phase = medium.phase(direction, out);
+                    let shadow = Ray::new(pos, Vec3::from_f64(out)).with_time(ray.time);
+
+                    if environment_pdf > 0.0 && self.trace(shadow).0.is_none() {
+                        let weight = phase / (phase + environment_pdf);
+
+                        color = color + throughput * self.environment.color(out) * (weight * self.transmittance(&shadow, f64::INFINITY));
+                    }
+                }
+
+                // Samples the lights directly, in the same way.
+                if let Some((out, light, light_pdf)) = self.sample_lights(&lights, pos, ray.time) {
+                    let phase = medium.phase(direction, out);
+
+                    color = color + throughput * light * (phase / (phase + light_pdf));
+                }
+
+                let next = medium.sample_phase(direction, rng.gen(), rng.gen());
+
+                last_pdf = Some(medium.phase(direction, next));
+                ray = Ray::new(pos, Vec3::from_f64(next)).with_time(ray.time);
+            } else {
+                let obj = match hit {
+                    Some(obj) => obj,
+                    None => {
+                        let direction = ray.direction.to_f64();
+
+                        let weight = match last_pdf {
+                            Some(pdf) => pdf / (pdf + self.environment.pdf(direction)),
+                            None => 1.0,
+                        };
+
+                        return color + throughput * self.environment.color(direction) * weight;
+                    }
+                };
+
+                // Light given off by the object, weighted against sampling it directly.
+                if let Some(emitted) = Self::emitted(obj, &ray) {
+                    let weight = match last_pdf {
+                        Some(pdf) => pdf / (pdf + obj.light_pdf(&ray).map_or(0.0, |x| x / lights.len() as f64)),
+                        None => 1.0,
+                    };
+
+                    color = color + throughput * emitted * weight;
+                }
+
+                // Samples the environment directly, if it is visible from the intersection.
+                if let Some((direction, environment_pdf)) = self.environment.sample(rng.gen(), rng.gen()) {
+                    if let (Some(pdf), Some(pos)) = (obj.scatter_pdf(&ray, &Vec3::from_f64(direction)), obj.intersects_at(&ray)) {
+                        let shadow = Ray::new(pos, Vec3::from_f64(direction)).with_time(ray.time);
+
+                        if pdf > 0.0 && environment_pdf > 0.0 && self.trace(shadow).0.is_none() {
+                            let weight = pdf / (pdf + environment_pdf) * self.transmittance(&shadow, f64::INFINITY);
+
+                            color = color + obj.recolor(&ray, throughput * self.environment.color(direction)) * weight;
+                        }
+                    }
+                }
+
+                // Samples the lights directly, if they are visible from the intersection.
+                if let Some(pos) = obj.intersects_at(&ray) {
+                    if let Some((direction, light, light_pdf)) = self.sample_lights(&lights, pos, ray.time) {
+                        if let Some(pdf) = obj.scatter_pdf(&ray, &Vec3::from_f64(direction)).filter(|&pdf| pdf > 0.0) {
+                            color = color + obj.recolor(&ray, throughput * light) * (pdf / (pdf + light_pdf));
+                        }
+                    }
+                }
+
+                let next = match obj.transmit(&ray) {
+                    Some(x) => x,
+                    None => break
+                };
+
+                last_pdf = obj.scatter_pdf(&ray, &next.direction);
+                throughput = obj.recolor(&ray, throughput);
+                ray = next.with_time(ray.time);
+            }
+
+            if bounce >= depth {
+                let survival = throughput.r.max(throughput.g).max(throughput.b).min(0.95);
+
+                if rng.gen::<f64>() >= survival {
+                    break;
+                }
+
+                throughput = throughput / survival;
+            }
         }
 
-        match self.trace(ray).0 {
-            Some(obj) => {
-                (*obj).recolor(&ray, self.trace_bounce(obj.transmit(&ray).unwrap(), depth - 1))
-            },
-            None => self.environment
+        color
+    }
+
+    /// The ray leaving the camera through a point on the image,
+    /// where (x, y) is measured in pixels from the top left corner, cast at a time.
+    fn camera_ray<const WIDTH: usize, const HEIGHT: usize>(&self, x: f64, y: f64, fov_distance: f64, time: f64) -> Ray<T> {
+        let aspect_ratio = WIDTH as f64 / HEIGHT as f64;
+
+        let abs_x = (x / WIDTH as f64) * 2.0 - 1.0;
+        let abs_y = 1.0 - (y / HEIGHT as f64) * 2.0;
+
+        Ray::new(
+            self.camera.position,
+            self.camera.transform(Vec3::new((abs_x * aspect_ratio * fov_distance).into(), (abs_y * fov_distance).into(), (1.0).into()).unit())
+        ).with_time(time)
+    }
+
+    /// The value of a pass for a ray through the centre of a pixel.
+    ///
+    /// Samples is not known from a single ray, so is left black.
+    fn pass_value(&self, pass: Pass, ray: Ray<T>) -> Color {
+        let (index, distance) = self.trace_index(ray);
+
+        let raw = |x: Vec3<T>| Color { r: x.x.into(), g: x.y.into(), b: x.z.into() };
+
+        match (index, pass) {
+            (_, Pass::Samples) => Color::new(0.0, 0.0, 0.0),
+
+            (Some(_), Pass::Depth) => Color { r: distance.into(), g: distance.into(), b: distance.into() },
+            (None, Pass::Depth) => Color { r: f64::INFINITY, g: f64::INFINITY, b: f64::INFINITY },
+
+            (Some(i), Pass::Normal) => self.objects[i].normal(&ray).map_or(Color::new(0.0, 0.0, 0.0), raw),
+            (None, Pass::Normal) => Color::new(0.0, 0.0, 0.0),
+
+            (Some(i), Pass::Albedo) => self.objects[i].albedo(&ray),
+            (None, Pass::Albedo) => self.environment.color(ray.direction.to_f64()) * EXPOSURE,
+
+            (Some(i), Pass::Object) => Color { r: i as f64, g: i as f64, b: i as f64 },
+            (None, Pass::Object) => Color { r: -1.0, g: -1.0, b: -1.0 },
+
+            (Some(_), Pass::Position) => raw(ray.at(distance)),
+            (None, Pass::Position) => Color::new(0.0, 0.0, 0.0),
         }
     }
 
     /// Runs the trace_bounce function multiple times for each pixel.
+    ///
+    /// Each sample is placed across the support of the scene's filter in proportion to the magnitude of its weight,
+    /// and averaged with the sign and normalised weight of the filter, so filters with negative lobes stay bounded.
+    ///
+    /// Depth is not a limit on the number of bounces, but the number of bounces after which paths
+    /// are randomly terminated with Russian roulette, as in [Scene::trace_bounce].
+    /// Paths which never lose energy, such as between two perfect mirrors, are still stopped after [MAX_BOUNCES].
     pub fn raytrace<const WIDTH: usize, const HEIGHT: usize>(&self, rays: usize, depth: usize, fov: f64, transmit: Option<mpsc::Sender<(usize, Vec<Color>)>>) -> Image<WIDTH, HEIGHT>
     where T: Sync {
-        let mut img: Image<WIDTH, HEIGHT> = Image::new();
+        self.render(rays, depth, fov, &[], transmit).image
+    }
+
+    /// Raytraces the scene, as with [Scene::raytrace], along with the requested passes.
+    ///
+    /// Rays is the number of samples taken for each pixel,
+    /// or the minimum number of samples when the scene uses adaptive sampling.
+    ///
+    /// Depth is the number of bounces after which Russian roulette starts, as with [Scene::raytrace].
+    pub fn render<const WIDTH: usize, const HEIGHT: usize>(&self, rays: usize, depth: usize, fov: f64, passes: &[Pass], transmit: Option<mpsc::Sender<(usize, Vec<Color>)>>) -> Render<WIDTH, HEIGHT>
+    where T: Sync {
+        let mut render = Render::<WIDTH, HEIGHT> {
+            image: Image::new(),
+            passes: passes.iter().map(|&pass| (pass, Image::new())).collect(),
+        };
 
-        let aspect_ratio = img.data.len() as f64 / img.data[0].len() as f64;
         let fov_distance = (fov / 2.0).to_radians().tan();
+        let sampler = &self.filter.sampler();
+        let max = self.sampling.max(rays);
 
         let (tx, rx) = mpsc::channel();
         thread::scope(|s| {
@@ -82,50 +425,57 @@ impl<T: Copy + From<f64> + From<i32> + Into<f64> + PartialOrd + Add<Output = T>
                 let transmit_thread = transmit.clone();
 
                 s.spawn(move || {
-                    let mut batch = vec![Color::new(0.0, 0.0, 0.0); HEIGHT];
+                    // Uses ThreadRng::Default() so is not re-seeded.
+                    let mut rng = rand::thread_rng();
 
-                    for y in 0..HEIGHT {
-                        let abs_x = -(1.0 - (x as f64 / WIDTH as f64) * 2.0);
-                        let abs_y = 1.0 - (y as f64 / HEIGHT as f64) * 2.0;
+                    let mut batch = vec![Color::new(0.0, 0.0, 0.0); HEIGHT];
+                    let mut pass_batches = vec![vec![Color::new(0.0, 0.0, 0.0); HEIGHT]; passes.len()];
 
+                    for (y, pixel) in batch.iter_mut().enumerate() {
                         let mut color = Color::new(0.0, 0.0, 0.0);
-                        let mut bounces = 0.0 as f64;
-
-                        let camera_ray = Ray::new(
-                            self.camera.position,
-                            self.camera.transform(Vec3::new((abs_x * aspect_ratio * fov_distance).into(), (abs_y * fov_distance).into(), (1.0).into()).unit())
-                        );
-
-                        let cache_obj = self.trace(camera_ray).0;
-
-                        for _i in 0..rays {
-                            color = color + match cache_obj {
-                                Some(obj) => {
-                                    (*obj).recolor(&camera_ray, self.trace_bounce(obj.transmit(&camera_ray).unwrap(), depth - 1))
-                                },
-                                None => self.environment
-                            } * 0.001;
-                            bounces += 1.0;
+                        let mut estimate = Estimate::new();
+
+                        while self.sampling.more(rays, &estimate) {
+                            let (offset_x, offset_y, weight) = sampler.sample(rng.gen(), rng.gen());
+
+                            let camera_ray = self.camera_ray::<WIDTH, HEIGHT>(x as f64 + 0.5 + offset_x, y as f64 + 0.5 + offset_y, fov_distance, self.camera.time(rng.gen()));
+
+                            let sample = self.trace_bounce(camera_ray, depth) * EXPOSURE;
+
+                            color = color + sample * weight;
+                            estimate.add(sample.luminance());
+                        }
+
+                        if estimate.count != 0 {
+                            *pixel = color / estimate.count as f64;
                         }
 
-                        color = color / bounces;
+                        let centre_ray = self.camera_ray::<WIDTH, HEIGHT>(x as f64 + 0.5, y as f64 + 0.5, fov_distance, self.camera.time(0.5));
 
-                        batch[y] = color;
+                        for (i, &pass) in passes.iter().enumerate() {
+                            pass_batches[i][y] = match pass {
+                                Pass::Samples => Color::heat(estimate.count as f64 / max as f64),
+                                _ => self.pass_value(pass, centre_ray),
+                            };
+                        }
                     }
 
-                    match transmit_thread {
-                        Some(tx) => tx.send((x, batch.clone())).unwrap(),
-                        None => ()
+                    if let Some(tx) = transmit_thread {
+                        tx.send((x, batch.clone())).unwrap();
                     }
 
-                    tx_thread.send((x, batch)).unwrap();
+                    tx_thread.send((x, batch, pass_batches)).unwrap();
                 });
             }
 
             let mut counter = 0;
 
             for x in rx {
-                img[x.0] = x.1;
+                render.image[x.0] = x.1;
+
+                for (i, column) in x.2.into_iter().enumerate() {
+                    render.passes[i].1[x.0] = column;
+                }
 
                 counter += 1;
 
@@ -135,6 +485,38 @@ impl<T: Copy + From<f64> + From<i32> + Into<f64> + PartialOrd + Add<Output = T>
             }
         });
 
-        img
+        render
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mitchell() {
+        // An empty scene, so every sample sees the same environment.
+        let scene = Scene::<f64> {
+            objects: vec![],
+            camera: Camera::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0)),
+            environment: Box::new(Color::new_emission(1.0, 1.0, 1.0, 500.0)),
+            filter: Filter::Mitchell { radius: 2.0, b: 1.0 / 3.0, c: 1.0 / 3.0 },
+            sampling: Sampling::Fixed,
+            fog: None,
+        };
+
+        let image = scene.raytrace::<32, 32>(2, 4, 90.0, None);
+
+        // Negative lobes may flip single samples, but never blow up the sum.
+        let mut total = 0.0;
+        for x in 0..32 {
+            for pixel in &image[x] {
+                assert!(pixel.r.is_finite() && pixel.r.abs() < 0.75);
+
+                total += pixel.r;
+            }
+        }
+
+        assert!((total / (32 * 32) as f64 - 0.5).abs() < 0.05);
     }
 }
@@ -0,0 +1,453 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+
+use std::ops::*;
+
+/// A signed distance field, describing a shape by the distance to its surface.
+///
+/// Distances are negative inside the shape.
+/// Estimates are allowed to be too small, which only slows tracing, but never too large.
+pub trait Sdf {
+    /// The distance from a point to the nearest surface.
+    fn distance(&self, point: Vec3<f64>) -> f64;
+}
+
+/// A sphere, as a signed distance field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfSphere {
+    /// The center.
+    pub center: Vec3<f64>,
+
+    /// Radius.
+    pub radius: f64,
+}
+
+impl SdfSphere {
+    /// Default constructor.
+    pub fn new(center: Vec3<f64>, radius: f64) -> Self {
+        SdfSphere { center, radius }
+    }
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        (point - self.center).length() - self.radius
+    }
+}
+
+/// An axis-aligned box with rounded edges, as a signed distance field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfBox {
+    /// The center.
+    pub center: Vec3<f64>,
+
+    /// The length along each axis.
+    pub size: Vec3<f64>,
+
+    /// Radius of the rounding of the edges and corners, which is 0 for sharp edges.
+    pub rounding: f64,
+}
+
+impl SdfBox {
+    /// Default constructor, with sharp edges.
+    pub fn new(center: Vec3<f64>, size: Vec3<f64>) -> Self {
+        SdfBox { center, size, rounding: 0.0 }
+    }
+
+    /// Rounds the edges and corners, keeping the overall size.
+    pub fn with_rounding(mut self, rounding: f64) -> Self {
+        self.rounding = rounding;
+
+        self
+    }
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        let relative = point - self.center;
+
+        // The distance outside of each pair of faces, of the box shrunk by the rounding.
+        let q = Vec3::new(
+            relative.x.abs() - self.size.x / 2.0 + self.rounding,
+            relative.y.abs() - self.size.y / 2.0 + self.rounding,
+            relative.z.abs() - self.size.z / 2.0 + self.rounding,
+        );
+
+        let outside = Vec3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+        let inside = q.x.max(q.y).max(q.z).min(0.0);
+
+        outside + inside - self.rounding
+    }
+}
+
+/// A torus around the y axis, as a signed distance field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SdfTorus {
+    /// The center of the ring.
+    pub center: Vec3<f64>,
+
+    /// Radius of the circle the tube is swept around.
+    pub major_radius: f64,
+
+    /// Radius of the tube.
+    pub minor_radius: f64,
+}
+
+impl SdfTorus {
+    /// Default constructor.
+    pub fn new(center: Vec3<f64>, major_radius: f64, minor_radius: f64) -> Self {
+        SdfTorus { center, major_radius, minor_radius }
+    }
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        let relative = point - self.center;
+        let across = (relative.x * relative.x + relative.z * relative.z).sqrt() - self.major_radius;
+
+        (across * across + relative.y * relative.y).sqrt() - self.minor_radius
+    }
+}
+
+/// The Mandelbulb fractal, as a distance estimate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mandelbulb {
+    /// The center.
+    pub center: Vec3<f64>,
+
+    /// The size, where 1 fits roughly within a unit sphere.
+    pub scale: f64,
+
+    /// The power the point is raised to each iteration, which is 8 for the usual bulb.
+    pub power: f64,
+
+    /// How many iterations are taken, with more giving finer detail.
+    pub iterations: usize,
+}
+
+impl Mandelbulb {
+    /// Default constructor, of power 8.
+    pub fn new(center: Vec3<f64>, scale: f64) -> Self {
+        Mandelbulb { center, scale, power: 8.0, iterations: 12 }
+    }
+}
+
+impl Sdf for Mandelbulb {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        let start = (point - self.center) / self.scale;
+
+        let mut z = start;
+        let mut derivative = 1.0;
+        let mut radius = z.length();
+
+        for _i in 0..self.iterations {
+            if radius > 2.0 || radius == 0.0 {
+                break;
+            }
+
+            // Raises z to the power, in spherical coordinates.
+            let theta = (z.z / radius).acos() * self.power;
+            let phi = z.y.atan2(z.x) * self.power;
+
+            derivative = radius.powf(self.power - 1.0) * self.power * derivative + 1.0;
+
+            z = Vec3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) * radius.powf(self.power) + start;
+            radius = z.length();
+        }
+
+        if radius == 0.0 {
+            return 0.0;
+        }
+
+        0.5 * radius.ln() * radius / derivative * self.scale
+    }
+}
+
+/// Joins two fields, blending between them where they meet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmoothUnion<A, B> {
+    /// The first field.
+    pub a: A,
+
+    /// The second field.
+    pub b: B,
+
+    /// The distance over which the fields blend, which is 0 for a sharp join.
+    pub smoothness: f64,
+}
+
+impl<A, B> SmoothUnion<A, B> {
+    /// Default constructor.
+    pub fn new(a: A, b: B, smoothness: f64) -> Self {
+        SmoothUnion { a, b, smoothness }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        let (a, b) = (self.a.distance(point), self.b.distance(point));
+
+        if self.smoothness <= 0.0 {
+            return a.min(b);
+        }
+
+        let h = (0.5 + 0.5 * (b - a) / self.smoothness).clamp(0.0, 1.0);
+
+        b + (a - b) * h - self.smoothness * h * (1.0 - h)
+    }
+}
+
+/// Cuts the second field away from the first, rounding the cut edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmoothSubtract<A, B> {
+    /// The field which is cut.
+    pub a: A,
+
+    /// The field which is cut away.
+    pub b: B,
+
+    /// The distance over which the fields blend, which is 0 for a sharp cut.
+    pub smoothness: f64,
+}
+
+impl<A, B> SmoothSubtract<A, B> {
+    /// Default constructor.
+    pub fn new(a: A, b: B, smoothness: f64) -> Self {
+        SmoothSubtract { a, b, smoothness }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothSubtract<A, B> {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        let (a, b) = (self.a.distance(point), -self.b.distance(point));
+
+        if self.smoothness <= 0.0 {
+            return a.max(b);
+        }
+
+        let h = (0.5 - 0.5 * (a - b) / self.smoothness).clamp(0.0, 1.0);
+
+        a + (b - a) * h + self.smoothness * h * (1.0 - h)
+    }
+}
+
+/// Repeats a field endlessly, in a grid around the origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Repeat<S> {
+    /// The field, which should fit within one cell of the grid around the origin.
+    pub shape: S,
+
+    /// The distance between repeats along each axis, where 0 does not repeat along that axis.
+    pub period: Vec3<f64>,
+}
+
+impl<S> Repeat<S> {
+    /// Default constructor.
+    pub fn new(shape: S, period: Vec3<f64>) -> Self {
+        Repeat { shape, period }
+    }
+}
+
+impl<S: Sdf> Sdf for Repeat<S> {
+    fn distance(&self, point: Vec3<f64>) -> f64 {
+        let wrap = |x: f64, period: f64| if period == 0.0 { x } else { x - period * (x / period).round() };
+
+        self.shape.distance(Vec3::new(wrap(point.x, self.period.x), wrap(point.y, self.period.y), wrap(point.z, self.period.z)))
+    }
+}
+
+/// Renders a signed distance field, by sphere tracing.
+///
+/// Each step along the ray is as far as the field says is clear,
+/// until the ray is within a small distance of the surface.
+///
+/// ```
+/// # use rusttracing::sdf::*;
+/// # use rusttracing::vector::*;
+/// # use rusttracing::color::*;
+/// # use rusttracing::ray::*;
+/// # use rusttracing::raytrace::*;
+/// let blob = Traced::new(
+///     SmoothUnion::new(SdfSphere::new(Vec3::new(-0.5, 0.0, 5.0), 1.0), SdfSphere::new(Vec3::new(0.5, 0.0, 5.0), 1.0), 0.5),
+///     Color::new(1.0, 1.0, 1.0),
+/// );
+///
+/// let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+/// assert!(blob.intersects_along(&ray).unwrap() < 4.2);
+/// ```
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Traced<S> {
+    /// The field.
+    pub shape: S,
+
+    /// Colour of the surface.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+
+    /// How close a ray must come to the surface to hit it.
+    pub epsilon: f64,
+
+    /// The most steps taken along a ray before it is counted as a miss.
+    pub max_steps: usize,
+
+    /// The furthest distance along a ray before it is counted as a miss.
+    pub max_distance: f64,
+}
+
+impl<S> Traced<S> {
+    /// Default constructor.
+    pub fn new(shape: S, color: Color) -> Self {
+        Traced { shape, color, roughness: 1.0, epsilon: 1e-4, max_steps: 512, max_distance: 1000.0 }
+    }
+}
+
+impl<S: Sdf> Traced<S> {
+    /// Marches along the ray until it meets the surface.
+    fn march(&self, origin: Vec3<f64>, direction: Vec3<f64>) -> Option<f64> {
+        let mut distance = 0.01;
+
+        // Rays leaving the surface first step clear of it, so as not to hit where they started.
+        let mut leaving = self.shape.distance(origin + direction * distance).abs() < self.epsilon;
+
+        for _i in 0..self.max_steps {
+            let step = self.shape.distance(origin + direction * distance);
+
+            if leaving {
+                leaving = step.abs() < self.epsilon;
+            } else if step.abs() < self.epsilon {
+                return Some(distance);
+            }
+
+            distance += step.abs().max(self.epsilon);
+
+            if distance > self.max_distance {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// The unit gradient of the field, by central differences.
+    fn gradient(&self, point: Vec3<f64>) -> Vec3<f64> {
+        let h = self.epsilon;
+        let difference = |offset: Vec3<f64>| self.shape.distance(point + offset) - self.shape.distance(point - offset);
+
+        Vec3::new(difference(Vec3::new(h, 0.0, 0.0)), difference(Vec3::new(0.0, h, 0.0)), difference(Vec3::new(0.0, 0.0, h))).unit()
+    }
+
+    /// The distance along the ray to the surface, and the normal there.
+    fn hit<T: Copy + Into<f64>>(&self, ray: &Ray<T>) -> Option<(f64, Vec3<f64>)> {
+        let direction = ray.direction.to_f64();
+        let length = direction.length();
+
+        let origin = ray.origin.to_f64();
+        let distance = self.march(origin, direction / length)?;
+
+        Some((distance / length, self.gradient(origin + direction * (distance / length))))
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Sdf> Raytrace<T> for Traced<S> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        Some(self.hit(ray)?.0.into())
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        let (distance, normal) = self.hit(ray)?;
+
+        Some(Ray::new(ray.at(T::from(distance)), Vec3::from_f64(perturbed_reflection(ray.direction.to_f64(), normal, self.roughness))))
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(Vec3::from_f64(self.hit(ray)?.1))
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let normal = self.hit(ray)?.1;
+        reflection_pdf(ray.direction.to_f64(), normal, self.roughness, direction.to_f64())
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color * self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let rounded = SdfBox::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 2.0, 2.0)).with_rounding(0.5);
+
+        assert!((rounded.distance(Vec3::new(3.0, 0.0, 0.0)) - 2.0).abs() < 1e-9);
+        assert!((rounded.distance(Vec3::new(0.0, 0.0, 0.0)) + 1.0).abs() < 1e-9);
+
+        // The corner is rounded off, so further than that of a sharp box.
+        let corner = Vec3::new(2.0, 2.0, 2.0);
+        assert!((rounded.distance(corner) - (1.5 * 3.0_f64.sqrt() - 0.5)).abs() < 1e-9);
+
+        let torus = SdfTorus::new(Vec3::new(0.0, 0.0, 0.0), 2.0, 0.5);
+        assert!((torus.distance(Vec3::new(0.0, 0.0, 0.0)) - 1.5).abs() < 1e-9);
+        assert!((torus.distance(Vec3::new(0.0, 1.0, 2.0)) - 0.5).abs() < 1e-9);
+
+        // Repeats of a sphere every 4 along x.
+        let row = Repeat::new(SdfSphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0), Vec3::new(4.0, 0.0, 0.0));
+        assert!((row.distance(Vec3::new(8.0, 2.0, 0.0)) - 1.0).abs() < 1e-9);
+        assert!((row.distance(Vec3::new(2.0, 0.0, 0.0)) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn smooth() {
+        let a = SdfSphere::new(Vec3::new(-1.0, 0.0, 0.0), 1.0);
+        let b = SdfSphere::new(Vec3::new(1.0, 0.0, 0.0), 1.0);
+
+        // Away from the join, the union is unchanged, and at the join it swells out.
+        let union = SmoothUnion::new(a, b, 0.5);
+        assert!((union.distance(Vec3::new(-3.0, 0.0, 0.0)) - 1.0).abs() < 1e-9);
+        assert!(union.distance(Vec3::new(0.0, 0.1, 0.0)) < a.distance(Vec3::new(0.0, 0.1, 0.0)));
+
+        let sharp = SmoothSubtract::new(a, b, 0.0);
+        assert!((sharp.distance(Vec3::new(-1.0, 0.0, 0.0)) + 1.0).abs() < 1e-9);
+        assert!((sharp.distance(Vec3::new(0.5, 0.0, 0.0)) - 0.5).abs() < 1e-9);
+
+        // Smoothing only ever cuts away more.
+        let soft = SmoothSubtract::new(a, b, 0.5);
+        for x in [-1.5, -0.5, -0.1, 0.3] {
+            let point = Vec3::new(x, 0.2, 0.0);
+            assert!(soft.distance(point) >= sharp.distance(point));
+            assert!((soft.distance(point) - sharp.distance(point)) <= 0.125 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn traced() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let sphere = Traced::new(SdfSphere::new(Vec3::new(1.0, 0.0, 5.0), 2.0), white);
+        let exact = crate::sphere::Sphere::new(Vec3::new(1.0, 0.0, 5.0), 2.0_f64, white);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.1, 0.0, 1.0));
+        assert!((sphere.intersects_along(&ray).unwrap() - exact.intersects_along(&ray).unwrap()).abs() < 1e-3);
+        assert!((sphere.normal(&ray).unwrap() - exact.normal(&ray).unwrap()).length() < 1e-3);
+
+        // Leaving the surface does not hit it again.
+        let out = Traced { roughness: 0.0, ..sphere }.transmit(&ray).unwrap();
+        assert_eq!(sphere.intersects_along(&out), None);
+
+        let ray = Ray::new(Vec3::new(0.0, 3.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(sphere.intersects_along(&ray), None);
+
+        // The bulb fits within a sphere of radius 1.2 of its scale.
+        let bulb = Traced::new(Mandelbulb::new(Vec3::new(0.0, 0.0, 5.0), 1.0), white);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.05, 0.03, 1.0));
+        let distance: f64 = bulb.intersects_along(&ray).unwrap();
+        assert!(distance > 3.8 && distance < 4.5);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::vector::Vec3;
+use crate::color::Color;
+use crate::environment::Environment;
+
+use std::f64::consts::PI;
+
+/// The angular radius of the sun, in radians.
+const SUN_RADIUS: f64 = 0.00465;
+
+/// The luminance of the sun outside of the atmosphere, in the same units as the sky (kcd/m²).
+const SUN_LUMINANCE: f64 = 1.6e6;
+
+/// The chance of sampling the sun, rather than the sky, while it is above the horizon.
+const SUN_PROBABILITY: f64 = 0.5;
+
+/// The Perez distribution coefficients, for luminance and both chromaticities.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    /// The relative brightness of a direction at theta from the zenith, and gamma from the sun.
+    fn at(&self, cos_theta: f64, gamma: f64) -> f64 {
+        (1.0 + self.a * (self.b / cos_theta).exp()) * (1.0 + self.c * (self.d * gamma).exp() + self.e * gamma.cos() * gamma.cos())
+    }
+}
+
+/// An analytic daylight sky, from the Preetham model, along with the sun.
+///
+/// The sun is sampled directly by the renderer, so acts as a directional light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sky {
+    /// The unit direction towards the sun.
+    pub sun: Vec3<f64>,
+
+    /// The haziness of the atmosphere, from 2 for a very clear sky to 10 for a hazy one.
+    pub turbidity: f64,
+
+    /// A multiplier applied to the sky and sun, which are otherwise in kcd/m².
+    pub strength: f64,
+
+    /// The distributions for luminance and both chromaticities.
+    perez: [Perez; 3],
+
+    /// Luminance and chromaticities at the zenith, divided by the distributions at the zenith.
+    zenith: [f64; 3],
+
+    /// The color of the sun after passing through the atmosphere.
+    sun_color: Color,
+}
+
+impl Sky {
+    /// Creates a sky from the direction towards the sun, and turbidity.
+    pub fn new(sun: Vec3<f64>, turbidity: f64, strength: f64) -> Self {
+        let sun = sun.unit();
+        let t = turbidity;
+
+        // Angle of the sun from the zenith, which is kept just above the horizon.
+        let theta_s = sun.y.clamp(0.0, 1.0).acos().min(PI / 2.0 - 0.01);
+
+        let perez = [
+            Perez { a: 0.1787 * t - 1.4630, b: -0.3554 * t + 0.4275, c: -0.0227 * t + 5.3251, d: 0.1206 * t - 2.5771, e: -0.0670 * t + 0.3703 },
+            Perez { a: -0.0193 * t - 0.2592, b: -0.0665 * t + 0.0008, c: -0.0004 * t + 0.2125, d: -0.0641 * t - 0.8989, e: -0.0033 * t + 0.0452 },
+            Perez { a: -0.0167 * t - 0.2608, b: -0.0950 * t + 0.0092, c: -0.0079 * t + 0.2102, d: -0.0441 * t - 1.6537, e: -0.0109 * t + 0.0529 },
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+
+        let (t1, t2, t3) = (theta_s, theta_s * theta_s, theta_s * theta_s * theta_s);
+
+        let x = t * t * (0.00166 * t3 - 0.00375 * t2 + 0.00209 * t1)
+            + t * (-0.02903 * t3 + 0.06377 * t2 - 0.03202 * t1 + 0.00394)
+            + (0.11693 * t3 - 0.21196 * t2 + 0.06052 * t1 + 0.25886);
+        let y = t * t * (0.00275 * t3 - 0.00610 * t2 + 0.00317 * t1)
+            + t * (-0.04214 * t3 + 0.08970 * t2 - 0.04153 * t1 + 0.00516)
+            + (0.15346 * t3 - 0.26756 * t2 + 0.06670 * t1 + 0.26688);
+
+        let zenith = [
+            luminance / perez[0].at(1.0, theta_s),
+            x / perez[1].at(1.0, theta_s),
+            y / perez[2].at(1.0, theta_s),
+        ];
+
+        Sky { sun, turbidity, strength, perez, zenith, sun_color: Self::transmittance(theta_s, turbidity) }
+    }
+
+    /// The fraction of sunlight reaching the ground for red, green and blue,
+    /// from Rayleigh and aerosol scattering.
+    fn transmittance(theta_s: f64, turbidity: f64) -> Color {
+        // Relative distance travelled through the atmosphere.
+        let air_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+        let beta = 0.04608 * turbidity - 0.04586;
+
+        // Wavelengths of red, green and blue in micrometres.
+        let channel = |lambda: f64| {
+            let rayleigh = (-0.008735 * lambda.powf(-4.08) * air_mass).exp();
+            let aerosol = (-beta * lambda.powf(-1.3) * air_mass).exp();
+
+            rayleigh * aerosol
+        };
+
+        Color { r: channel(0.680), g: channel(0.550), b: channel(0.440) }
+    }
+
+    /// The color of the sky alone, without the sun.
+    fn sky_color(&self, direction: Vec3<f64>) -> Color {
+        // Below the horizon is treated as the horizon.
+        let cos_theta = direction.y.max(0.01);
+        let gamma = (direction * self.sun).clamp(-1.0, 1.0).acos();
+
+        let luminance = self.zenith[0] * self.perez[0].at(cos_theta, gamma);
+        let x = self.zenith[1] * self.perez[1].at(cos_theta, gamma);
+        let y = self.zenith[2] * self.perez[2].at(cos_theta, gamma);
+
+        xyy_to_rgb(x, y, luminance)
+    }
+}
+
+/// Converts from CIE xyY to linear sRGB.
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+
+    Color {
+        r: (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        g: (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        b: (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    }
+}
+
+impl Environment for Sky {
+    fn color(&self, direction: Vec3<f64>) -> Color {
+        let mut color = self.sky_color(direction);
+
+        if self.sun.y > 0.0 && direction * self.sun > SUN_RADIUS.cos() {
+            color = color + self.sun_color * SUN_LUMINANCE;
+        }
+
+        color * self.strength
+    }
+
+    /// Chooses between the sun and the upper hemisphere.
+    fn sample(&self, u: f64, v: f64) -> Option<(Vec3<f64>, f64)> {
+        let direction = if self.sun.y > 0.0 && u < SUN_PROBABILITY {
+            // Uniformly within the cone of the sun.
+            let u = u / SUN_PROBABILITY;
+
+            let cos_theta = 1.0 - u * (1.0 - SUN_RADIUS.cos());
+            let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+            let phi = 2.0 * PI * v;
+
+            let tangent = if self.sun.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+            let tangent = tangent.cross(&self.sun).unit();
+            let bitangent = self.sun.cross(&tangent);
+
+            tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + self.sun * cos_theta
+        } else {
+            // Uniformly over the upper hemisphere.
+            let u = if self.sun.y > 0.0 { (u - SUN_PROBABILITY) / (1.0 - SUN_PROBABILITY) } else { u };
+
+            let r = (1.0 - u * u).max(0.0).sqrt();
+            let phi = 2.0 * PI * v;
+
+            Vec3::new(r * phi.sin(), u, r * phi.cos())
+        };
+
+        Some((direction, self.pdf(direction)))
+    }
+
+    fn pdf(&self, direction: Vec3<f64>) -> f64 {
+        let hemisphere = if direction.y >= 0.0 { 1.0 / (2.0 * PI) } else { 0.0 };
+
+        if self.sun.y <= 0.0 {
+            return hemisphere;
+        }
+
+        let sun = if direction * self.sun > SUN_RADIUS.cos() { 1.0 / (2.0 * PI * (1.0 - SUN_RADIUS.cos())) } else { 0.0 };
+
+        SUN_PROBABILITY * sun + (1.0 - SUN_PROBABILITY) * hemisphere
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors() {
+        let sky = Sky::new(Vec3::new(0.0, 1.0, 1.0), 2.5, 1.0);
+
+        // Clear skies are blue overhead.
+        let zenith = sky.color(Vec3::new(0.0, 1.0, 0.0));
+        assert!(zenith.b > zenith.r);
+
+        // The sun is far brighter than the sky.
+        assert!(sky.color(sky.sun).luminance() > zenith.luminance() * 1000.0);
+
+        // Sunsets are redder than midday.
+        let sunset = Sky::new(Vec3::new(0.0, 0.05, 1.0), 2.5, 1.0);
+        assert!(sunset.sun_color.b / sunset.sun_color.r < sky.sun_color.b / sky.sun_color.r);
+    }
+
+    #[test]
+    fn sample() {
+        let sky = Sky::new(Vec3::new(1.0, 1.0, 0.0), 3.0, 1.0);
+
+        let (sun, pdf) = sky.sample(0.25, 0.5).unwrap();
+        assert!(sun * sky.sun > SUN_RADIUS.cos());
+        assert_eq!(pdf, sky.pdf(sun));
+
+        let (other, pdf) = sky.sample(0.75, 0.5).unwrap();
+        assert!(other.y >= 0.0);
+        assert_eq!(pdf, (1.0 - SUN_PROBABILITY) / (2.0 * PI));
+    }
+}
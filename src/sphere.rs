@@ -2,7 +2,7 @@ use rand::Rng;
 
 use crate::vector::Vec3;
 use crate::ray::Ray;
-use crate::raytrace::Raytrace;
+use crate::raytrace::*;
 use crate::color::Color;
 
 use std::ops::*;
@@ -66,6 +66,27 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
         Some(distance)
     }
 
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        let offset = ray.origin - self.origin;
+
+        let a = ray.direction * ray.direction;
+        let b = ray.direction * offset * (2.0).into();
+        let c = offset * offset - self.radius * self.radius;
+
+        let discriminant = b * b - a * c * (4.0).into();
+
+        if discriminant < (0.0).into() {
+            return Some(Vec::new());
+        }
+
+        let root: T = discriminant.into().sqrt().into();
+
+        Some(vec![(
+            (b * (-1.0).into() - root) / (a * (2.0).into()),
+            (b * (-1.0).into() + root) / (a * (2.0).into()),
+        )])
+    }
+
     /// Reflects a ray along the normal.
     fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
         // Uses ThreadRng::Default() so is not re-seeded.
@@ -81,6 +102,41 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
         Some(Ray::new(pos, direction + random * <f64 as Into<T>>::into(self.roughness)))
     }
 
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(self.normal_at(&self.intersects_at(ray)?))
+    }
+
+    /// Spherical coordinates, with v from 0 at the bottom to 1 at the top.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let normal = self.normal(ray)?.to_f64();
+
+        Some((
+            0.5 + normal.x.atan2(normal.z) / (2.0 * std::f64::consts::PI),
+            1.0 - normal.y.clamp(-1.0, 1.0).acos() / std::f64::consts::PI,
+        ))
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        let normal = self.normal(ray)?.to_f64();
+        let radius: f64 = self.radius.into();
+
+        // Undefined at the poles, where u does not change position.
+        let sin_theta = (normal.x * normal.x + normal.z * normal.z).sqrt();
+        if sin_theta < 1e-9 {
+            return None;
+        }
+
+        Some((
+            Vec3::new(normal.z, 0.0, -normal.x) * (2.0 * std::f64::consts::PI * radius),
+            Vec3::new(-normal.y * normal.x / sin_theta, sin_theta, -normal.y * normal.z / sin_theta) * (std::f64::consts::PI * radius),
+        ))
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        let normal = self.normal(ray)?;
+        reflection_pdf(ray.direction.to_f64(), normal.to_f64(), self.roughness, direction.to_f64())
+    }
+
     fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
         let mut out = color;
 
@@ -113,6 +169,31 @@ mod tests {
             );
         }
 
+        #[test]
+        fn uv() {
+            let sphere = Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, Color::new(0.0, 0.0, 0.0));
+
+            assert_eq!(
+                sphere.uv(&Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0))),
+                Some((0.5, 0.5))
+            );
+            assert_eq!(
+                sphere.uv(&Ray::new(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0))),
+                Some((0.5, 1.0))
+            );
+
+            // Tangents match the change in position between nearby uv coordinates.
+            let ray = Ray::new(Vec3::new(5.0, 3.0, 2.0), Vec3::new(-5.0, -3.0, -2.0));
+            let nearby = Ray::new(Vec3::new(5.0, 3.0, 2.0), Vec3::new(-5.0, -3.0, -2.01));
+
+            let (du, dv) = sphere.uv_tangents(&ray).unwrap();
+            let (uv, nearby_uv) = (sphere.uv(&ray).unwrap(), sphere.uv(&nearby).unwrap());
+            let moved = sphere.intersects_at(&nearby).unwrap() - sphere.intersects_at(&ray).unwrap();
+            let estimate = du * (nearby_uv.0 - uv.0) + dv * (nearby_uv.1 - uv.1);
+
+            assert!((moved - estimate).length() < moved.length() * 0.01);
+        }
+
         #[test]
         fn normal() {
             assert_eq!(
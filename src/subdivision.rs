@@ -0,0 +1,345 @@
+use crate::vector::Vec3;
+
+use std::collections::HashMap;
+
+/// A subdivision scheme, refining a control mesh toward a smooth limit surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Subdivision {
+    /// Splits each triangle into 4, approaching a surface with continuous curvature away from irregular points.
+    ///
+    /// Faces which are not triangles are split into triangles first.
+    Loop,
+
+    /// Splits each face of n sides into n quads, approaching a bicubic B-spline surface away from irregular points.
+    CatmullClark,
+}
+
+/// A polygon mesh, with faces of any number of sides indexing into shared vertices.
+///
+/// Edges with one face, or more than two, are kept as sharp creases,
+/// and points where other than two creases meet are kept in place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControlMesh {
+    /// The positions of the vertices.
+    pub vertices: Vec<Vec3<f64>>,
+
+    /// The indices of the vertices around each face, wound consistently.
+    pub faces: Vec<Vec<usize>>,
+
+    /// The index of the original face which each face was subdivided from.
+    pub groups: Vec<usize>,
+}
+
+/// An edge between two vertices, and the faces either side of it.
+struct Edge {
+    /// The vertices at each end.
+    ends: (usize, usize),
+
+    /// The faces which share the edge.
+    faces: Vec<usize>,
+}
+
+/// The edges of a mesh, and those around each vertex.
+struct Adjacency {
+    /// Every edge, in the order it is first met around the faces.
+    edges: Vec<Edge>,
+
+    /// The index of each edge, by its vertices with the lowest first.
+    lookup: HashMap<(usize, usize), usize>,
+
+    /// The edges around each vertex.
+    vertex_edges: Vec<Vec<usize>>,
+
+    /// The faces around each vertex.
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Adjacency {
+    fn new(mesh: &ControlMesh) -> Self {
+        let mut adjacency = Adjacency {
+            edges: vec![],
+            lookup: HashMap::new(),
+            vertex_edges: vec![vec![]; mesh.vertices.len()],
+            vertex_faces: vec![vec![]; mesh.vertices.len()],
+        };
+
+        for (f, face) in mesh.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+
+                let index = *adjacency.lookup.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    adjacency.edges.push(Edge { ends: (a, b), faces: vec![] });
+                    adjacency.vertex_edges[a].push(adjacency.edges.len() - 1);
+                    adjacency.vertex_edges[b].push(adjacency.edges.len() - 1);
+
+                    adjacency.edges.len() - 1
+                });
+
+                adjacency.edges[index].faces.push(f);
+                adjacency.vertex_faces[a].push(f);
+            }
+        }
+
+        adjacency
+    }
+
+    /// The index of the edge between two vertices.
+    fn edge(&self, a: usize, b: usize) -> usize {
+        self.lookup[&(a.min(b), a.max(b))]
+    }
+
+    /// Whether the edge is a crease, without exactly two faces.
+    fn sharp(&self, edge: usize) -> bool {
+        self.edges[edge].faces.len() != 2
+    }
+
+    /// The vertex at the other end of an edge.
+    fn other(&self, edge: usize, vertex: usize) -> usize {
+        let (a, b) = self.edges[edge].ends;
+
+        if a == vertex { b } else { a }
+    }
+
+    /// The new position of a vertex along creases, if it lies on any.
+    ///
+    /// On exactly two creases, it is smoothed along them, otherwise it is kept in place.
+    fn crease(&self, mesh: &ControlMesh, vertex: usize) -> Option<Vec3<f64>> {
+        let creases: Vec<usize> = self.vertex_edges[vertex].iter().copied().filter(|&e| self.sharp(e)).collect();
+        let point = mesh.vertices[vertex];
+
+        match creases.len() {
+            0 => None,
+            2 => Some(point * 0.75 + (mesh.vertices[self.other(creases[0], vertex)] + mesh.vertices[self.other(creases[1], vertex)]) * 0.125),
+            _ => Some(point),
+        }
+    }
+}
+
+impl ControlMesh {
+    /// Default constructor, where each face is its own group.
+    pub fn new(vertices: Vec<Vec3<f64>>, faces: Vec<Vec<usize>>) -> Self {
+        let groups = (0..faces.len()).collect();
+
+        ControlMesh { vertices, faces, groups }
+    }
+
+    /// Subdivides the mesh a number of times.
+    ///
+    /// ```
+    /// # use rusttracing::subdivision::*;
+    /// # use rusttracing::vector::*;
+    /// let square = ControlMesh::new(
+    ///     vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)],
+    ///     vec![vec![0, 1, 2, 3]],
+    /// );
+    ///
+    /// assert_eq!(square.subdivide(Subdivision::CatmullClark, 2).faces.len(), 16);
+    /// ```
+    pub fn subdivide(self, scheme: Subdivision, levels: usize) -> Self {
+        let mut mesh = self;
+
+        for _level in 0..levels {
+            mesh = match scheme {
+                Subdivision::Loop => mesh.triangulate().loop_step(),
+                Subdivision::CatmullClark => mesh.catmull_clark_step(),
+            };
+        }
+
+        mesh
+    }
+
+    /// Splits each face into a fan of triangles.
+    pub fn triangulate(self) -> Self {
+        let mut out = ControlMesh { vertices: self.vertices, faces: vec![], groups: vec![] };
+
+        for (face, group) in self.faces.into_iter().zip(self.groups) {
+            for i in 1..face.len().saturating_sub(1) {
+                out.faces.push(vec![face[0], face[i], face[i + 1]]);
+                out.groups.push(group);
+            }
+        }
+
+        out
+    }
+
+    /// One step of Loop subdivision, of a mesh of triangles.
+    fn loop_step(&self) -> Self {
+        let adjacency = Adjacency::new(self);
+        let count = self.vertices.len();
+
+        let mut vertices: Vec<Vec3<f64>> = (0..count).map(|v| {
+            if let Some(point) = adjacency.crease(self, v) {
+                return point;
+            }
+
+            let neighbours = &adjacency.vertex_edges[v];
+            let n = neighbours.len();
+            if n == 0 {
+                return self.vertices[v];
+            }
+
+            let beta = if n == 3 { 3.0 / 16.0 } else { 3.0 / (8.0 * n as f64) };
+            let sum = neighbours.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, &e| sum + self.vertices[adjacency.other(e, v)]);
+
+            self.vertices[v] * (1.0 - n as f64 * beta) + sum * beta
+        }).collect();
+
+        for edge in &adjacency.edges {
+            let (a, b) = (self.vertices[edge.ends.0], self.vertices[edge.ends.1]);
+
+            vertices.push(if edge.faces.len() == 2 {
+                // The vertex of each triangle opposite to the edge.
+                let opposite = |f: usize| {
+                    let face = &self.faces[f];
+                    let v = *face.iter().find(|&&v| v != edge.ends.0 && v != edge.ends.1).unwrap_or(&face[0]);
+
+                    self.vertices[v]
+                };
+
+                (a + b) * 0.375 + (opposite(edge.faces[0]) + opposite(edge.faces[1])) * 0.125
+            } else {
+                (a + b) * 0.5
+            });
+        }
+
+        let mut out = ControlMesh { vertices, faces: vec![], groups: vec![] };
+
+        for (face, &group) in self.faces.iter().zip(&self.groups) {
+            let [a, b, c] = [face[0], face[1], face[2]];
+            let (ab, bc, ca) = (count + adjacency.edge(a, b), count + adjacency.edge(b, c), count + adjacency.edge(c, a));
+
+            for face in [vec![a, ab, ca], vec![ab, b, bc], vec![ca, bc, c], vec![ab, bc, ca]] {
+                out.faces.push(face);
+                out.groups.push(group);
+            }
+        }
+
+        out
+    }
+
+    /// One step of Catmull-Clark subdivision, of a mesh of any polygons.
+    fn catmull_clark_step(&self) -> Self {
+        let adjacency = Adjacency::new(self);
+        let count = self.vertices.len();
+        let edge_count = adjacency.edges.len();
+
+        let face_points: Vec<Vec3<f64>> = self.faces.iter().map(|face| {
+            face.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, &v| sum + self.vertices[v]) / face.len() as f64
+        }).collect();
+
+        let midpoint = |edge: &Edge| (self.vertices[edge.ends.0] + self.vertices[edge.ends.1]) * 0.5;
+
+        let mut vertices: Vec<Vec3<f64>> = (0..count).map(|v| {
+            if let Some(point) = adjacency.crease(self, v) {
+                return point;
+            }
+
+            let (edges, faces) = (&adjacency.vertex_edges[v], &adjacency.vertex_faces[v]);
+            if edges.is_empty() || faces.is_empty() {
+                return self.vertices[v];
+            }
+
+            let n = edges.len() as f64;
+            let average_face = faces.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, &f| sum + face_points[f]) / faces.len() as f64;
+            let average_edge = edges.iter().fold(Vec3::new(0.0, 0.0, 0.0), |sum, &e| sum + midpoint(&adjacency.edges[e])) / n;
+
+            (average_face + average_edge * 2.0 + self.vertices[v] * (n - 3.0)) / n
+        }).collect();
+
+        for edge in &adjacency.edges {
+            vertices.push(if edge.faces.len() == 2 {
+                (midpoint(edge) + (face_points[edge.faces[0]] + face_points[edge.faces[1]]) * 0.5) * 0.5
+            } else {
+                midpoint(edge)
+            });
+        }
+
+        vertices.extend(face_points);
+
+        let mut out = ControlMesh { vertices, faces: vec![], groups: vec![] };
+
+        for (f, (face, &group)) in self.faces.iter().zip(&self.groups).enumerate() {
+            let center = count + edge_count + f;
+            let k = face.len();
+
+            for i in 0..k {
+                let (previous, current, next) = (face[(i + k - 1) % k], face[i], face[(i + 1) % k]);
+
+                out.faces.push(vec![current, count + adjacency.edge(current, next), center, count + adjacency.edge(previous, current)]);
+                out.groups.push(group);
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catmull_clark() {
+        // A cube of side 2 about the origin, from 6 quads facing outwards.
+        let vertices = (0..8).map(|i| Vec3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+        )).collect();
+
+        let cube = ControlMesh::new(vertices, vec![
+            vec![0, 2, 3, 1], vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4], vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2], vec![1, 3, 7, 5],
+        ]);
+
+        let mesh = cube.subdivide(Subdivision::CatmullClark, 1);
+
+        assert_eq!((mesh.vertices.len(), mesh.faces.len()), (8 + 12 + 6, 24));
+
+        // Corners of a cube move to 5/9 of the way out, and edges to 3/4.
+        assert!((mesh.vertices[7] - Vec3::new(5.0, 5.0, 5.0) / 9.0).length() < 1e-12);
+        assert!(mesh.vertices[8..20].iter().all(|point| (point.length() - 0.75 * 2.0_f64.sqrt()).abs() < 1e-12));
+        assert!(mesh.vertices[20..].iter().all(|point| (point.length() - 1.0).abs() < 1e-12));
+
+        // Each face is refined within its own group.
+        assert_eq!(mesh.groups.iter().filter(|&&group| group == 3).count(), 4);
+
+        // The surface shrinks toward the limit, but not to a point.
+        let mesh = mesh.subdivide(Subdivision::CatmullClark, 3);
+        let radius = mesh.vertices.iter().map(|point| point.length()).fold(0.0, f64::max);
+        assert!(radius > 0.7 && radius < 1.0);
+    }
+
+    #[test]
+    fn loop_subdivision() {
+        // A cube of side 2 about the origin, from 6 quads facing outwards.
+        let vertices = (0..8).map(|i| Vec3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+        )).collect();
+
+        let cube = ControlMesh::new(vertices, vec![
+            vec![0, 2, 3, 1], vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4], vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2], vec![1, 3, 7, 5],
+        ]);
+
+        let mesh = cube.subdivide(Subdivision::Loop, 1);
+
+        // Split into 12 triangles, and then 48.
+        assert_eq!(mesh.faces.len(), 48);
+        assert_eq!(mesh.vertices.len(), 8 + 18);
+
+        // A flat open square stays flat, with its border smoothed inwards along itself.
+        let square = ControlMesh::new(
+            vec![Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)],
+            vec![vec![0, 1, 2], vec![0, 2, 3]],
+        ).subdivide(Subdivision::Loop, 2);
+
+        assert!(square.vertices.iter().all(|point| point.z == 0.0));
+        assert_eq!(square.vertices[0], Vec3::new(0.15625, 0.15625, 0.0));
+        assert!(square.vertices.iter().all(|point| (0.0..=1.0).contains(&point.x) && (0.0..=1.0).contains(&point.y)));
+    }
+}
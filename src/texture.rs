@@ -0,0 +1,212 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::Raytrace;
+use crate::color::Color;
+use crate::image::Image;
+
+use std::ops::*;
+
+/// The trait for colors which vary across a surface.
+pub trait Texture {
+    /// Gives the color at a point, from its uv coordinates on the surface and its position in space.
+    fn color_at(&self, uv: (f64, f64), position: Vec3<f64>) -> Color;
+}
+
+impl Texture for Color {
+    /// The same color everywhere.
+    fn color_at(&self, _uv: (f64, f64), _position: Vec3<f64>) -> Color {
+        *self
+    }
+}
+
+/// Alternating squares of two textures, in uv space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Checker<A, B> {
+    /// The texture of squares where the sum of the square's coordinates is even.
+    pub even: A,
+
+    /// The texture of the remaining squares.
+    pub odd: B,
+
+    /// The number of squares per unit of uv.
+    pub scale: f64,
+}
+
+impl<A: Texture, B: Texture> Texture for Checker<A, B> {
+    fn color_at(&self, uv: (f64, f64), position: Vec3<f64>) -> Color {
+        let square = (uv.0 * self.scale).floor() as i64 + (uv.1 * self.scale).floor() as i64;
+
+        if square % 2 == 0 {
+            self.even.color_at(uv, position)
+        } else {
+            self.odd.color_at(uv, position)
+        }
+    }
+}
+
+/// How an image texture treats uv coordinates outside of 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wrap {
+    /// The image tiles endlessly.
+    Repeat,
+
+    /// The edge pixels extend endlessly.
+    Clamp,
+}
+
+/// An image stretched over uv space, with bilinear filtering.
+///
+/// (0, 0) is the bottom left of the image, and (1, 1) is the top right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTexture {
+    /// The pixels of the image, as in [Image::data].
+    pub data: Vec<Vec<Color>>,
+
+    /// How uv coordinates outside of 0 to 1 are treated.
+    pub wrap: Wrap,
+}
+
+impl ImageTexture {
+    /// Creates a texture from an image.
+    pub fn new<const WIDTH: usize, const HEIGHT: usize>(image: &Image<WIDTH, HEIGHT>, wrap: Wrap) -> Self {
+        ImageTexture { data: image.data.clone(), wrap }
+    }
+
+    /// The pixel at a coordinate, which may be outside of the image.
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let width = self.data.len() as i64;
+        let height = self.data[0].len() as i64;
+
+        let (x, y) = match self.wrap {
+            Wrap::Repeat => (x.rem_euclid(width), y.rem_euclid(height)),
+            Wrap::Clamp => (x.clamp(0, width - 1), y.clamp(0, height - 1)),
+        };
+
+        self.data[x as usize][y as usize]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn color_at(&self, uv: (f64, f64), _position: Vec3<f64>) -> Color {
+        // Pixel centres lie on whole numbers.
+        let x = uv.0 * self.data.len() as f64 - 0.5;
+        let y = (1.0 - uv.1) * self.data[0].len() as f64 - 0.5;
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        self.pixel(x0, y0) * ((1.0 - tx) * (1.0 - ty))
+            + self.pixel(x0 + 1, y0) * (tx * (1.0 - ty))
+            + self.pixel(x0, y0 + 1) * ((1.0 - tx) * ty)
+            + self.pixel(x0 + 1, y0 + 1) * (tx * ty)
+    }
+}
+
+/// A texture given by a function of uv coordinates and position.
+///
+/// ```
+/// # use rusttracing::texture::*;
+/// # use rusttracing::color::*;
+/// # use rusttracing::vector::*;
+/// let stripes = Procedural(|uv: (f64, f64), _pos: Vec3<f64>| Color::new((uv.0 * 10.0).sin() * 0.5 + 0.5, 0.0, 0.0));
+/// assert_eq!(stripes.color_at((0.0, 0.0), Vec3::new(0.0, 0.0, 0.0)), Color::new(0.5, 0.0, 0.0));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Procedural<F>(pub F);
+
+impl<F: Fn((f64, f64), Vec3<f64>) -> Color> Texture for Procedural<F> {
+    fn color_at(&self, uv: (f64, f64), position: Vec3<f64>) -> Color {
+        (self.0)(uv, position)
+    }
+}
+
+/// A shape with a texture applied.
+///
+/// The texture tints the shape's own color, so a white shape shows the texture unchanged.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Textured<S, X> {
+    /// The shape, which must give uv coordinates for the texture to vary across it.
+    pub shape: S,
+
+    /// The texture.
+    pub texture: X,
+}
+
+impl<S, X> Textured<S, X> {
+    /// Default constructor.
+    pub fn new(shape: S, texture: X) -> Self {
+        Textured { shape, texture }
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>, S: Raytrace<T>, X: Texture> Raytrace<T> for Textured<S, X> {
+    fn intersects_along(&self, ray: &Ray<T>) -> Option<T> {
+        self.shape.intersects_along(ray)
+    }
+
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        self.shape.intervals(ray)
+    }
+
+    fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
+        self.shape.transmit(ray)
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        self.shape.normal(ray)
+    }
+
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        self.shape.uv(ray)
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.shape.uv_tangents(ray)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.shape.scatter_pdf(ray, direction)
+    }
+
+    fn recolor(&self, ray: &Ray<T>, color: Color) -> Color {
+        let uv = self.shape.uv(ray).unwrap_or((0.0, 0.0));
+        let position = self.shape.intersects_at(ray).map_or(Vec3::new(0.0, 0.0, 0.0), |x| x.to_f64());
+
+        self.shape.recolor(ray, color * self.texture.color_at(uv, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checker() {
+        let checker = Checker { even: Color::new(1.0, 1.0, 1.0), odd: Color::new(0.0, 0.0, 0.0), scale: 2.0 };
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+
+        assert_eq!(checker.color_at((0.1, 0.1), origin), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(checker.color_at((0.6, 0.1), origin), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(checker.color_at((0.6, 0.6), origin), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(checker.color_at((-0.1, 0.1), origin), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn image() {
+        let mut img: Image<2, 1> = Image::new();
+        img[1][0] = Color::new(1.0, 1.0, 1.0);
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+
+        let clamp = ImageTexture::new(&img, Wrap::Clamp);
+        assert_eq!(clamp.color_at((0.25, 0.5), origin), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(clamp.color_at((0.5, 0.5), origin), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(clamp.color_at((1.5, 0.5), origin), Color::new(1.0, 1.0, 1.0));
+
+        let repeat = ImageTexture::new(&img, Wrap::Repeat);
+        assert_eq!(repeat.color_at((0.0, 0.5), origin), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(repeat.color_at((1.25, 0.5), origin), Color::new(0.0, 0.0, 0.0));
+    }
+}
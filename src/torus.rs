@@ -0,0 +1,134 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::polynomial::roots;
+
+use std::ops::*;
+
+/// Torus.
+///
+/// A ring, of a tube swept around a circle about an axis.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Torus<T> {
+    /// The center of the ring.
+    pub origin: Vec3<T>,
+
+    /// Unit vector the ring is swept around.
+    pub axis: Vec3<T>,
+
+    /// Radius of the circle the tube is swept around.
+    pub major_radius: T,
+
+    /// Radius of the tube.
+    pub minor_radius: T,
+
+    /// Colour of the torus.
+    pub color: Color,
+
+    /// The uniformity of transmission.
+    pub roughness: f64,
+}
+
+impl<T> Torus<T> {
+    /// Default constructor.
+    pub fn new(origin: Vec3<T>, axis: Vec3<T>, major_radius: T, minor_radius: T, color: Color) -> Self {
+        Torus { origin, axis, major_radius, minor_radius, color, roughness: 1.0 }
+    }
+}
+
+impl<T: Copy + Into<f64>> Solid<T> for Torus<T> {
+    fn hits(&self, ray: &Ray<T>) -> Vec<(f64, Vec3<f64>)> {
+        let origin = self.origin.to_f64();
+        let axis = self.axis.to_f64();
+
+        // With no axis the ring has no plane, so nothing to hit.
+        if axis.length() == 0.0 {
+            return vec![];
+        }
+
+        let axis = axis.unit();
+        let (major, minor): (f64, f64) = (self.major_radius.into(), self.minor_radius.into());
+
+        let direction = ray.direction.to_f64();
+
+        // Solves from the point on the ray closest to the center, to keep the coefficients small.
+        let closest = ((origin - ray.origin.to_f64()) * direction) / (direction * direction);
+        let offset = ray.origin.to_f64() + direction * closest - origin;
+
+        let (along, speed) = (offset * axis, direction * axis);
+        let (across, drift) = (offset - axis * along, direction - axis * speed);
+
+        // (|p|² + R² - r²)² = 4R²|p across the axis|², expanded in powers of the distance.
+        let a = direction * direction;
+        let b = 2.0 * (offset * direction);
+        let c = offset * offset + major * major - minor * minor;
+        let k = 4.0 * major * major;
+
+        let coefficients = [
+            a * a,
+            2.0 * a * b,
+            b * b + 2.0 * a * c - k * (drift * drift),
+            2.0 * b * c - 2.0 * k * (across * drift),
+            c * c - k * (across * across),
+        ];
+
+        roots(&coefficients).into_iter().map(|t| {
+            let pos = offset + direction * t;
+            let radial = pos - axis * (pos * axis);
+
+            // The nearest point on the circle at the middle of the tube.
+            let ring = radial.unit() * major;
+
+            (t + closest, (pos - ring).unit())
+        }).collect()
+    }
+
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn roughness(&self) -> f64 {
+        self.roughness
+    }
+}
+
+solid_raytrace!(Torus);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects() {
+        let torus = Torus::new(Vec3::new(0.0, 0.0, 10.0), Vec3::new(0.0, 1.0, 0.0), 2.0_f64, 0.5, Color::new(1.0, 1.0, 1.0));
+
+        // Across the ring, through both sides of the tube.
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let intervals = torus.intervals(&ray).unwrap();
+        let expected = [(7.5, 8.5), (11.5, 12.5)];
+
+        assert_eq!(intervals.len(), 2);
+        for ((enter, exit), (a, b)) in intervals.into_iter().zip(expected) {
+            assert!((enter - a).abs() < 1e-9 && (exit - b).abs() < 1e-9);
+        }
+
+        assert!((torus.normal(&ray).unwrap() - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+
+        // Down through the hole.
+        let ray = Ray::new(Vec3::new(0.0, 5.0, 10.0), Vec3::new(0.0, -1.0, 0.0));
+        assert_eq!(torus.intersects_along(&ray), None);
+
+        // Down onto the top of the tube.
+        let ray = Ray::new(Vec3::new(2.0, 5.0, 10.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!((torus.intersects_along(&ray).unwrap() - 4.5).abs() < 1e-9);
+        assert!((torus.normal(&ray).unwrap() - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-9);
+
+        // With no axis, never hit.
+        let flat = Torus { axis: Vec3::new(0.0, 0.0, 0.0), ..torus };
+        assert_eq!(flat.intersects_along(&ray), None);
+        assert_eq!(flat.intervals(&ray), Some(vec![]));
+    }
+}
@@ -17,6 +17,16 @@ pub struct Tri<T> {
 
     /// The plane constructed from the 3 points.
     pub plane: Plane<T>,
+
+    /// The uv texture coordinates of each of the 3 points.
+    ///
+    /// Defaults to (0, 0), (1, 0), and (0, 1), giving the barycentric coordinates.
+    pub uvs: Vec3<(f64, f64)>,
+
+    /// The unit normals at each of the 3 points, interpolated across the tri for smooth shading.
+    ///
+    /// Defaults to None, where the normal of the plane is used throughout.
+    pub normals: Option<Vec3<Vec3<T>>>,
 }
 
 impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T>> Tri<T> {
@@ -25,8 +35,94 @@ impl<T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output
         Tri::<T> {
             bounds: Vec3::new(p1, p2, p3),
             plane: Plane::from_points(p1, p2, p3, color, roughness),
+            uvs: Vec3::new((0.0, 0.0), (1.0, 0.0), (0.0, 1.0)),
+            normals: None,
         }
     }
+
+    /// Sets the uv texture coordinates of each of the 3 points.
+    pub fn with_uvs(mut self, uv1: (f64, f64), uv2: (f64, f64), uv3: (f64, f64)) -> Self {
+        self.uvs = Vec3::new(uv1, uv2, uv3);
+
+        self
+    }
+
+    /// Sets the normals at each of the 3 points, for smooth shading.
+    pub fn with_normals(mut self, n1: Vec3<T>, n2: Vec3<T>, n3: Vec3<T>) -> Self {
+        self.normals = Some(Vec3::new(n1, n2, n3));
+
+        self
+    }
+
+    /// The weights of each of the 3 points at a position within the tri, which sum to 1.
+    pub fn barycentric(&self, pos: Vec3<T>) -> Vec3<f64> where T: Into<f64> {
+        let (p1, p2, p3) = (self.bounds.x.to_f64(), self.bounds.y.to_f64(), self.bounds.z.to_f64());
+        let pos = pos.to_f64();
+
+        // Areas are signed by the normal, so stay correct outside of the tri.
+        let normal = (p2 - p1).cross(&(p3 - p1));
+        let area = normal * normal;
+
+        let w2 = (pos - p1).cross(&(p3 - p1)) * normal / area;
+        let w3 = (p2 - p1).cross(&(pos - p1)) * normal / area;
+
+        Vec3::new(1.0 - w2 - w3, w2, w3)
+    }
+
+    /// The plane through the intersection which the ray is shaded by,
+    /// with the normal interpolated from the normals at each point.
+    ///
+    /// Falls back to the flat plane of the tri where the interpolated normal
+    /// would reflect the ray through the surface.
+    fn shading_plane(&self, ray: &Ray<T>) -> Option<Plane<T>> where T: PartialOrd + From<f64> + Into<f64> {
+        Some(self.shading_plane_at(ray, self.intersects_at(ray)?))
+    }
+
+    /// The plane which the ray is shaded by, at a position already known to be within the tri.
+    pub(crate) fn shading_plane_at(&self, ray: &Ray<T>, pos: Vec3<T>) -> Plane<T> where T: PartialOrd + From<f64> + Into<f64> {
+        let normals = match self.normals {
+            Some(x) => x,
+            None => return self.plane,
+        };
+
+        let weights = self.barycentric(pos);
+        let normal: Vec3<T> = (normals.x * T::from(weights.x) + normals.y * T::from(weights.y) + normals.z * T::from(weights.z)).unit();
+
+        let reflected = ray.direction - (normal * (normal * ray.direction) * T::from(2.0));
+        if (reflected * self.plane.normal) * (ray.direction * self.plane.normal) > 0.0.into() {
+            return self.plane;
+        }
+
+        Plane { normal, offset: pos * normal, ..self.plane }
+    }
+}
+
+/// Intersects the line of a ray with the tri between 3 points, by the Möller-Trumbore method.
+///
+/// Gives the distance along the line, which may be negative, and the weights u and v of the second and third points,
+/// or None if the line misses or runs alongside the tri.
+pub(crate) fn moller_trumbore(origin: Vec3<f64>, direction: Vec3<f64>, points: [Vec3<f64>; 3]) -> Option<(f64, f64, f64)> {
+    let [p1, p2, p3] = points;
+    let (e1, e2) = (p2 - p1, p3 - p1);
+
+    let p = direction.cross(&e2);
+    let determinant = e1 * p;
+
+    // Relative to the size of the tri and the ray, so small tris are not taken as parallel.
+    if determinant.abs() <= 1e-12 * e1.length() * e2.length() * direction.length() {
+        return None;
+    }
+
+    let offset = origin - p1;
+    let u = (offset * p) / determinant;
+    let q = offset.cross(&e1);
+    let v = (direction * q) / determinant;
+
+    if u < 0.0 || v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    Some(((e2 * q) / determinant, u, v))
 }
 
 impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for Tri<T> {
@@ -56,10 +152,110 @@ impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output
     }
 
     fn transmit(&self, ray: &Ray<T>) -> Option<Ray<T>> {
-        self.plane.transmit(ray)
+        self.shading_plane(ray)?.transmit(ray)
+    }
+
+    fn normal(&self, ray: &Ray<T>) -> Option<Vec3<T>> {
+        Some(self.shading_plane(ray)?.normal)
+    }
+
+    fn scatter_pdf(&self, ray: &Ray<T>, direction: &Vec3<T>) -> Option<f64> {
+        self.shading_plane(ray)?.scatter_pdf(ray, direction)
+    }
+
+    /// Interpolates the uv coordinates of each point.
+    fn uv(&self, ray: &Ray<T>) -> Option<(f64, f64)> {
+        let weights = self.barycentric(self.intersects_at(ray)?);
+
+        Some((
+            self.uvs.x.0 * weights.x + self.uvs.y.0 * weights.y + self.uvs.z.0 * weights.z,
+            self.uvs.x.1 * weights.x + self.uvs.y.1 * weights.y + self.uvs.z.1 * weights.z,
+        ))
+    }
+
+    fn uv_tangents(&self, ray: &Ray<T>) -> Option<(Vec3<f64>, Vec3<f64>)> {
+        self.intersects_along(ray)?;
+
+        let p1 = self.bounds.x.to_f64();
+        let (e1, e2) = (self.bounds.y.to_f64() - p1, self.bounds.z.to_f64() - p1);
+
+        let (du1, dv1) = (self.uvs.y.0 - self.uvs.x.0, self.uvs.y.1 - self.uvs.x.1);
+        let (du2, dv2) = (self.uvs.z.0 - self.uvs.x.0, self.uvs.z.1 - self.uvs.x.1);
+
+        // Undefined where the uv coordinates do not span an area.
+        let determinant = du1 * dv2 - du2 * dv1;
+        if determinant == 0.0 {
+            return None;
+        }
+
+        Some(((e1 * dv2 - e2 * dv1) / determinant, (e2 * du1 - e1 * du2) / determinant))
     }
 
     fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
         self.plane.recolor(_ray, color)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uv() {
+        let tri = Tri::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            1.0,
+        );
+
+        let ray = Ray::new(Vec3::new(0.5, 1.0, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(tri.uv(&ray), Some((0.25, 0.5)));
+
+        assert_eq!(tri.uv_tangents(&ray), Some((Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0))));
+
+        let tri = tri.with_uvs((1.0, 1.0), (1.0, 1.0), (0.0, 1.0));
+        assert_eq!(tri.uv(&ray), Some((0.5, 1.0)));
+        assert_eq!(tri.uv_tangents(&ray), None);
+    }
+
+    #[test]
+    fn normals() {
+        let tri = Tri::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            0.0,
+        );
+
+        let ray = Ray::new(Vec3::new(1.0, 0.0, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(tri.normal(&ray), Some(tri.plane.normal));
+
+        // Halfway between a normal tilted along x and one tilted against it.
+        let tilted = Vec3::new(0.6, 0.0, -0.8);
+        let tri = tri.with_normals(tilted, Vec3::new(-0.6, 0.0, -0.8), tilted);
+
+        let normal = tri.normal(&ray).unwrap();
+        assert!((normal - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-9);
+
+        // The reflection leaves from the intersection.
+        let ray = Ray::new(Vec3::new(0.5, 0.5, -1.0), Vec3::new(0.0, 0.0, 1.0));
+        let reflected = tri.transmit(&ray).unwrap();
+        assert!((reflected.origin - Vec3::new(0.5, 0.5, 0.0)).length() < 1e-9);
+        assert!(reflected.direction.z < 0.0);
+    }
+
+    #[test]
+    fn moller_trumbore_scale() {
+        let points = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(1e-7, 0.0, 0.0), Vec3::new(0.0, 1e-7, 0.0)];
+
+        // A tiny tri is still hit, with the same weights as a large one.
+        let (distance, u, v) = moller_trumbore(Vec3::new(2.5e-8, 5e-8, -1.0), Vec3::new(0.0, 0.0, 1.0), points).unwrap();
+        assert!((distance - 1.0).abs() < 1e-9 && (u - 0.25).abs() < 1e-9 && (v - 0.5).abs() < 1e-9);
+
+        // But a ray alongside it is not.
+        assert_eq!(moller_trumbore(Vec3::new(-1.0, 2.5e-8, 0.0), Vec3::new(1.0, 0.0, 0.0), points), None);
+    }
+}
@@ -46,6 +46,25 @@ impl<T: From<f64> + Into<f64> + Add<Output = T>> Vec3<T> {
     }
 }
 
+impl<T: Copy + Into<f64>> Vec3<T> {
+    /// Converts each part to an f64.
+    ///
+    /// ```
+    /// # use rusttracing::vector::*;
+    /// assert_eq!(Vec3::new(1.0f32, 2.0, 3.0).to_f64(), Vec3::new(1.0f64, 2.0, 3.0));
+    /// ```
+    pub fn to_f64(&self) -> Vec3<f64> {
+        Vec3::new(self.x.into(), self.y.into(), self.z.into())
+    }
+}
+
+impl<T: From<f64>> Vec3<T> {
+    /// Converts each part from an f64.
+    pub fn from_f64(vec: Vec3<f64>) -> Self {
+        Vec3::new(vec.x.into(), vec.y.into(), vec.z.into())
+    }
+}
+
 /// The error type for a mismatch of sizes between the object and the Vec3.
 #[derive(Debug)]
 pub struct SizeError;
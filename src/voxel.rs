@@ -0,0 +1,340 @@
+use crate::vector::Vec3;
+use crate::ray::Ray;
+use crate::raytrace::*;
+use crate::color::Color;
+use crate::aabb::Aabb;
+use crate::medium::Medium;
+
+use std::ops::*;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug)]
+/// Invalid or unsupported voxel data.
+pub struct VoxelError;
+impl fmt::Display for VoxelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid voxel data, please give the size of the grid followed by a density for every voxel")
+    }
+}
+
+/// A dense grid of densities, filling a box.
+///
+/// Each density is at the centre of its voxel, and densities between are interpolated trilinearly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoxelGrid {
+    /// The box filled by the grid.
+    pub bounds: Aabb<f64>,
+
+    /// The number of voxels along x, y and z.
+    size: (usize, usize, usize),
+
+    /// The density of every voxel, along x, then y, then z.
+    densities: Vec<f64>,
+}
+
+impl VoxelGrid {
+    /// Creates a grid from the density of every voxel, along x, then y, then z.
+    ///
+    /// # Errors
+    /// Will error if the number of densities does not match the size, any of the sizes are 0, or any densities are negative.
+    pub fn new(bounds: Aabb<f64>, size: (usize, usize, usize), densities: Vec<f64>) -> Result<Self, VoxelError> {
+        if densities.len() != Self::count(size)? || densities.iter().any(|x| x.is_nan() || *x < 0.0) {
+            return Err(VoxelError);
+        }
+
+        Ok(VoxelGrid { bounds, size, densities })
+    }
+
+    /// The number of voxels in a grid of a size.
+    ///
+    /// # Errors
+    /// Will error if any of the sizes are 0, or the number of voxels overflows.
+    fn count(size: (usize, usize, usize)) -> Result<usize, VoxelError> {
+        size.0.checked_mul(size.1).and_then(|x| x.checked_mul(size.2)).filter(|&x| x > 0).ok_or(VoxelError)
+    }
+
+    /// Creates a grid from a function of position, sampled at the centre of every voxel,
+    /// such as noise for smoke and clouds.
+    ///
+    /// Negative densities are taken as 0.
+    ///
+    /// # Errors
+    /// Will error if any of the sizes are 0, or the number of voxels overflows.
+    ///
+    /// ```
+    /// # use rusttracing::voxel::*;
+    /// # use rusttracing::aabb::*;
+    /// # use rusttracing::vector::*;
+    /// # use rusttracing::noise::*;
+    /// let noise = Perlin::new(7);
+    /// let bounds = Aabb::new(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+    ///
+    /// let cloud = VoxelGrid::from_fn(bounds, (32, 16, 32), |p| noise.fbm(p * 3.0, 4) * 2.0).unwrap();
+    ///
+    /// assert_eq!(cloud.size(), (32, 16, 32));
+    /// ```
+    pub fn from_fn(bounds: Aabb<f64>, size: (usize, usize, usize), density: impl Fn(Vec3<f64>) -> f64) -> Result<Self, VoxelError> {
+        let mut densities = Vec::with_capacity(Self::count(size)?);
+        let extent = bounds.size();
+
+        for z in 0..size.2 {
+            for y in 0..size.1 {
+                for x in 0..size.0 {
+                    let point = bounds.min + Vec3::new(
+                        extent.x * (x as f64 + 0.5) / size.0 as f64,
+                        extent.y * (y as f64 + 0.5) / size.1 as f64,
+                        extent.z * (z as f64 + 0.5) / size.2 as f64,
+                    );
+
+                    densities.push(density(point).max(0.0));
+                }
+            }
+        }
+
+        Self::new(bounds, size, densities)
+    }
+
+    /// Reads a grid in a simple text format.
+    ///
+    /// The number of voxels along x, y and z, then the density of every voxel, along x, then y, then z.
+    /// Lines starting with # are ignored.
+    ///
+    /// # Errors
+    /// Will error on missing or invalid numbers.
+    ///
+    /// ```
+    /// # use rusttracing::voxel::*;
+    /// # use rusttracing::aabb::*;
+    /// # use rusttracing::vector::*;
+    /// let bounds = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+    /// let grid = VoxelGrid::from_text(bounds, "# A single voxel\n1 1 1\n0.5").unwrap();
+    ///
+    /// assert_eq!(grid.density(Vec3::new(0.3, 0.6, 0.9)), 0.5);
+    /// assert!(VoxelGrid::from_text(bounds, "2 1 1\n0.5").is_err());
+    /// ```
+    pub fn from_text(bounds: Aabb<f64>, text: &str) -> Result<Self, VoxelError> {
+        let mut tokens = text.lines().filter(|line| !line.trim_start().starts_with('#')).flat_map(|line| line.split_whitespace());
+
+        let mut size = [0; 3];
+        for axis in size.iter_mut() {
+            *axis = tokens.next().ok_or(VoxelError)?.parse().map_err(|_| VoxelError)?;
+        }
+
+        let densities = tokens.map(|x| x.parse().map_err(|_| VoxelError)).collect::<Result<Vec<f64>, VoxelError>>()?;
+
+        VoxelGrid::new(bounds, (size[0], size[1], size[2]), densities)
+    }
+
+    /// Reads a grid of raw 8 bit densities, as used by many volume datasets,
+    /// where 255 is a density of 1.
+    ///
+    /// # Errors
+    /// Will error if the number of bytes does not match the size.
+    pub fn from_raw(bounds: Aabb<f64>, size: (usize, usize, usize), bytes: &[u8]) -> Result<Self, VoxelError> {
+        VoxelGrid::new(bounds, size, bytes.iter().map(|&x| x as f64 / 255.0).collect())
+    }
+
+    /// The number of voxels along x, y and z.
+    pub fn size(&self) -> (usize, usize, usize) {
+        self.size
+    }
+
+    /// The density of a voxel.
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.densities[x + self.size.0 * (y + self.size.1 * z)]
+    }
+
+    /// The density at a point, interpolated trilinearly between the centres of the voxels around it.
+    ///
+    /// Points outside of the grid have no density.
+    pub fn density(&self, point: Vec3<f64>) -> f64 {
+        if !self.bounds.contains(point) {
+            return 0.0;
+        }
+
+        let extent = self.bounds.size();
+        let local = point - self.bounds.min;
+
+        // The voxels below and above along an axis, and the weight of the one above.
+        let axis = |along: f64, length: f64, count: usize| {
+            let position = (along / length * count as f64 - 0.5).clamp(0.0, (count - 1) as f64);
+            let below = (position.floor() as usize).min(count - 1);
+
+            (below, (below + 1).min(count - 1), position - below as f64)
+        };
+
+        let (x0, x1, tx) = axis(local.x, extent.x, self.size.0);
+        let (y0, y1, ty) = axis(local.y, extent.y, self.size.1);
+        let (z0, z1, tz) = axis(local.z, extent.z, self.size.2);
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+
+        let along_x = |y: usize, z: usize| lerp(self.voxel(x0, y, z), self.voxel(x1, y, z), tx);
+
+        lerp(
+            lerp(along_x(y0, z0), along_x(y1, z0), ty),
+            lerp(along_x(y0, z1), along_x(y1, z1), ty),
+            tz,
+        )
+    }
+
+    /// The highest density within the grid.
+    pub fn max(&self) -> f64 {
+        self.densities.iter().fold(0.0, |max, &x| max.max(x))
+    }
+
+    /// The smallest box containing every point with any density.
+    ///
+    /// Returns None if no voxel has any density.
+    pub fn occupied(&self) -> Option<Aabb<f64>> {
+        let mut range: Option<([usize; 3], [usize; 3])> = None;
+
+        for z in 0..self.size.2 {
+            for y in 0..self.size.1 {
+                for x in 0..self.size.0 {
+                    if self.voxel(x, y, z) > 0.0 {
+                        let (low, high) = range.get_or_insert(([x, y, z], [x, y, z]));
+
+                        for (i, index) in [x, y, z].into_iter().enumerate() {
+                            low[i] = low[i].min(index);
+                            high[i] = high[i].max(index);
+                        }
+                    }
+                }
+            }
+        }
+
+        let (low, high) = range?;
+        let extent = self.bounds.size();
+
+        // Density fades to the centre of the next voxel, or is held to the edge of the grid.
+        let edge = |index: f64, count: usize, length: f64| index.clamp(0.0, count as f64) / count as f64 * length;
+
+        Some(Aabb::new(
+            self.bounds.min + Vec3::new(
+                edge(low[0] as f64 - 0.5, self.size.0, extent.x),
+                edge(low[1] as f64 - 0.5, self.size.1, extent.y),
+                edge(low[2] as f64 - 0.5, self.size.2, extent.z),
+            ),
+            self.bounds.min + Vec3::new(
+                edge(high[0] as f64 + 1.5, self.size.0, extent.x),
+                edge(high[1] as f64 + 1.5, self.size.1, extent.y),
+                edge(high[2] as f64 + 1.5, self.size.2, extent.z),
+            ),
+        ))
+    }
+}
+
+/// Voxel volume.
+///
+/// A participating medium whose density varies through a [VoxelGrid], such as smoke or a cloud.
+///
+/// Rendered with delta tracking against the highest density of the grid,
+/// and only traced within the box around the voxels with any density.
+///
+/// Implements the Raytrace trait.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoxelVolume {
+    /// The densities through the volume.
+    grid: VoxelGrid,
+
+    /// The medium at a density of 1.
+    pub medium: Medium,
+
+    /// The highest density within the grid.
+    max: f64,
+
+    /// The box around the voxels with any density.
+    occupied: Option<Aabb<f64>>,
+}
+
+impl VoxelVolume {
+    /// Default constructor.
+    pub fn new(grid: VoxelGrid, medium: Medium) -> Self {
+        VoxelVolume { max: grid.max(), occupied: grid.occupied(), grid, medium }
+    }
+
+    /// The densities through the volume.
+    pub fn grid(&self) -> &VoxelGrid {
+        &self.grid
+    }
+}
+
+impl<T: PartialOrd + From<f64> + Into<f64> + Copy + Add<Output = T> + Mul<Output = T> + Div<Output = T> + Sub<Output = T>> Raytrace<T> for VoxelVolume {
+    /// Never hit, as light is only scattered within the medium.
+    fn intersects_along(&self, _ray: &Ray<T>) -> Option<T> {
+        None
+    }
+
+    /// The interval within the box around the voxels with any density.
+    fn intervals(&self, ray: &Ray<T>) -> Option<Vec<(T, T)>> {
+        let Some(occupied) = self.occupied else {
+            return Some(Vec::new());
+        };
+
+        let ray = Ray { origin: ray.origin.to_f64(), direction: ray.direction.to_f64(), time: ray.time };
+
+        Some(occupied.slab(&ray).into_iter().map(|(near, far)| (near.into(), far.into())).collect())
+    }
+
+    fn transmit(&self, _ray: &Ray<T>) -> Option<Ray<T>> {
+        None
+    }
+
+    /// The medium at the highest density of the grid.
+    fn medium(&self) -> Option<Medium> {
+        Some(Medium {
+            absorption: self.medium.absorption * self.max,
+            scattering: self.medium.scattering * self.max,
+            ..self.medium
+        })
+    }
+
+    /// The density at the point, as a fraction of the highest density of the grid.
+    fn density(&self, point: &Vec3<T>, _time: f64) -> Option<f64> {
+        if self.max <= 0.0 {
+            return Some(0.0);
+        }
+
+        Some(self.grid.density(point.to_f64()) / self.max)
+    }
+
+    fn recolor(&self, _ray: &Ray<T>, color: Color) -> Color {
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density() {
+        let bounds = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+        let grid = VoxelGrid::new(bounds, (2, 1, 1), vec![0.0, 1.0]).unwrap();
+
+        // Held to the edges, and interpolated between the centres of the voxels.
+        assert_eq!(grid.density(Vec3::new(0.2, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.density(Vec3::new(1.0, 0.1, 0.9)), 0.5);
+        assert_eq!(grid.density(Vec3::new(1.9, 0.5, 0.5)), 1.0);
+        assert_eq!(grid.density(Vec3::new(2.1, 0.5, 0.5)), 0.0);
+
+        assert!(VoxelGrid::new(bounds, (2, 1, 1), vec![0.0, -1.0]).is_err());
+        assert!(VoxelGrid::from_fn(bounds, (2, 0, 1), |_p| 1.0).is_err());
+        assert!(VoxelGrid::from_fn(bounds, (usize::MAX, 2, 1), |_p| 1.0).is_err());
+        assert!(VoxelGrid::from_text(bounds, "18446744073709551615 2 1\n0.5").is_err());
+        assert_eq!(VoxelGrid::from_raw(bounds, (2, 1, 1), &[0, 255]).unwrap(), grid);
+
+        // Density fades from the first voxel with any, to the centre of the empty voxel before it.
+        let sparse = VoxelGrid::new(Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(4.0, 1.0, 1.0)), (4, 1, 1), vec![0.0, 0.0, 2.0, 0.0]).unwrap();
+        assert_eq!(sparse.occupied(), Some(Aabb::new(Vec3::new(1.5, 0.0, 0.0), Vec3::new(3.5, 1.0, 1.0))));
+
+        let volume = VoxelVolume::new(sparse, Medium::new(0.5, 1.0, Color::new(1.0, 1.0, 1.0)));
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0));
+
+        assert_eq!(volume.intervals(&ray), Some(vec![(1.5, 3.5)]));
+        assert_eq!(Raytrace::<f64>::medium(&volume).unwrap().extinction(), 3.0);
+        assert_eq!(volume.density(&Vec3::new(2.5, 0.5, 0.5), 0.0), Some(1.0));
+        assert_eq!(volume.density(&Vec3::new(2.0, 0.5, 0.5), 0.0), Some(0.5));
+    }
+}